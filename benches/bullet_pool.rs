@@ -0,0 +1,116 @@
+//! Frame time with 500 bullets in flight while 50 are fired and 50 taken out
+//! of flight every frame, once through the `BulletPool` and once spawning and
+//! despawning every bullet as the game did before it. Run with
+//! `cargo bench --bench bullet_pool`.
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use galaga_bevy::{
+    bullet::{checkout_bullet, release_bullet, BulletPlugin, BulletPool},
+    game_assets::GameAssets,
+    Bullet, BulletOwner, ColorsPalette, Velocity,
+};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Bullets kept in flight
+const ACTIVE_BULLETS: usize = 500;
+
+/// Bullets fired, and as many taken out of flight, every frame
+const SHOTS_PER_FRAME: usize = 50;
+
+/// Frames run before timing starts, filling the screen and the pool
+const WARMUP_FRAMES: u32 = 100;
+
+/// Frames timed
+const TIMED_FRAMES: u32 = 2000;
+
+/// Bullets in flight, oldest first, and whether they come from the pool
+#[derive(Resource)]
+struct Churn {
+    pooled: bool,
+    in_flight: VecDeque<Entity>,
+}
+
+fn churn_bullets(
+    mut commands: Commands,
+    mut churn: ResMut<Churn>,
+    mut bullet_pool: ResMut<BulletPool>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+) {
+    if churn.in_flight.len() >= ACTIVE_BULLETS {
+        for _ in 0..SHOTS_PER_FRAME {
+            let Some(bullet) = churn.in_flight.pop_front() else {
+                break;
+            };
+            if churn.pooled {
+                release_bullet(&mut commands, bullet);
+            } else {
+                commands.entity(bullet).despawn();
+            }
+        }
+    }
+
+    for shot in 0..SHOTS_PER_FRAME {
+        let translation = Vec3::new(shot as f32 * 10.0 - 250.0, -300.0, 0.0);
+        let velocity = Vec2::new(0.0, 300.0);
+        let bullet = if churn.pooled {
+            checkout_bullet(
+                &mut commands,
+                &mut bullet_pool,
+                BulletOwner::Player,
+                translation,
+                velocity,
+                &color_palette,
+                &game_assets,
+            )
+        } else {
+            commands
+                .spawn((
+                    Bullet { owner: BulletOwner::Player },
+                    Velocity(velocity),
+                    GameAssets::sprite(&game_assets.bullet, color_palette.bullet_color(BulletOwner::Player)),
+                    Transform {
+                        translation,
+                        scale: BulletOwner::Player.bullet_scale(),
+                        ..Default::default()
+                    },
+                ))
+                .id()
+        };
+        churn.in_flight.push_back(bullet);
+    }
+}
+
+/// Average time of a frame, one fixed step each
+fn frame_time(pooled: bool) -> Duration {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, BulletPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / 60.0)))
+        .init_resource::<ColorsPalette>()
+        .init_resource::<GameAssets>()
+        .insert_resource(Churn {
+            pooled,
+            in_flight: VecDeque::new(),
+        })
+        .add_systems(Update, churn_bullets);
+
+    for _ in 0..WARMUP_FRAMES {
+        app.update();
+    }
+    let start = Instant::now();
+    for _ in 0..TIMED_FRAMES {
+        app.update();
+    }
+    start.elapsed() / TIMED_FRAMES
+}
+
+fn main() {
+    let spawned = frame_time(false);
+    let pooled = frame_time(true);
+    println!("{} bullets in flight, {} replaced every frame", ACTIVE_BULLETS, SHOTS_PER_FRAME);
+    println!("spawn and despawn: {:>8.1} µs per frame", spawned.as_secs_f64() * 1e6);
+    println!("bullet pool:       {:>8.1} µs per frame", pooled.as_secs_f64() * 1e6);
+}
@@ -0,0 +1,297 @@
+use bevy::prelude::*;
+use crate::{
+    game_assets::GameAssets,
+    game_state::{GameState, MenuScreen, PauseState},
+    player_input::{handle_player_input, handle_touch_input, PlayerSpeed, TouchControls},
+    Bullet, BulletOwner, ColorsPalette, Fly, InputSet, Player,
+};
+
+/// Seconds the title screen has to sit untouched before the demo starts
+const IDLE_BEFORE_DEMO: f32 = 15.0;
+
+/// Seconds the demo banner stays on, and then off, while flashing
+const BANNER_FLASH_INTERVAL: f32 = 0.5;
+
+/// Enemy bullets closer above the ship than this are dodged
+const DODGE_HEIGHT: f32 = 150.0;
+
+/// Enemy bullets closer to the ship's column than this are dodged
+const DODGE_WIDTH: f32 = 40.0;
+
+/// How close to a fly's column the demo ship has to get before it stops steering
+const AIM_TOLERANCE: f32 = 8.0;
+
+/// Attract mode: after the title screen sits idle for a while, the game plays
+/// itself until any input brings the menu back
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuIdleTimer>()
+            .add_systems(OnEnter(MenuScreen::Main), reset_idle_timer)
+            .add_systems(OnEnter(GameState::Menu), end_demo)
+            .add_systems(OnEnter(GameState::Playing), spawn_demo_banner.run_if(resource_exists::<DemoRun>))
+            .add_systems(OnEnter(GameState::GameOver), leave_demo_on_game_over.run_if(resource_exists::<DemoRun>))
+            .add_systems(Update, start_demo_when_idle.run_if(in_state(MenuScreen::Main)))
+            .add_systems(
+                Update,
+                (
+                    leave_demo_on_input,
+                    flash_demo_banner,
+                    drive_demo_player
+                        .after(handle_touch_input)
+                        .before(handle_player_input)
+                        .in_set(InputSet)
+                        .run_if(in_state(PauseState::Running)),
+                )
+                    .run_if(in_state(GameState::Playing).and(resource_exists::<DemoRun>)),
+            );
+    }
+}
+
+/// Present while the run in progress is the demo. Demo runs never count
+/// toward the high score.
+#[derive(Resource)]
+pub struct DemoRun;
+
+/// Time the title screen has been left alone
+#[derive(Resource)]
+struct MenuIdleTimer(Timer);
+
+impl Default for MenuIdleTimer {
+    fn default() -> Self {
+        MenuIdleTimer(Timer::from_seconds(IDLE_BEFORE_DEMO, TimerMode::Once))
+    }
+}
+
+/// Marks the flashing banner shown over the demo
+#[derive(Component)]
+struct DemoBanner;
+
+/// Whether any key, mouse button, gamepad button or finger is down
+fn any_input(
+    keyboard_input: &ButtonInput<KeyCode>,
+    mouse_input: &ButtonInput<MouseButton>,
+    gamepads: &Query<&Gamepad>,
+    touches: &Touches,
+) -> bool {
+    keyboard_input.get_pressed().next().is_some()
+        || mouse_input.get_pressed().next().is_some()
+        || gamepads.iter().any(|gamepad| gamepad.get_pressed().next().is_some())
+        || touches.iter().next().is_some()
+}
+
+/// Starts counting the idle time again whenever the main menu page is shown
+fn reset_idle_timer(mut idle_timer: ResMut<MenuIdleTimer>) {
+    idle_timer.0.reset();
+}
+
+/// Starts the demo once the main menu page was left alone for `IDLE_BEFORE_DEMO`
+/// of real time. Any input restarts the count.
+#[allow(clippy::too_many_arguments)]
+fn start_demo_when_idle(
+    mut commands: Commands,
+    mut idle_timer: ResMut<MenuIdleTimer>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    touches: Res<Touches>,
+    time: Res<Time<Real>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if any_input(&keyboard_input, &mouse_input, &gamepads, &touches) {
+        idle_timer.0.reset();
+        return;
+    }
+
+    if idle_timer.0.tick(time.delta()).just_finished() {
+        info!("Menu idle, starting the demo");
+        commands.insert_resource(DemoRun);
+        next_state.set(GameState::Playing);
+    }
+}
+
+/// Goes back to the menu as soon as the player touches anything during the demo
+fn leave_demo_on_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    touches: Res<Touches>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if any_input(&keyboard_input, &mouse_input, &gamepads, &touches) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+/// A demo that runs out of lives goes back to the menu instead of the game over screen
+fn leave_demo_on_game_over(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Menu);
+}
+
+/// Back on the menu the next run is a real one again
+fn end_demo(mut commands: Commands) {
+    commands.remove_resource::<DemoRun>();
+}
+
+/// Shows "DEMO — PRESS ENTER" near the bottom of the screen for the whole demo
+fn spawn_demo_banner(mut commands: Commands, game_assets: Res<GameAssets>, color_palette: Res<ColorsPalette>) {
+    commands
+        .spawn((
+            StateScoped(GameState::Playing),
+            Node {
+                width: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                DemoBanner,
+                Text::new("DEMO — PRESS ENTER"),
+                TextFont {
+                    font: game_assets.font.clone(),
+                    font_size: 32.0,
+                    ..Default::default()
+                },
+                TextColor(color_palette.player_color),
+            ));
+        });
+}
+
+/// Blinks the demo banner every `BANNER_FLASH_INTERVAL`
+fn flash_demo_banner(mut banner_query: Query<&mut Visibility, With<DemoBanner>>, time: Res<Time<Real>>) {
+    let flashes = (time.elapsed_secs() / BANNER_FLASH_INTERVAL) as u32;
+    let target = if flashes % 2 == 0 { Visibility::Inherited } else { Visibility::Hidden };
+
+    for mut visibility in banner_query.iter_mut() {
+        if *visibility != target {
+            *visibility = target;
+        }
+    }
+}
+
+/// Plays the demo: writes the movement and fire a touch would, so
+/// `handle_player_input` moves and shoots the ship exactly as for a player.
+/// It fires whenever it can, the cooldown and bullet cap still apply.
+fn drive_demo_player(
+    player_query: Query<&Transform, With<Player>>,
+    fly_query: Query<&Transform, With<Fly>>,
+    bullet_query: Query<(&Bullet, &Transform)>,
+    player_speed: Res<PlayerSpeed>,
+    time: Res<Time>,
+    mut touch_controls: ResMut<TouchControls>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return; // No ship while waiting to respawn
+    };
+
+    let steering = demo_steering(
+        player_transform.translation.truncate(),
+        fly_query.iter().map(|transform| transform.translation.truncate()),
+        bullet_query
+            .iter()
+            .filter(|(bullet, _)| bullet.owner == BulletOwner::Enemy)
+            .map(|(_, transform)| transform.translation.truncate()),
+    );
+
+    touch_controls.move_x = steering * player_speed.0 * time.delta_secs();
+    touch_controls.fire = true;
+}
+
+/// Which way the demo ship steers, -1 (left) to 1 (right): away from the
+/// nearest enemy bullet about to come down on it, otherwise toward the column
+/// of the nearest fly
+fn demo_steering(
+    ship: Vec2,
+    flies: impl Iterator<Item = Vec2>,
+    enemy_bullets: impl Iterator<Item = Vec2>,
+) -> f32 {
+    let nearest = |a: &Vec2, b: &Vec2| a.distance_squared(ship).total_cmp(&b.distance_squared(ship));
+
+    let threat = enemy_bullets
+        .filter(|bullet| {
+            bullet.y >= ship.y && bullet.y - ship.y < DODGE_HEIGHT && (bullet.x - ship.x).abs() < DODGE_WIDTH
+        })
+        .min_by(nearest);
+    if let Some(bullet) = threat {
+        return if bullet.x > ship.x { -1.0 } else { 1.0 };
+    }
+
+    match flies.min_by(nearest) {
+        Some(fly) if (fly.x - ship.x).abs() > AIM_TOLERANCE => (fly.x - ship.x).signum(),
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+    use std::time::Duration;
+
+    #[test]
+    fn demo_ship_dodges_bullets_before_chasing_flies() {
+        let ship = Vec2::new(0.0, -250.0);
+        let flies = || [Vec2::new(-200.0, 100.0), Vec2::new(300.0, 200.0)].into_iter();
+
+        assert_eq!(demo_steering(ship, flies(), std::iter::empty()), -1.0);
+        assert_eq!(demo_steering(ship, std::iter::empty(), std::iter::empty()), 0.0);
+        // Right above and slightly to the left, so it moves right
+        assert_eq!(demo_steering(ship, flies(), [Vec2::new(-10.0, -180.0)].into_iter()), 1.0);
+        // Far above the ship, no reason to dodge yet
+        assert_eq!(demo_steering(ship, flies(), [Vec2::new(-10.0, 100.0)].into_iter()), -1.0);
+    }
+
+    fn menu_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(4)))
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<Touches>()
+            .init_resource::<MenuIdleTimer>()
+            .init_state::<GameState>()
+            .add_sub_state::<MenuScreen>()
+            .add_systems(OnEnter(GameState::Menu), end_demo)
+            .add_systems(Update, start_demo_when_idle.run_if(in_state(MenuScreen::Main)))
+            .add_systems(
+                Update,
+                leave_demo_on_input.run_if(in_state(GameState::Playing).and(resource_exists::<DemoRun>)),
+            );
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Menu);
+        app
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world().resource::<State<GameState>>().get()
+    }
+
+    #[test]
+    fn idle_menu_starts_the_demo_and_input_ends_it() {
+        let mut app = menu_app();
+        for _ in 0..4 {
+            app.update(); // 12 seconds, the first update has no elapsed time
+        }
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::ArrowDown);
+        app.update();
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().release(KeyCode::ArrowDown);
+        for _ in 0..3 {
+            app.update(); // Another 12 seconds after the key press reset the count
+        }
+        assert_eq!(state(&app), GameState::Menu);
+
+        app.update();
+        app.update();
+        assert_eq!(state(&app), GameState::Playing);
+        assert!(app.world().contains_resource::<DemoRun>());
+
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::Space);
+        app.update();
+        app.update();
+        assert_eq!(state(&app), GameState::Menu);
+        assert!(!app.world().contains_resource::<DemoRun>());
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{music::BackgroundMusic, sound::SoundEffect};
+
+/// How much a single +/- press changes the master volume
+pub const VOLUME_STEP: f32 = 0.1;
+
+/// Volume and mute controls, saved to the user config dir whenever they change
+pub struct AudioSettingsPlugin;
+
+impl Plugin for AudioSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings_file = AudioSettingsFile(settings_path());
+        let settings = settings_file
+            .0
+            .as_deref()
+            .map(AudioSettings::load)
+            .unwrap_or_default();
+
+        app.insert_resource(settings)
+            .insert_resource(settings_file)
+            .add_systems(
+                Update,
+                (
+                    adjust_master_volume,
+                    toggle_mute,
+                    apply_volume_to_sinks.after(adjust_master_volume).after(toggle_mute),
+                    save_audio_settings.after(adjust_master_volume).after(toggle_mute),
+                ),
+            );
+    }
+}
+
+/// Volume levels in `[0, 1]`. Channel volumes are scaled by `master`, and
+/// `muted` silences everything without losing the levels.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 1.0,
+            music: 0.6,
+            sfx: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Volume the background music should play at right now
+    pub fn music_volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master * self.music
+        }
+    }
+
+    /// Volume sound effects should play at right now
+    pub fn sfx_volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master * self.sfx
+        }
+    }
+
+    /// Changes the master volume by `delta`, staying within `[0, 1]`
+    pub fn adjust_master(&mut self, delta: f32) {
+        // Round to whole steps so repeated presses don't accumulate float error
+        let master = ((self.master + delta) / VOLUME_STEP).round() * VOLUME_STEP;
+        self.master = master.clamp(0.0, 1.0);
+    }
+
+    /// Reads settings from `path`. A missing or unreadable file falls back to
+    /// the defaults, and out-of-range levels are clamped back into `[0, 1]`.
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return AudioSettings::default();
+        };
+
+        match ron::from_str::<AudioSettings>(&contents) {
+            Ok(settings) => AudioSettings {
+                master: settings.master.clamp(0.0, 1.0),
+                music: settings.music.clamp(0.0, 1.0),
+                sfx: settings.sfx.clamp(0.0, 1.0),
+                muted: settings.muted,
+            },
+            Err(error) => {
+                warn!("Ignoring invalid audio settings in {}: {}", path.display(), error);
+                AudioSettings::default()
+            }
+        }
+    }
+
+    /// Writes the settings to `path`, creating its parent directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+}
+
+/// Where the audio settings are stored, `None` if no config dir could be found
+#[derive(Resource)]
+pub struct AudioSettingsFile(pub Option<PathBuf>);
+
+/// `audio.ron` inside the per-user config dir of the game
+fn settings_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("galaga_bevy").join("audio.ron"))
+}
+
+/// Raises or lowers the master volume with + and -
+fn adjust_master_volume(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<AudioSettings>) {
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        settings.adjust_master(VOLUME_STEP);
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        settings.adjust_master(-VOLUME_STEP);
+    }
+}
+
+/// Mutes or unmutes all audio when M is pressed
+fn toggle_mute(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<AudioSettings>) {
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        settings.muted = !settings.muted;
+    }
+}
+
+/// Pushes changed settings to everything that is currently playing
+fn apply_volume_to_sinks(
+    settings: Res<AudioSettings>,
+    music_query: Query<&AudioSink, With<BackgroundMusic>>,
+    sfx_query: Query<&AudioSink, With<SoundEffect>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for sink in music_query.iter() {
+        sink.set_volume(settings.music_volume());
+    }
+    for sink in sfx_query.iter() {
+        sink.set_volume(settings.sfx_volume());
+    }
+}
+
+/// Persists the settings after every change made while the game runs
+fn save_audio_settings(settings: Res<AudioSettings>, settings_file: Res<AudioSettingsFile>) {
+    // The freshly loaded settings don't need to be written straight back
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+
+    let Some(path) = settings_file.0.as_deref() else {
+        return;
+    };
+
+    if let Err(error) = settings.save(path) {
+        warn!("Could not save audio settings to {}: {}", path.display(), error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn master_volume_steps_and_clamps() {
+        let mut settings = AudioSettings::default();
+        settings.adjust_master(VOLUME_STEP);
+        assert_eq!(settings.master, 1.0);
+
+        for _ in 0..3 {
+            settings.adjust_master(-VOLUME_STEP);
+        }
+        assert!((settings.master - 0.7).abs() < f32::EPSILON);
+
+        for _ in 0..20 {
+            settings.adjust_master(-VOLUME_STEP);
+        }
+        assert_eq!(settings.master, 0.0);
+    }
+
+    #[test]
+    fn muting_silences_every_channel() {
+        let settings = AudioSettings {
+            muted: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.music_volume(), 0.0);
+        assert_eq!(settings.sfx_volume(), 0.0);
+    }
+
+    #[test]
+    fn settings_survive_a_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("galaga_bevy_audio_{}", std::process::id()))
+            .join("audio.ron");
+        let settings = AudioSettings {
+            master: 0.4,
+            music: 0.2,
+            sfx: 0.9,
+            muted: true,
+        };
+
+        settings.save(&path).unwrap();
+        let loaded = AudioSettings::load(&path);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn missing_file_loads_defaults() {
+        let path = std::env::temp_dir().join("galaga_bevy_missing").join("audio.ron");
+        assert_eq!(AudioSettings::load(&path), AudioSettings::default());
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use crate::{
+    bullet::release_bullet,
+    collision::damage_fly,
+    effects::FadingEffect,
+    explosion::Lifetime,
+    fly_logic::{EnemyKind, Health, Points},
+    game_state::{GameState, PauseState},
+    layer,
+    lives::DeathSequence,
+    run_intro::RunIntro,
+    player_input::ShipKeys,
+    Bullet, BulletOwner, ColorsPalette, EnemyKilled, Fly, InputSet, PlayField,
+};
+
+/// Bombs the player starts every run with
+const START_BOMBS: u32 = 2;
+
+/// Most bombs the player can hold, bomb pickups beyond it are wasted
+pub const MAX_BOMBS: u32 = 3;
+
+/// Seconds the screen flash of a bomb takes to fade
+const BOMB_FLASH_DURATION: f32 = 0.3;
+
+/// Opacity the bomb flash starts fading from
+const BOMB_FLASH_ALPHA: f32 = 0.8;
+
+/// Gamepad button dropping a bomb, next to the south button used for menus
+pub const BOMB_BUTTON: GamepadButton = GamepadButton::West;
+
+/// Screen-clearing bombs: a few charges per run that wipe out enemy bullets
+/// and hit every fly on screen at once
+pub struct BombPlugin;
+
+impl Plugin for BombPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bombs>()
+            .add_systems(OnEnter(GameState::Playing), reset_bombs)
+            .add_systems(
+                Update,
+                drop_bomb
+                    .in_set(InputSet)
+                    .run_if(in_state(PauseState::Running).and(not(resource_exists::<DeathSequence>)))
+                    .run_if(not(resource_exists::<RunIntro>)),
+            );
+    }
+}
+
+/// Bombs left to drop this run
+#[derive(Resource, Debug, PartialEq)]
+pub struct Bombs(pub u32);
+
+impl Default for Bombs {
+    fn default() -> Self {
+        Bombs(START_BOMBS)
+    }
+}
+
+/// Marks the full-screen flash of a bomb. No bomb can be dropped while one is fading.
+#[derive(Component)]
+pub struct BombFlash;
+
+/// Gives a fresh run its starting bombs
+fn reset_bombs(mut commands: Commands) {
+    commands.insert_resource(Bombs::default());
+}
+
+/// Drops a bomb on a fresh press of a bomb key or the gamepad's bomb button:
+/// every enemy bullet is removed and every fly inside the play field takes one
+/// hit. Kills go out as `EnemyKilled` for half the usual points, so score,
+/// popups and explosions treat them like any other kill.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn drop_bomb(
+    mut commands: Commands,
+    ship_keys: ShipKeys,
+    mut bombs: ResMut<Bombs>,
+    flash_query: Query<(), With<BombFlash>>,
+    bullet_query: Query<(Entity, &Bullet)>,
+    mut fly_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&EnemyKind>,
+            Option<&Points>,
+            Option<&mut Health>,
+            Option<&mut Sprite>,
+        ),
+        With<Fly>,
+    >,
+    play_field: Res<PlayField>,
+    color_palette: Res<ColorsPalette>,
+    mut enemy_killed_events: EventWriter<EnemyKilled>,
+) {
+    if !ship_keys.bomb() || bombs.0 == 0 || !flash_query.is_empty() {
+        return;
+    }
+
+    bombs.0 -= 1;
+    info!(left = bombs.0, "Bomb dropped");
+
+    for (entity, bullet) in bullet_query.iter() {
+        if bullet.owner == BulletOwner::Enemy {
+            release_bullet(&mut commands, entity);
+        }
+    }
+
+    let half_size = play_field.half_size();
+    for (entity, transform, kind, points, health, sprite) in fly_query.iter_mut() {
+        let position = transform.translation;
+        // Flies still flying in from behind the walls are out of reach
+        if position.x.abs() > half_size.x || position.y.abs() > half_size.y {
+            continue;
+        }
+
+        let kind = kind.copied().unwrap_or_default();
+        if damage_fly(&mut commands, entity, kind, 1, health, sprite, &color_palette) {
+            commands.entity(entity).despawn_recursive();
+            enemy_killed_events.send(EnemyKilled {
+                entity,
+                points: points.map_or(kind.points(), |points| points.0) / 2,
+                position,
+                kind,
+                player: 0, // Only the first ship carries bombs
+            });
+        }
+    }
+
+    spawn_bomb_flash(&mut commands, &play_field, &color_palette);
+}
+
+/// Covers the whole play field in a flash fading out over `BOMB_FLASH_DURATION`
+fn spawn_bomb_flash(commands: &mut Commands, play_field: &PlayField, color_palette: &ColorsPalette) {
+    commands.spawn((
+        BombFlash,
+        FadingEffect {
+            alpha: BOMB_FLASH_ALPHA,
+        },
+        Lifetime(Timer::from_seconds(BOMB_FLASH_DURATION, TimerMode::Once)),
+        StateScoped(GameState::Playing),
+        Sprite {
+            color: color_palette.hit_flash_color.with_alpha(BOMB_FLASH_ALPHA),
+            ..Default::default()
+        },
+        Transform {
+            // In front of everything else in the play field
+            translation: Vec3::new(0.0, 0.0, layer::OVERLAY),
+            scale: Vec3::new(play_field.width, play_field.height, 1.0),
+            ..Default::default()
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybindings::KeyBindings;
+
+    fn bomb_app() -> App {
+        let mut keyboard_input = ButtonInput::<KeyCode>::default();
+        keyboard_input.press(KeyCode::KeyB);
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(keyboard_input)
+            .init_resource::<KeyBindings>()
+            .init_resource::<Bombs>()
+            .init_resource::<PlayField>()
+            .init_resource::<ColorsPalette>()
+            .add_event::<EnemyKilled>()
+            .add_systems(Update, drop_bomb);
+        app
+    }
+
+    fn spawn_fly(app: &mut App, kind: EnemyKind, position: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((Fly, kind, Health(kind.max_health()), Transform::from_translation(position)))
+            .id()
+    }
+
+    #[test]
+    fn bomb_clears_enemy_bullets_and_hits_every_fly_on_screen() {
+        let mut app = bomb_app();
+        let bee = spawn_fly(&mut app, EnemyKind::Bee, Vec3::new(100.0, 200.0, 0.0));
+        let boss = spawn_fly(&mut app, EnemyKind::Boss, Vec3::new(-100.0, 250.0, 0.0));
+        let entering = spawn_fly(&mut app, EnemyKind::Bee, Vec3::new(-700.0, 200.0, 0.0));
+        let enemy_bullet = app
+            .world_mut()
+            .spawn((Bullet { owner: BulletOwner::Enemy }, Transform::default()))
+            .id();
+        let player_bullet = app
+            .world_mut()
+            .spawn((Bullet { owner: BulletOwner::Player }, Transform::default()))
+            .id();
+
+        app.update();
+
+        let exists = |app: &App, entity: Entity| app.world().get_entity(entity).is_ok();
+        assert!(!exists(&app, bee));
+        assert!(!exists(&app, enemy_bullet));
+        assert!(exists(&app, player_bullet));
+        assert!(exists(&app, entering));
+        assert_eq!(app.world().get::<Health>(boss).unwrap().0, EnemyKind::Boss.max_health() - 1);
+        assert_eq!(app.world().resource::<Bombs>().0, START_BOMBS - 1);
+
+        let events = app.world().resource::<Events<EnemyKilled>>();
+        let kills: Vec<&EnemyKilled> = events.iter_current_update_events().collect();
+        assert_eq!(kills.len(), 1);
+        assert_eq!(kills[0].points, EnemyKind::Bee.points() / 2);
+    }
+
+    #[test]
+    fn no_second_bomb_while_the_flash_fades_or_without_charges() {
+        let mut app = bomb_app();
+        app.update();
+        assert_eq!(app.world().resource::<Bombs>().0, START_BOMBS - 1);
+
+        // Still pressed while the first flash is on screen
+        app.update();
+        assert_eq!(app.world().resource::<Bombs>().0, START_BOMBS - 1);
+
+        let mut flashes = app.world_mut().query_filtered::<Entity, With<BombFlash>>();
+        let flash = flashes.single(app.world());
+        app.world_mut().despawn(flash);
+        app.world_mut().resource_mut::<Bombs>().0 = 0;
+        app.update();
+        assert!(app.world_mut().query::<&BombFlash>().iter(app.world()).next().is_none());
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use crate::{
+    game_assets::GameAssets, game_state::GameState, layer, Bullet, BulletOwner, ColorsPalette, Damage,
+    DespawnOutOfBounds, FiredBy, MovementSet, Velocity,
+};
+
+/// Default speed of newly fired bullets, in units per second
+pub(crate) const BULLET_SPEED: f32 = 300.0;
+
+/// Bullets spawned into the pool at startup, more than a busy wave keeps in flight
+pub const POOL_START_SIZE: usize = 64;
+
+/// Most bullets the pool grows to. Past it, bullets are spawned and despawned
+/// one by one, as if there were no pool.
+pub const POOL_CAP: usize = 1024;
+
+/// Where parked bullets wait, far outside the play field
+const PARKED_POSITION: Vec3 = Vec3::new(0.0, -10_000.0, 0.0);
+
+/// Movement of bullets and everything else with a `Velocity`, and the pool
+/// bullets are taken from and handed back to instead of being spawned and
+/// despawned every shot. The bullet speed itself comes from the game config.
+pub struct BulletPlugin;
+
+impl Plugin for BulletPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BulletPool>()
+            .add_systems(Startup, fill_bullet_pool)
+            .add_systems(FixedUpdate, apply_velocity.in_set(MovementSet))
+            .add_systems(OnExit(GameState::Playing), park_active_bullets);
+    }
+}
+
+/// Marks a bullet entity owned by the `BulletPool`. It only counts as in
+/// flight while it has a `Bullet`: parking takes that away along with its
+/// `Velocity`, so hit checks, movement and culling never see a parked one.
+#[derive(Component, Debug)]
+pub struct Pooled;
+
+/// Bullet entities kept around between shots, hidden and parked off-screen
+/// while not in flight
+#[derive(Resource, Debug, Default)]
+pub struct BulletPool {
+    parked: Vec<Entity>,
+    size: usize, // Pooled bullets in all, parked or in flight
+}
+
+impl BulletPool {
+    /// Pooled bullets waiting to be fired
+    pub fn parked(&self) -> usize {
+        self.parked.len()
+    }
+
+    /// Pooled bullets in all, parked or in flight
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Spawns `count` more parked bullets, never going past `POOL_CAP`
+    fn grow(&mut self, commands: &mut Commands, count: usize) {
+        let count = count.min(POOL_CAP - self.size);
+        for _ in 0..count {
+            let bullet = commands
+                .spawn((Pooled, Transform::from_translation(PARKED_POSITION), Visibility::Hidden))
+                .id();
+            self.parked.push(bullet);
+        }
+        self.size += count;
+    }
+}
+
+fn fill_bullet_pool(mut commands: Commands, mut bullet_pool: ResMut<BulletPool>) {
+    bullet_pool.grow(&mut commands, POOL_START_SIZE);
+}
+
+/// Puts a bullet of `owner` in flight at `translation`, on the bullet layer,
+/// returning it. It is a parked one if there is any. Otherwise the pool
+/// doubles, up to `POOL_CAP`, and once it is full the bullet is spawned
+/// outside of it.
+pub fn checkout_bullet(
+    commands: &mut Commands,
+    bullet_pool: &mut BulletPool,
+    owner: BulletOwner,
+    translation: Vec3,
+    velocity: Vec2,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+) -> Entity {
+    let in_flight = (
+        Bullet { owner },
+        Velocity(velocity),
+        DespawnOutOfBounds,
+        GameAssets::sprite(&game_assets.bullet, color_palette.bullet_color(owner)),
+        Transform {
+            translation: translation.with_z(layer::BULLETS),
+            scale: owner.bullet_scale(),
+            ..Default::default()
+        },
+    );
+
+    if bullet_pool.parked.is_empty() && bullet_pool.size < POOL_CAP {
+        let count = bullet_pool.size.max(1);
+        bullet_pool.grow(commands, count);
+        info!(size = bullet_pool.size, "Bullet pool grown");
+    }
+
+    match bullet_pool.parked.pop() {
+        Some(bullet) => {
+            commands.entity(bullet).insert((in_flight, Visibility::Inherited));
+            bullet
+        }
+        None => commands.spawn((in_flight, StateScoped(GameState::Playing))).id(),
+    }
+}
+
+/// Takes a bullet out of flight. A pooled one is hidden, parked off-screen and
+/// handed back to the pool, any other is despawned. Releasing a bullet twice,
+/// like one that hit two flies in the same step, does nothing the second time.
+pub fn release_bullet(commands: &mut Commands, bullet: Entity) {
+    commands.queue(move |world: &mut World| {
+        let Ok(mut entity) = world.get_entity_mut(bullet) else {
+            return;
+        };
+        if !entity.contains::<Pooled>() {
+            entity.despawn();
+            return;
+        }
+        if !entity.contains::<Bullet>() {
+            return;
+        }
+
+        entity
+            .remove::<(Bullet, Velocity, DespawnOutOfBounds, FiredBy, Damage)>()
+            .insert((Transform::from_translation(PARKED_POSITION), Visibility::Hidden));
+        world.resource_mut::<BulletPool>().parked.push(bullet);
+    });
+}
+
+/// Hands every pooled bullet still in flight back when the run is left, the
+/// way the others go with `StateScoped`
+fn park_active_bullets(mut commands: Commands, bullet_query: Query<Entity, (With<Pooled>, With<Bullet>)>) {
+    for bullet in bullet_query.iter() {
+        release_bullet(&mut commands, bullet);
+    }
+}
+
+/// Moves everything with a `Velocity`, like bullets and falling pickups. Leaving
+/// the screen is handled by the out-of-bounds despawner. In `FixedUpdate`,
+/// `Time` is the fixed clock, so every step covers the same short distance
+/// however slow the frame rate.
+pub fn apply_velocity(mut query: Query<(&mut Transform, &Velocity)>, time: Res<Time>) {
+    let delta_time = time.delta_secs();
+
+    for (mut transform, velocity) in query.iter_mut() {
+        transform.translation += (velocity.0 * delta_time).extend(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{ecs::system::RunSystemOnce, time::TimeUpdateStrategy};
+    use std::time::Duration;
+
+    fn pool_app(bullet_pool: BulletPool) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .insert_resource(bullet_pool);
+        app
+    }
+
+    fn fire(app: &mut App) -> Entity {
+        app.world_mut()
+            .run_system_once(
+                |mut commands: Commands,
+                 mut bullet_pool: ResMut<BulletPool>,
+                 color_palette: Res<ColorsPalette>,
+                 game_assets: Res<GameAssets>| {
+                    checkout_bullet(
+                        &mut commands,
+                        &mut bullet_pool,
+                        BulletOwner::Enemy,
+                        Vec3::new(0.0, 100.0, 0.0),
+                        Vec2::new(0.0, -300.0),
+                        &color_palette,
+                        &game_assets,
+                    )
+                },
+            )
+            .unwrap()
+    }
+
+    fn release(app: &mut App, bullet: Entity) {
+        app.world_mut()
+            .run_system_once(move |mut commands: Commands| release_bullet(&mut commands, bullet))
+            .unwrap();
+    }
+
+    fn pool(app: &App) -> (usize, usize) {
+        let bullet_pool = app.world().resource::<BulletPool>();
+        (bullet_pool.parked(), bullet_pool.size())
+    }
+
+    #[test]
+    fn released_bullets_are_parked_and_fired_again() {
+        let mut app = pool_app(BulletPool::default());
+        app.add_systems(Startup, fill_bullet_pool);
+        app.update();
+        assert_eq!(pool(&app), (POOL_START_SIZE, POOL_START_SIZE));
+
+        let bullet = fire(&mut app);
+        assert_eq!(pool(&app), (POOL_START_SIZE - 1, POOL_START_SIZE));
+        assert_eq!(app.world().get::<Visibility>(bullet), Some(&Visibility::Inherited));
+        assert_eq!(app.world().get::<Velocity>(bullet), Some(&Velocity(Vec2::new(0.0, -300.0))));
+
+        // A bullet hitting two flies in one step is released twice
+        release(&mut app, bullet);
+        release(&mut app, bullet);
+        assert_eq!(pool(&app), (POOL_START_SIZE, POOL_START_SIZE));
+        assert!(app.world().get::<Bullet>(bullet).is_none());
+        assert!(app.world().get::<Velocity>(bullet).is_none());
+        assert_eq!(app.world().get::<Visibility>(bullet), Some(&Visibility::Hidden));
+        assert_eq!(app.world().get::<Transform>(bullet).unwrap().translation, PARKED_POSITION);
+
+        assert_eq!(fire(&mut app), bullet);
+        assert_eq!(app.world().get::<Transform>(bullet).unwrap().translation, Vec3::new(0.0, 100.0, layer::BULLETS));
+    }
+
+    #[test]
+    fn pool_grows_up_to_its_cap_then_spawns_outside_it() {
+        let mut app = pool_app(BulletPool::default());
+        for size in [1, 2, 4, 4] {
+            fire(&mut app);
+            assert_eq!(pool(&app).1, size);
+        }
+
+        let mut app = pool_app(BulletPool {
+            parked: Vec::new(),
+            size: POOL_CAP,
+        });
+        let bullet = fire(&mut app);
+        assert!(app.world().get::<Pooled>(bullet).is_none());
+        assert_eq!(pool(&app), (0, POOL_CAP));
+
+        release(&mut app, bullet);
+        assert!(app.world().get_entity(bullet).is_err());
+        assert_eq!(pool(&app), (0, POOL_CAP));
+    }
+
+    #[test]
+    fn velocity_moves_bullets_along_it() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .add_systems(Update, apply_velocity);
+
+        let bullet = app
+            .world_mut()
+            .spawn((
+                Bullet { owner: BulletOwner::Enemy },
+                Velocity(Vec2::new(30.0, -300.0)),
+                Transform::default(),
+            ))
+            .id();
+
+        app.update();
+        app.update();
+
+        let translation = app.world().get::<Transform>(bullet).unwrap().translation;
+        assert!(translation.x > 0.0);
+        assert!(translation.y < 0.0);
+        assert!((translation.y / translation.x + 10.0).abs() < 1e-3);
+    }
+}
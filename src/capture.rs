@@ -0,0 +1,501 @@
+use bevy::prelude::*;
+use rand::seq::IteratorRandom;
+use crate::{
+    collision::is_colliding,
+    coop::CoopLives,
+    dive::{dive_flies, Diving},
+    explosion::spawn_explosion,
+    fly_logic::{EnemyKind, FlyState},
+    formation::{follow_entry_path, hold_formation, EnteringFormation, FormationSlot},
+    game_assets::GameAssets,
+    game_rng::GameRng,
+    game_state::{GameState, PauseState},
+    lives::{lose_life, Invulnerable, Lives},
+    players::{CurrentPlayer, PlayerSlots},
+    practice::{infinite_lives, PracticeRun},
+    run_intro::RunIntro,
+    ColorsPalette, EnemyKilled, Fly, MovementSet, PlayField, Player, PlayerPosition,
+};
+
+/// Seconds between two bosses trying to capture the ship
+const BEAM_INTERVAL: f32 = 12.0;
+
+/// Speed a beaming boss flies down to its hover spot at, in units per second
+const BEAM_DIVE_SPEED: f32 = 250.0;
+
+/// How far above the player ship a beaming boss hovers
+const BEAM_HOVER_HEIGHT: f32 = 260.0;
+
+/// Seconds the beam stays on
+const BEAM_DURATION: f32 = 2.0;
+
+/// Width of the tractor beam
+const BEAM_WIDTH: f32 = 90.0;
+
+/// Seconds a boss takes to fly back up to its slot after beaming
+const BEAM_RETURN_DURATION: f32 = 1.5;
+
+/// How far above its captor a captured ship is carried
+const CAPTIVE_HOVER: f32 = 50.0;
+
+/// Distance between the player ship and a docked second ship
+pub const DOCK_OFFSET: f32 = 50.0;
+
+/// Boss tractor beams: a captured ship is carried by its boss until the boss
+/// is shot in formation, which frees the ship to dock next to the player
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BeamTimer::default())
+            .add_systems(OnEnter(GameState::Playing), reset_beam_timer)
+            .add_systems(
+                FixedUpdate,
+                (
+                    beam_dive,
+                    capture_player.after(beam_dive),
+                    carry_captured_ships
+                        .after(capture_player)
+                        .after(follow_entry_path)
+                        .after(hold_formation)
+                        .after(dive_flies),
+                )
+                    .in_set(MovementSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    // No boss goes for the ship while the run intro plays
+                    start_beam_dives.run_if(not(resource_exists::<RunIntro>)),
+                    release_captured_ships,
+                )
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// Timer deciding when the next boss tries to capture the ship
+#[derive(Resource)]
+pub struct BeamTimer(pub Timer);
+
+impl Default for BeamTimer {
+    fn default() -> Self {
+        BeamTimer(Timer::from_seconds(BEAM_INTERVAL, TimerMode::Repeating))
+    }
+}
+
+/// A boss that left the formation to hover over the player and beam them up
+#[derive(Component)]
+pub struct BeamDive {
+    pub hover: Vec3,                   // Where the boss stops to switch on the beam
+    pub beam: Option<(Entity, Timer)>, // The beam and its remaining time while on
+}
+
+/// The translucent area below a beaming boss that captures the ship
+#[derive(Component)]
+pub struct TractorBeam {
+    pub boss: Entity,
+}
+
+/// A player ship held by a boss. It is carried above its captor and no
+/// longer counts as the player.
+#[derive(Component)]
+pub struct Captured {
+    pub captor: Entity,
+    pub captor_in_formation: bool, // Decides the ship's fate when the captor dies
+}
+
+/// A player ship with a rescued second ship docked to its right. It fires
+/// from both ships and is hit anywhere across both.
+#[derive(Component)]
+pub struct DualFighter;
+
+/// Area the player ship can be hit in, as its center and size on the screen
+/// plane: the layer it is drawn on plays no part. A docked second ship makes
+/// it twice as wide.
+pub fn hitbox(transform: &Transform, dual: bool) -> (Vec2, Vec2) {
+    let position = transform.translation.truncate();
+    let size = transform.scale.truncate();
+    if !dual {
+        return (position, size);
+    }
+
+    let center = position + Vec2::new(DOCK_OFFSET / 2.0, 0.0);
+    (center, Vec2::new(size.x + DOCK_OFFSET, size.y))
+}
+
+/// Path back up to the slot for a boss that finished beaming
+fn return_to_formation(start: Vec3) -> EnteringFormation {
+    EnteringFormation {
+        start,
+        control: start + Vec3::new(0.0, 150.0, 0.0),
+        progress: 0.0,
+        duration: BEAM_RETURN_DURATION,
+    }
+}
+
+fn reset_beam_timer(mut beam_timer: ResMut<BeamTimer>) {
+    *beam_timer = BeamTimer::default();
+}
+
+/// Every time the beam timer fires, sends a random settled boss to hover above
+/// the player. Only one ship can be held at a time, and a dual fighter is
+/// never targeted.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn start_beam_dives(
+    mut commands: Commands,
+    mut beam_timer: ResMut<BeamTimer>,
+    boss_query: Query<
+        (Entity, &EnemyKind),
+        (
+            With<FormationSlot>,
+            Without<EnteringFormation>,
+            Without<Diving>,
+            Without<BeamDive>,
+        ),
+    >,
+    busy_query: Query<(), Or<(With<BeamDive>, With<Captured>)>>,
+    player_query: Query<(), (With<Player>, Without<DualFighter>)>,
+    player_position: Res<PlayerPosition>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    if !beam_timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if !busy_query.is_empty() || player_query.is_empty() {
+        return;
+    }
+
+    let boss = boss_query
+        .iter()
+        .filter(|(_, kind)| **kind == EnemyKind::Boss)
+        .choose(&mut *game_rng);
+
+    if let Some((entity, _)) = boss {
+        let hover = player_position.0 + Vec3::new(0.0, BEAM_HOVER_HEIGHT, 0.0);
+        debug!(boss = ?entity, "Boss dives to beam");
+        commands.entity(entity).insert((BeamDive { hover, beam: None }, FlyState::Diving));
+    }
+}
+
+/// Flies beaming bosses down to their hover spot, keeps the beam on below them
+/// for `BEAM_DURATION` and then sends them back to their slot
+fn beam_dive(
+    mut commands: Commands,
+    mut boss_query: Query<(Entity, &mut Transform, &mut BeamDive)>,
+    beam_query: Query<(Entity, &TractorBeam)>,
+    play_field: Res<PlayField>,
+    color_palette: Res<ColorsPalette>,
+    time: Res<Time>,
+) {
+    // Bosses shot mid-beam leave their beam behind
+    for (beam_entity, beam) in beam_query.iter() {
+        if boss_query.get(beam.boss).is_err() {
+            commands.entity(beam_entity).despawn();
+        }
+    }
+
+    for (entity, mut transform, mut beam_dive) in boss_query.iter_mut() {
+        let hover = beam_dive.hover.with_z(transform.translation.z);
+
+        if let Some((beam_entity, timer)) = beam_dive.beam.as_mut() {
+            if timer.tick(time.delta()).finished() {
+                commands.entity(*beam_entity).despawn();
+                commands
+                    .entity(entity)
+                    .remove::<BeamDive>()
+                    .insert((return_to_formation(transform.translation), FlyState::Entering));
+            }
+            continue;
+        }
+
+        let step = BEAM_DIVE_SPEED * time.delta_secs();
+        if transform.translation.distance(hover) > step {
+            let direction = (hover - transform.translation).normalize();
+            transform.translation += direction * step;
+            continue;
+        }
+
+        // Arrived, the beam reaches from below the boss down to the bottom edge
+        transform.translation = hover;
+        let top = hover.y - transform.scale.y / 2.0;
+        let bottom = -play_field.half_size().y;
+        let beam_entity = commands
+            .spawn((
+                TractorBeam { boss: entity },
+                StateScoped(GameState::Playing),
+                Sprite {
+                    color: color_palette.powerup_color.with_alpha(0.35),
+                    ..Default::default()
+                },
+                Transform {
+                    translation: Vec3::new(hover.x, (top + bottom) / 2.0, hover.z - 0.5),
+                    scale: Vec3::new(BEAM_WIDTH, top - bottom, 1.0),
+                    ..Default::default()
+                },
+            ))
+            .id();
+        beam_dive.beam = Some((beam_entity, Timer::from_seconds(BEAM_DURATION, TimerMode::Once)));
+    }
+}
+
+/// Captures a vulnerable player ship caught in a beam: the ship is handed to
+/// the boss, a life is taken and a replacement ship respawns as usual
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn capture_player(
+    mut commands: Commands,
+    beam_query: Query<(Entity, &Transform, &TractorBeam)>,
+    mut player_query: Query<
+        (Entity, &Transform, Option<&mut Sprite>),
+        (With<Player>, Without<Invulnerable>),
+    >,
+    boss_query: Query<&Transform, With<BeamDive>>,
+    mut lives: ResMut<Lives>,
+    slots: Res<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+    coop_lives: Option<Res<CoopLives>>,
+    practice: Option<Res<PracticeRun>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    color_palette: Res<ColorsPalette>,
+) {
+    let Ok((player_entity, player_transform, sprite)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    for (beam_entity, beam_transform, beam) in beam_query.iter() {
+        if !is_colliding(
+            player_transform.translation.truncate(),
+            player_transform.scale.truncate(),
+            beam_transform.translation.truncate(),
+            beam_transform.scale.truncate(),
+        ) {
+            continue;
+        }
+
+        let Ok(boss_transform) = boss_query.get(beam.boss) else {
+            continue;
+        };
+
+        // The beam did its job, the boss carries the ship back up
+        commands.entity(beam_entity).despawn();
+        commands
+            .entity(beam.boss)
+            .remove::<BeamDive>()
+            .insert((return_to_formation(boss_transform.translation), FlyState::Entering));
+
+        commands.entity(player_entity).remove::<Player>().insert(Captured {
+            captor: beam.boss,
+            captor_in_formation: false,
+        });
+        // Captured ships take on the color of the enemy holding them
+        if let Some(mut sprite) = sprite {
+            sprite.color = color_palette.boss_color;
+        }
+
+        let partner_lives = coop_lives.as_deref().map_or(0, |coop_lives| coop_lives.0);
+        let infinite = infinite_lives(practice.as_deref());
+        lose_life(&mut commands, &mut lives, &slots, &current, partner_lives, infinite, &mut next_state);
+        info!(lives = lives.0, boss = ?beam.boss, "Player captured");
+        return;
+    }
+}
+
+/// Keeps captured ships hovering above their captor and notes whether the
+/// captor is sitting in the formation. A captive whose captor vanished
+/// without being shot is lost with it.
+#[allow(clippy::type_complexity)]
+fn carry_captured_ships(
+    mut commands: Commands,
+    mut captive_query: Query<(Entity, &mut Transform, &mut Captured)>,
+    captor_query: Query<
+        (&Transform, Has<Diving>, Has<BeamDive>, Has<EnteringFormation>),
+        (With<Fly>, Without<Captured>),
+    >,
+) {
+    for (entity, mut transform, mut captured) in captive_query.iter_mut() {
+        let Ok((captor_transform, diving, beaming, entering)) = captor_query.get(captured.captor) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        transform.translation = captor_transform.translation + Vec3::new(0.0, CAPTIVE_HOVER, 0.0);
+        captured.captor_in_formation = !diving && !beaming && !entering;
+    }
+}
+
+/// Decides what happens to a captive when its captor is destroyed: shot in
+/// formation, the ship is freed and docks next to the player; shot mid-dive,
+/// the ship is destroyed along with it. A freed ship with no player ship to
+/// dock to is lost.
+#[allow(clippy::type_complexity)]
+fn release_captured_ships(
+    mut commands: Commands,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    captive_query: Query<(Entity, &Transform, &Captured)>,
+    player_query: Query<(Entity, &Transform), (With<Player>, Without<DualFighter>)>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    for event in enemy_killed_events.read() {
+        for (captive_entity, captive_transform, captured) in captive_query.iter() {
+            if captured.captor != event.entity {
+                continue;
+            }
+
+            commands.entity(captive_entity).despawn_recursive();
+
+            if !captured.captor_in_formation {
+                debug!("Captured ship destroyed with its diving captor");
+                spawn_explosion(
+                    &mut commands,
+                    &mut game_rng,
+                    captive_transform.translation,
+                    color_palette.player_color,
+                );
+                continue;
+            }
+
+            let Ok((player_entity, player_transform)) = player_query.get_single() else {
+                continue;
+            };
+
+            info!("Captured ship freed, docking");
+            // The docked ship is a child, so its offset is in the player's scaled units
+            let offset = DOCK_OFFSET / player_transform.scale.x;
+            commands.entity(player_entity).insert(DualFighter).with_children(|parent| {
+                parent.spawn((
+                    GameAssets::sprite(&game_assets.player, color_palette.player_color),
+                    Transform::from_xyz(offset, 0.0, 0.0),
+                ));
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .insert_resource(GameRng::from_seed(0))
+            .add_event::<EnemyKilled>()
+            .add_systems(Update, release_captured_ships);
+        app
+    }
+
+    fn kill(app: &mut App, boss: Entity) {
+        app.world_mut().send_event(EnemyKilled {
+            entity: boss,
+            points: EnemyKind::Boss.points(),
+            position: Vec3::ZERO,
+            kind: EnemyKind::Boss,
+            player: 0,
+        });
+        app.update();
+    }
+
+    #[test]
+    fn beam_captures_the_ship_and_costs_a_life() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ColorsPalette>()
+            .insert_resource(Lives(3))
+            .init_resource::<PlayerSlots>()
+            .init_resource::<CurrentPlayer>()
+            .init_resource::<NextState<GameState>>()
+            .add_systems(Update, capture_player);
+
+        let boss = app
+            .world_mut()
+            .spawn((
+                BeamDive {
+                    hover: Vec3::ZERO,
+                    beam: None,
+                },
+                Transform::from_xyz(0.0, 0.0, 0.0),
+            ))
+            .id();
+        app.world_mut().spawn((
+            TractorBeam { boss },
+            Transform::from_xyz(0.0, -200.0, 0.0).with_scale(Vec3::new(BEAM_WIDTH, 400.0, 1.0)),
+        ));
+        let player = app
+            .world_mut()
+            .spawn((Player, Transform::from_xyz(10.0, -250.0, 0.0).with_scale(Vec3::splat(50.0))))
+            .id();
+
+        app.update();
+
+        assert!(app.world().get::<Player>(player).is_none());
+        assert_eq!(app.world().get::<Captured>(player).map(|captured| captured.captor), Some(boss));
+        assert_eq!(app.world().resource::<Lives>().0, 2);
+        assert!(app.world().get::<BeamDive>(boss).is_none());
+    }
+
+    #[test]
+    fn shooting_the_captor_in_formation_frees_the_ship() {
+        let mut app = release_app();
+        let boss = app.world_mut().spawn_empty().id();
+        let captive = app
+            .world_mut()
+            .spawn((
+                Captured {
+                    captor: boss,
+                    captor_in_formation: true,
+                },
+                Transform::default(),
+            ))
+            .id();
+        let player = app
+            .world_mut()
+            .spawn((Player, Transform::from_scale(Vec3::splat(50.0))))
+            .id();
+
+        kill(&mut app, boss);
+
+        assert!(app.world().get_entity(captive).is_err());
+        assert!(app.world().get::<DualFighter>(player).is_some());
+    }
+
+    #[test]
+    fn shooting_the_captor_mid_dive_destroys_the_ship() {
+        let mut app = release_app();
+        let boss = app.world_mut().spawn_empty().id();
+        let captive = app
+            .world_mut()
+            .spawn((
+                Captured {
+                    captor: boss,
+                    captor_in_formation: false,
+                },
+                Transform::default(),
+            ))
+            .id();
+        let player = app
+            .world_mut()
+            .spawn((Player, Transform::from_scale(Vec3::splat(50.0))))
+            .id();
+
+        kill(&mut app, boss);
+
+        assert!(app.world().get_entity(captive).is_err());
+        assert!(app.world().get::<DualFighter>(player).is_none());
+    }
+
+    #[test]
+    fn dual_fighter_hitbox_covers_both_ships() {
+        let transform = Transform::from_scale(Vec3::splat(50.0));
+        let (center, size) = hitbox(&transform, true);
+
+        assert_eq!(center, Vec2::new(DOCK_OFFSET / 2.0, 0.0));
+        assert_eq!(size, Vec2::new(50.0 + DOCK_OFFSET, 50.0));
+        assert_eq!(hitbox(&transform, false), (Vec2::ZERO, Vec2::splat(50.0)));
+    }
+}
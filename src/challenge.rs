@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+use crate::{
+    config::GameConfig,
+    fly_logic::{spawn_fly, EnemyKind, FlyState, Points},
+    game_assets::GameAssets,
+    game_state::PauseState,
+    paths::{FlightPath, FollowPath, PathLibrary},
+    stats::WaveStats,
+    wave::{NextWaveTimer, WaveIntroTimer, WaveRemaining},
+    ColorsPalette, EnemyKilled, EntityIdCounter, Fly, PlayField, Score,
+};
+
+/// Flies streaming past during a challenge stage
+pub const CHALLENGE_FLIES: usize = 24;
+
+/// Flies following the same path one after another
+const FLIES_PER_PATH: usize = 8;
+
+/// Seconds between two flies entering a path
+const CHALLENGE_SPAWN_GAP: f32 = 0.3;
+
+/// Points for every fly destroyed during a challenge stage
+const CHALLENGE_KILL_POINTS: u32 = 100;
+
+/// Extra points for destroying every fly of a challenge stage
+const PERFECT_BONUS: u32 = 1000;
+
+/// Seconds the results stay on screen before the next wave starts
+const RESULTS_DURATION: f32 = 3.0;
+
+/// Challenge stages: flies stream past along fixed paths without shooting or
+/// diving, and destroying all of them earns a bonus
+pub struct ChallengePlugin;
+
+impl Plugin for ChallengePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_challenge_flies.run_if(not(resource_exists::<WaveIntroTimer>)),
+                count_challenge_kills,
+                finish_challenge_stage.after(count_challenge_kills),
+            )
+                .run_if(resource_exists::<ChallengeStage>)
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// Present while a challenge stage is being played
+#[derive(Resource)]
+pub struct ChallengeStage {
+    pub killed: usize,
+    pub spawn_timer: Timer, // Brings in the next fly of the stream
+}
+
+impl Default for ChallengeStage {
+    fn default() -> Self {
+        ChallengeStage {
+            killed: 0,
+            spawn_timer: Timer::from_seconds(CHALLENGE_SPAWN_GAP, TimerMode::Repeating),
+        }
+    }
+}
+
+impl ChallengeStage {
+    /// Bonus earned at the end of the stage
+    pub fn bonus(&self) -> u32 {
+        if self.killed == CHALLENGE_FLIES {
+            PERFECT_BONUS
+        } else {
+            0
+        }
+    }
+}
+
+/// Sends the next fly of the stream onto its path every time the spawn timer
+/// fires. Each path gets its flies in a row before the next path starts, and
+/// flies leaving the field at the end of their path are removed by `follow_paths`.
+#[allow(clippy::too_many_arguments)]
+fn spawn_challenge_flies(
+    mut commands: Commands,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    mut stage: ResMut<ChallengeStage>,
+    mut remaining: ResMut<WaveRemaining>,
+    play_field: Res<PlayField>,
+    paths: Res<PathLibrary>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    if remaining.0 == 0 || !stage.spawn_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let index = CHALLENGE_FLIES - remaining.0;
+    let challenge_paths: Vec<&FlightPath> = paths.challenge_paths().collect();
+    let path_index = (index / FLIES_PER_PATH) % challenge_paths.len();
+    let path = challenge_paths[path_index];
+    // Every third path brings butterflies instead of bees
+    let kind = if path_index % 3 == 2 { EnemyKind::Butterfly } else { EnemyKind::Bee };
+    let start = path.start(&play_field);
+
+    let fly = spawn_fly(
+        &mut commands,
+        &mut entity_ids,
+        start,
+        kind,
+        &color_palette,
+        &game_assets,
+        &config,
+    );
+    commands
+        .entity(fly)
+        .insert((FollowPath::new(path), Points(CHALLENGE_KILL_POINTS), FlyState::Entering));
+    remaining.0 -= 1;
+}
+
+/// Counts the flies destroyed during the stage
+fn count_challenge_kills(
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut stage: ResMut<ChallengeStage>,
+) {
+    stage.killed += enemy_killed_events.read().count();
+}
+
+/// Once every fly has been destroyed or escaped, pays out the perfect bonus
+/// and starts the countdown to the next wave, during which the wave results
+/// screen shows the hits and the bonus
+fn finish_challenge_stage(
+    mut commands: Commands,
+    stage: Res<ChallengeStage>,
+    remaining: Res<WaveRemaining>,
+    fly_query: Query<(), With<Fly>>,
+    mut score: ResMut<Score>,
+    mut wave_stats: ResMut<WaveStats>,
+) {
+    if remaining.0 > 0 || !fly_query.is_empty() {
+        return;
+    }
+
+    let bonus = stage.bonus();
+    score.0 += bonus;
+    wave_stats.bonus = bonus;
+    info!(killed = stage.killed, bonus, "Challenge stage over");
+
+    commands.remove_resource::<ChallengeStage>();
+    commands.insert_resource(NextWaveTimer(Timer::from_seconds(RESULTS_DURATION, TimerMode::Once)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_perfect_stage_earns_the_bonus() {
+        let mut stage = ChallengeStage {
+            killed: CHALLENGE_FLIES - 1,
+            ..Default::default()
+        };
+        assert_eq!(stage.bonus(), 0);
+
+        stage.killed += 1;
+        assert_eq!(stage.bonus(), PERFECT_BONUS);
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+use galaga_bevy::difficulty::Difficulty;
+
+/// Printed for `--help` and for any command line that can't be understood
+const USAGE: &str = "\
+Usage: galaga_bevy [OPTIONS]
+
+Options:
+  --windowed             Start in a window instead of fullscreen
+  --seed <N>             Seed the random generator to replay a run
+  --difficulty <NAME>    Play on easy, normal or hard
+  --mute                 Start with all audio muted
+  --skip-menu            Go straight into a run
+  --wave <N>             Start runs on wave N
+  --config <PATH>        Read colors and tunables from another file
+  --replay <PATH>        Play back a saved .replay file
+  --help                 Show this message";
+
+/// Everything that can be set from the command line. Options that weren't
+/// given leave the game's usual defaults in place.
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    pub windowed: bool,
+    pub seed: Option<u64>,
+    pub difficulty: Option<Difficulty>,
+    pub mute: bool,
+    pub skip_menu: bool,
+    pub wave: Option<u32>,
+    pub config: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+
+/// Why the command line couldn't be used
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    Help,
+    Invalid(String),
+}
+
+impl CliArgs {
+    /// Parses the arguments of this process. Prints the usage and exits on
+    /// `--help` or anything it doesn't understand, so a typo never starts a run
+    /// with settings other than the ones asked for.
+    pub fn from_env() -> CliArgs {
+        match CliArgs::parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(CliError::Help) => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            Err(CliError::Invalid(message)) => {
+                eprintln!("{}\n\n{}", message, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Parses `args`, not including the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliArgs, CliError> {
+        let mut parsed = CliArgs::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--windowed" => parsed.windowed = true,
+                "--mute" => parsed.mute = true,
+                "--skip-menu" => parsed.skip_menu = true,
+                "--seed" => parsed.seed = Some(value(&arg, args.next())?),
+                "--difficulty" => parsed.difficulty = Some(value(&arg, args.next())?),
+                "--wave" => {
+                    let wave = value(&arg, args.next())?;
+                    if wave == 0 {
+                        return Err(CliError::Invalid("Waves start at 1".to_string()));
+                    }
+                    parsed.wave = Some(wave);
+                }
+                "--config" => parsed.config = Some(value(&arg, args.next())?),
+                "--replay" => parsed.replay = Some(value(&arg, args.next())?),
+                "--help" | "-h" => return Err(CliError::Help),
+                _ => return Err(CliError::Invalid(format!("Unknown option {:?}", arg))),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parses the value following `flag`
+fn value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, CliError> {
+    let value = value.ok_or_else(|| CliError::Invalid(format!("{} needs a value", flag)))?;
+    value
+        .parse()
+        .map_err(|_| CliError::Invalid(format!("Invalid value {:?} for {}", value, flag)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, CliError> {
+        CliArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn every_option_is_read() {
+        let args = parse(&[
+            "--windowed",
+            "--seed",
+            "1234",
+            "--difficulty",
+            "HARD",
+            "--mute",
+            "--skip-menu",
+            "--wave",
+            "4",
+            "--config",
+            "tuning/fast.ron",
+            "--replay",
+            "replays/run-7.replay",
+        ]);
+
+        assert_eq!(
+            args,
+            Ok(CliArgs {
+                windowed: true,
+                seed: Some(1234),
+                difficulty: Some(Difficulty::Hard),
+                mute: true,
+                skip_menu: true,
+                wave: Some(4),
+                config: Some(PathBuf::from("tuning/fast.ron")),
+                replay: Some(PathBuf::from("replays/run-7.replay")),
+            })
+        );
+        assert_eq!(parse(&[]), Ok(CliArgs::default()));
+    }
+
+    #[test]
+    fn unknown_or_broken_options_are_refused() {
+        assert!(matches!(parse(&["--fullscreen"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--seed", "banana"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--difficulty", "nightmare"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--wave"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--wave", "0"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--config"]), Err(CliError::Invalid(_))));
+        assert!(matches!(parse(&["--replay"]), Err(CliError::Invalid(_))));
+        assert_eq!(parse(&["--help"]), Err(CliError::Help));
+    }
+}
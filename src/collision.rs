@@ -0,0 +1,840 @@
+use bevy::{
+    math::bounding::{Aabb2d, IntersectsVolume},
+    prelude::*,
+    utils::HashSet,
+};
+use crate::{
+    bullet::release_bullet,
+    capture::{hitbox, DualFighter},
+    coop::{CoopPlayer, CoopScore},
+    effects::HitFlash,
+    fly_logic::{EnemyKind, Health, Points},
+    lives::Invulnerable,
+    mothership::Mothership,
+    spatial_grid::SpatialGrid,
+    Bullet, BulletHit, BulletOwner, ColorsPalette, Damage, DebugSettings, EnemyKilled, EntityType, FiredBy,
+    Fly, GameEntity, Player, PlayerHit, Score,
+};
+
+/// Points for shooting down an enemy bullet while `BulletClash` is on
+pub const BULLET_CLASH_POINTS: u32 = 10;
+
+/// Every hit check between bullets, flies and the player, and what the hits do
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialGrid>()
+            .add_event::<BulletHit>()
+            .add_event::<EnemyKilled>()
+            .add_event::<PlayerHit>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Hit checks on the positions everything moved to this step
+                    (rebuild_spatial_grid, collision).chain().in_set(CollisionSet::Detect),
+                    fly_player_collision.in_set(CollisionSet::Detect),
+                    clash_bullets
+                        .before(collision)
+                        .in_set(CollisionSet::Detect)
+                        .run_if(resource_equals(BulletClash(true))),
+                    (despawn_hit_bullets, apply_bullet_hits).in_set(CollisionSet::Resolve),
+                ),
+            );
+    }
+}
+
+/// Hit handling happens in two steps: `Detect` only finds hits and sends
+/// events about them, `Resolve` turns those into despawns, damage and kills.
+/// Both run in `FixedUpdate` between `MovementSet` and `CleanupSet`, and its
+/// steps all come before `Update`, so systems in `Update` reacting to
+/// `EnemyKilled` or `PlayerHit` see them the same frame.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CollisionSet {
+    Detect,
+    Resolve,
+}
+
+/// Whether player bullets shoot down the enemy bullets they touch, set in the
+/// game config. Off by default, bullets of both sides then pass through each other.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BulletClash(pub bool);
+
+/// Fills the spatial grid with the hit box of everything a bullet could hit
+/// this frame, after it all moved
+#[allow(clippy::type_complexity)]
+pub fn rebuild_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    target_query: Query<
+        (Entity, &Transform, Has<DualFighter>),
+        (
+            With<GameEntity>,
+            Or<(With<Player>, With<CoopPlayer>, With<Fly>)>,
+            Without<Bullet>,
+            Without<Invulnerable>,
+        ),
+    >,
+) {
+    grid.clear();
+
+    for (entity, transform, dual) in target_query.iter() {
+        let (position, size) = hitbox(transform, dual);
+        grid.insert(entity, position, size);
+    }
+}
+
+/// Detects collisions between bullets and other entities (like Fly or Player).
+/// Player bullets only hit flies and enemy bullets only hit the player ships,
+/// bullets are never targets and pass through each other. Invulnerable targets are ignored and let bullets pass through.
+/// Each bullet hits at most one target, and each target is hit by at most one
+/// bullet per step: the others keep flying and may hit it on a later step.
+/// Only targets sharing a cell of the spatial grid with a bullet are checked.
+#[allow(clippy::type_complexity)]
+pub fn collision(
+    bullet_query: Query<(Entity, &Transform, &Bullet)>,
+    target_query: Query<
+        (&Transform, &GameEntity, Has<DualFighter>),
+        (Or<(With<Player>, With<CoopPlayer>, With<Fly>)>, Without<Bullet>, Without<Invulnerable>),
+    >,
+    grid: Res<SpatialGrid>,
+    debug_settings: Res<DebugSettings>,
+    mut bullet_hit_events: EventWriter<BulletHit>,
+) {
+    // Targets already hit this step, so two bullets never consume the same one
+    let mut claimed = HashSet::default();
+
+    for (bullet_entity, bullet_transform, bullet) in bullet_query.iter() {
+        let nearby = grid.query(bullet_transform.translation.truncate(), bullet_transform.scale.truncate());
+
+        for target_entity in nearby {
+            if claimed.contains(&target_entity) {
+                continue;
+            }
+            let Ok((target_transform, target, dual)) = target_query.get(target_entity) else {
+                continue;
+            };
+
+            if !can_hit(bullet.owner, &target.entity_type) {
+                continue;
+            }
+
+            if collision_box(bullet_transform, false).intersects(&collision_box(target_transform, dual)) {
+                if debug_settings.verbose {
+                    debug!(
+                        bullet = ?bullet_transform.translation,
+                        target = ?target.entity_type,
+                        target_id = target.id,
+                        position = ?target_transform.translation,
+                        "Collision detected"
+                    );
+                }
+
+                bullet_hit_events.send(BulletHit {
+                    bullet: bullet_entity,
+                    target: target_entity,
+                    target_type: target.entity_type,
+                });
+                claimed.insert(target_entity);
+
+                // Break to avoid processing this bullet further
+                break;
+            }
+        }
+    }
+}
+
+/// With `BulletClash` on, a player bullet touching an enemy bullet destroys
+/// both and scores `BULLET_CLASH_POINTS` for whoever fired it. Each enemy
+/// bullet takes down at most one player bullet. Runs before `collision`, so
+/// a bullet spent here can't hit anything else in the same step.
+pub fn clash_bullets(
+    mut commands: Commands,
+    bullet_query: Query<(Entity, &Transform, &Bullet, Option<&FiredBy>)>,
+    mut score: ResMut<Score>,
+    mut coop_score: Option<ResMut<CoopScore>>,
+) {
+    let mut enemy_bullets: Vec<(Entity, &Transform)> = bullet_query
+        .iter()
+        .filter(|(_, _, bullet, _)| bullet.owner == BulletOwner::Enemy)
+        .map(|(entity, transform, _, _)| (entity, transform))
+        .collect();
+
+    for (bullet, transform, _, fired_by) in bullet_query
+        .iter()
+        .filter(|(_, _, bullet, _)| bullet.owner == BulletOwner::Player)
+    {
+        let bullet_box = collision_box(transform, false);
+        let Some(index) = enemy_bullets
+            .iter()
+            .position(|(_, enemy_transform)| bullet_box.intersects(&collision_box(enemy_transform, false)))
+        else {
+            continue;
+        };
+
+        let (enemy_bullet, _) = enemy_bullets.swap_remove(index);
+        release_bullet(&mut commands, bullet);
+        release_bullet(&mut commands, enemy_bullet);
+
+        match (fired_by.map_or(0, |fired_by| fired_by.0), coop_score.as_mut()) {
+            (0, _) | (_, None) => score.0 += BULLET_CLASH_POINTS,
+            (_, Some(coop_score)) => coop_score.0 += BULLET_CLASH_POINTS,
+        }
+        debug!(position = ?transform.translation, "Bullets clashed");
+    }
+}
+
+/// Takes every bullet that hit something this frame out of flight
+pub fn despawn_hit_bullets(mut commands: Commands, mut bullet_hit_events: EventReader<BulletHit>) {
+    for hit in bullet_hit_events.read() {
+        release_bullet(&mut commands, hit.bullet);
+    }
+}
+
+/// Applies the damage of every bullet hit this frame. Flies lose one health
+/// per hit and only die (and score, for whoever fired) once it reaches zero,
+/// a player ship takes a hit.
+#[allow(clippy::type_complexity)]
+pub fn apply_bullet_hits(
+    mut commands: Commands,
+    mut bullet_hit_events: EventReader<BulletHit>,
+    mut fly_query: Query<
+        (
+            &Transform,
+            Option<&EnemyKind>,
+            Option<&Points>,
+            Option<&mut Health>,
+            Option<&mut Sprite>,
+        ),
+        With<Fly>,
+    >,
+    shot_query: Query<(Option<&Damage>, Option<&FiredBy>)>,
+    color_palette: Res<ColorsPalette>,
+    mut enemy_killed_events: EventWriter<EnemyKilled>,
+    mut player_hit_events: EventWriter<PlayerHit>,
+) {
+    let mut destroyed = Vec::new();
+
+    for hit in bullet_hit_events.read() {
+        match hit.target_type {
+            EntityType::Fly => {
+                // Already destroyed by another bullet this frame
+                if destroyed.contains(&hit.target) {
+                    continue;
+                }
+
+                let Ok((transform, kind, points, health, sprite)) = fly_query.get_mut(hit.target)
+                else {
+                    continue;
+                };
+
+                let kind = kind.copied().unwrap_or_default();
+                let (damage, fired_by) = shot_query.get(hit.bullet).unwrap_or_default();
+                let damage = damage.map_or(1, |damage| damage.0);
+                if damage_fly(&mut commands, hit.target, kind, damage, health, sprite, &color_palette) {
+                    commands.entity(hit.target).despawn_recursive(); // Remove the fly
+                    destroyed.push(hit.target);
+                    enemy_killed_events.send(EnemyKilled {
+                        entity: hit.target,
+                        points: points.map_or(kind.points(), |points| points.0),
+                        position: transform.translation,
+                        kind,
+                        player: fired_by.map_or(0, |fired_by| fired_by.0),
+                    });
+                }
+            }
+            EntityType::Player => {
+                debug!("Player was hit");
+                // Invulnerable ships are never hit, so this one always flashes
+                commands.entity(hit.target).insert(HitFlash::default());
+                player_hit_events.send(PlayerHit {
+                    cause: hit.bullet,
+                    ship: hit.target,
+                });
+            }
+            EntityType::Bullet => {}
+        }
+    }
+}
+
+/// Takes `damage` points of health off a fly and returns whether that destroyed
+/// it. Flies without `Health` go down in one hit, a fly that survives flashes
+/// and takes the color of the health it has left. Destroying it is left to
+/// the caller, recursively so the mothership's health bar goes with it.
+pub fn damage_fly(
+    commands: &mut Commands,
+    fly: Entity,
+    kind: EnemyKind,
+    damage: u32,
+    health: Option<Mut<Health>>,
+    sprite: Option<Mut<Sprite>>,
+    color_palette: &ColorsPalette,
+) -> bool {
+    let left = match health {
+        Some(mut health) => {
+            health.0 = health.0.saturating_sub(damage);
+            health.0
+        }
+        None => 0,
+    };
+
+    if left > 0 {
+        commands.entity(fly).insert(HitFlash::default());
+        if let Some(mut sprite) = sprite {
+            sprite.color = kind.color_at(left, color_palette);
+        }
+    }
+
+    left == 0
+}
+
+/// Detects flies ramming into a player ship. The fly is destroyed and the
+/// ship takes a hit, unless it is currently invulnerable. The mothership is
+/// too big to go down that way and only takes the ship.
+#[allow(clippy::type_complexity)]
+pub fn fly_player_collision(
+    mut commands: Commands,
+    player_query: Query<
+        (Entity, &Transform, Has<DualFighter>),
+        (Or<(With<Player>, With<CoopPlayer>)>, Without<Invulnerable>),
+    >,
+    fly_query: Query<(Entity, &Transform, Has<Mothership>), With<Fly>>,
+    mut player_hit_events: EventWriter<PlayerHit>,
+    mut hit_last_frame: Local<Vec<Entity>>,
+) {
+    let mut hit_this_frame = Vec::new();
+
+    for (ship, player_transform, dual) in player_query.iter() {
+        let player_box = collision_box(player_transform, dual);
+        for (fly_entity, fly_transform, mothership) in fly_query.iter() {
+            // A despawned fly may still be visible for a frame, don't count it twice.
+            // A fly ramming both ships at once only takes one of them down.
+            if hit_last_frame.contains(&fly_entity) || hit_this_frame.contains(&fly_entity) {
+                continue;
+            }
+
+            if player_box.intersects(&collision_box(fly_transform, false)) {
+                debug!(position = ?player_transform.translation, "Fly rammed the player");
+                if !mothership {
+                    commands.entity(fly_entity).despawn();
+                }
+                player_hit_events.send(PlayerHit {
+                    cause: fly_entity,
+                    ship,
+                });
+                hit_this_frame.push(fly_entity);
+            }
+        }
+    }
+
+    *hit_last_frame = hit_this_frame;
+}
+
+/// Whether a bullet fired by `owner` is allowed to hit a target of the given type
+fn can_hit(owner: BulletOwner, target_type: &EntityType) -> bool {
+    match owner {
+        BulletOwner::Player => *target_type == EntityType::Fly,
+        BulletOwner::Enemy => *target_type == EntityType::Player,
+    }
+}
+
+/// Helper function to determine whether two entities are colliding.
+/// Each entity is treated as an axis-aligned box of `size` centered on its position,
+/// matching how its sprite is drawn. Boxes that only touch at an edge count as a hit.
+/// Positions are on the screen plane, so the layers sprites are drawn on never
+/// change the result: truncate translations before passing them.
+pub fn is_colliding(pos1: Vec2, size1: Vec2, pos2: Vec2, size2: Vec2) -> bool {
+    let box1 = Aabb2d::new(pos1, size1 / 2.0);
+    let box2 = Aabb2d::new(pos2, size2 / 2.0);
+    box1.intersects(&box2)
+}
+
+/// The box every hit check uses for an entity: its sprite, across both ships
+/// for a dual fighter (see `hitbox`). The collision debug drawing shows the
+/// same boxes, so what is drawn is what hits.
+pub fn collision_box(transform: &Transform, dual: bool) -> Aabb2d {
+    let (position, size) = hitbox(transform, dual);
+    Aabb2d::new(position, size / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bullet::apply_velocity, game_rng::GameRng, layer, Velocity};
+    use bevy::{math::bounding::BoundingVolume, time::TimeUpdateStrategy};
+    use rand::Rng;
+    use std::time::Duration;
+
+    /// App running hit detection and resolution for one frame per update
+    fn hit_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ColorsPalette>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<SpatialGrid>()
+            .add_event::<BulletHit>()
+            .add_event::<EnemyKilled>()
+            .add_event::<PlayerHit>()
+            .add_systems(
+                Update,
+                (
+                    (rebuild_spatial_grid, collision).chain(),
+                    (despawn_hit_bullets, apply_bullet_hits).after(collision),
+                ),
+            );
+        app
+    }
+
+    /// Hits found by checking every bullet against every target, the way
+    /// `collision` worked before the spatial grid, one bullet per target
+    fn brute_force_hits(world: &mut World) -> Vec<(Entity, Entity)> {
+        let bullets: Vec<(Entity, Transform, BulletOwner)> = world
+            .query::<(Entity, &Transform, &Bullet)>()
+            .iter(world)
+            .map(|(entity, transform, bullet)| (entity, *transform, bullet.owner))
+            .collect();
+        let mut target_query = world.query_filtered::<
+            (Entity, &Transform, &GameEntity, Has<DualFighter>),
+            (Or<(With<Player>, With<Fly>)>, Without<Invulnerable>),
+        >();
+
+        let mut hits: Vec<(Entity, Entity)> = Vec::new();
+        for (bullet_entity, bullet_transform, owner) in bullets {
+            for (target_entity, target_transform, target, dual) in target_query.iter(world) {
+                let claimed = hits.iter().any(|(_, hit_target)| *hit_target == target_entity);
+                if claimed || !can_hit(owner, &target.entity_type) {
+                    continue;
+                }
+
+                let (position, size) = hitbox(target_transform, dual);
+                let bullet_position = bullet_transform.translation.truncate();
+                if is_colliding(bullet_position, bullet_transform.scale.truncate(), position, size) {
+                    hits.push((bullet_entity, target_entity));
+                    break;
+                }
+            }
+        }
+        hits
+    }
+
+    #[test]
+    fn player_survives_own_bullet() {
+        let mut app = hit_app();
+
+        let position = Vec3::new(0.0, -250.0, 0.0);
+        let player = app
+            .world_mut()
+            .spawn((
+                GameEntity {
+                    id: 1,
+                    entity_type: EntityType::Player,
+                },
+                Player,
+                Transform::from_translation(position),
+            ))
+            .id();
+        let bullet = app
+            .world_mut()
+            .spawn((
+                Bullet { owner: BulletOwner::Player },
+                Transform::from_translation(position),
+            ))
+            .id();
+
+        app.update();
+
+        assert!(app.world().get_entity(player).is_ok());
+        assert!(app.world().get_entity(bullet).is_ok());
+    }
+
+    /// A player bullet and an enemy bullet on top of each other at the center
+    fn spawn_crossing_bullets(app: &mut App) -> (Entity, Entity) {
+        let player_bullet = app
+            .world_mut()
+            .spawn((
+                Bullet { owner: BulletOwner::Player },
+                Transform::default().with_scale(BulletOwner::Player.bullet_scale()),
+            ))
+            .id();
+        let enemy_bullet = app
+            .world_mut()
+            .spawn((
+                Bullet { owner: BulletOwner::Enemy },
+                Transform::default().with_scale(BulletOwner::Enemy.bullet_scale()),
+            ))
+            .id();
+        (player_bullet, enemy_bullet)
+    }
+
+    #[test]
+    fn bullets_pass_through_each_other_by_default() {
+        let mut app = hit_app();
+        let (player_bullet, enemy_bullet) = spawn_crossing_bullets(&mut app);
+
+        app.update();
+
+        assert!(app.world().get_entity(player_bullet).is_ok());
+        assert!(app.world().get_entity(enemy_bullet).is_ok());
+    }
+
+    #[test]
+    fn clashing_bullets_destroy_each_other_for_points() {
+        let mut app = hit_app();
+        app.init_resource::<Score>()
+            .insert_resource(BulletClash(true))
+            .add_systems(
+                Update,
+                clash_bullets.before(collision).run_if(resource_equals(BulletClash(true))),
+            );
+        let (first, enemy_bullet) = spawn_crossing_bullets(&mut app);
+        let second = app
+            .world_mut()
+            .spawn((
+                Bullet { owner: BulletOwner::Player },
+                Transform::default().with_scale(BulletOwner::Player.bullet_scale()),
+            ))
+            .id();
+
+        app.update();
+
+        // Only one of the two player bullets goes down with the enemy bullet
+        assert!(app.world().get_entity(enemy_bullet).is_err());
+        let survivors = [first, second].into_iter().filter(|bullet| app.world().get_entity(*bullet).is_ok());
+        assert_eq!(survivors.count(), 1);
+        assert_eq!(app.world().resource::<Score>().0, BULLET_CLASH_POINTS);
+    }
+
+    #[test]
+    fn boss_survives_first_hit_and_scores_on_second() {
+        let mut app = hit_app();
+
+        let position = Vec3::new(0.0, 200.0, 0.0);
+        let boss = app
+            .world_mut()
+            .spawn((
+                GameEntity {
+                    id: 2,
+                    entity_type: EntityType::Fly,
+                },
+                Fly,
+                EnemyKind::Boss,
+                Health(EnemyKind::Boss.max_health()),
+                Sprite::default(),
+                Transform::from_translation(position).with_scale(Vec3::splat(50.0)),
+            ))
+            .id();
+        let fire = |app: &mut App| {
+            app.world_mut().spawn((
+                Bullet { owner: BulletOwner::Player },
+                Transform::from_translation(position).with_scale(Vec3::splat(10.0)),
+            ));
+            app.update();
+        };
+
+        fire(&mut app);
+        assert!(app.world().get_entity(boss).is_ok());
+        assert!(app.world().resource::<Events<EnemyKilled>>().is_empty());
+
+        fire(&mut app);
+        assert!(app.world().get_entity(boss).is_err());
+        let kills: Vec<u32> = app
+            .world_mut()
+            .resource_mut::<Events<EnemyKilled>>()
+            .drain()
+            .map(|event| event.points)
+            .collect();
+        assert_eq!(kills, vec![EnemyKind::Boss.points()]);
+    }
+
+    #[test]
+    fn two_bullets_on_one_fly_score_once_and_the_second_keeps_flying() {
+        let mut app = hit_app();
+
+        let position = Vec3::new(0.0, 200.0, 0.0);
+        let fly = app
+            .world_mut()
+            .spawn((
+                GameEntity {
+                    id: 2,
+                    entity_type: EntityType::Fly,
+                },
+                Fly,
+                EnemyKind::Bee,
+                Health(1),
+                Transform::from_translation(position).with_scale(Vec3::splat(50.0)),
+            ))
+            .id();
+        let bullets: Vec<Entity> = (0..2)
+            .map(|_| {
+                app.world_mut()
+                    .spawn((
+                        Bullet { owner: BulletOwner::Player },
+                        Transform::from_translation(position).with_scale(Vec3::splat(10.0)),
+                    ))
+                    .id()
+            })
+            .collect();
+
+        app.update();
+
+        assert!(app.world().get_entity(fly).is_err());
+        let remaining = bullets
+            .iter()
+            .filter(|bullet| app.world().get_entity(**bullet).is_ok())
+            .count();
+        assert_eq!(remaining, 1);
+        let kills = app.world().resource::<Events<EnemyKilled>>();
+        assert_eq!(kills.len(), 1);
+        assert_eq!(kills.iter_current_update_events().next().unwrap().points, EnemyKind::Bee.points());
+    }
+
+    #[test]
+    fn grid_finds_the_same_hits_as_checking_every_pair() {
+        let mut total_hits = 0;
+
+        for seed in 0..20 {
+            let mut rng = GameRng::from_seed(seed);
+            let mut app = App::new();
+            app.add_plugins(MinimalPlugins)
+                .init_resource::<DebugSettings>()
+                .init_resource::<SpatialGrid>()
+                .add_event::<BulletHit>()
+                .add_systems(Update, (rebuild_spatial_grid, collision).chain());
+
+            // Even coordinates and sizes put plenty of box edges right on cell borders
+            let random_position = |rng: &mut GameRng| {
+                let x = rng.gen_range(-160..=160) as f32 * 2.0;
+                let y = rng.gen_range(-160..=160) as f32 * 2.0;
+                Vec3::new(x, y, 0.0)
+            };
+
+            for id in 0..40 {
+                let position = random_position(&mut rng);
+                let size = rng.gen_range(15..=35) as f32 * 2.0;
+                app.world_mut().spawn((
+                    GameEntity {
+                        id,
+                        entity_type: EntityType::Fly,
+                    },
+                    Fly,
+                    Transform::from_translation(position).with_scale(Vec3::splat(size)),
+                ));
+            }
+
+            let player = app
+                .world_mut()
+                .spawn((
+                    GameEntity {
+                        id: 40,
+                        entity_type: EntityType::Player,
+                    },
+                    Player,
+                    Transform::from_translation(random_position(&mut rng)).with_scale(Vec3::splat(50.0)),
+                ))
+                .id();
+            if rng.gen_bool(0.5) {
+                app.world_mut().entity_mut(player).insert(DualFighter);
+            }
+
+            for _ in 0..60 {
+                let owner = if rng.gen_bool(0.7) { BulletOwner::Player } else { BulletOwner::Enemy };
+                app.world_mut().spawn((
+                    Bullet { owner },
+                    Transform::from_translation(random_position(&mut rng)).with_scale(Vec3::splat(10.0)),
+                ));
+            }
+
+            let expected = brute_force_hits(app.world_mut());
+            app.update();
+            let found: Vec<(Entity, Entity)> = app
+                .world_mut()
+                .resource_mut::<Events<BulletHit>>()
+                .drain()
+                .map(|hit| (hit.bullet, hit.target))
+                .collect();
+
+            assert_eq!(found, expected, "seed {}", seed);
+            total_hits += found.len();
+        }
+
+        assert!(total_hits > 0);
+    }
+
+    #[test]
+    fn fly_ramming_player_is_destroyed_and_hits_once() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_event::<PlayerHit>()
+            .add_systems(Update, fly_player_collision);
+
+        let position = Vec3::new(0.0, -250.0, 0.0);
+        app.world_mut().spawn((
+            GameEntity {
+                id: 1,
+                entity_type: EntityType::Player,
+            },
+            Player,
+            Transform::from_translation(position).with_scale(Vec3::splat(50.0)),
+        ));
+        let fly = app
+            .world_mut()
+            .spawn((
+                GameEntity {
+                    id: 2,
+                    entity_type: EntityType::Fly,
+                },
+                Fly,
+                Transform::from_translation(position).with_scale(Vec3::splat(50.0)),
+            ))
+            .id();
+
+        app.update();
+        app.update();
+
+        assert!(app.world().get_entity(fly).is_err());
+        let hits = app.world().resource::<Events<PlayerHit>>();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn fast_bullet_does_not_tunnel_through_a_fly() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ColorsPalette>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<SpatialGrid>()
+            .insert_resource(Time::<Fixed>::from_hz(60.0))
+            // A slow 10 fps frame: one step per frame would jump 240 units, well past the fly
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .add_event::<BulletHit>()
+            .add_event::<EnemyKilled>()
+            .add_event::<PlayerHit>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_velocity,
+                    rebuild_spatial_grid,
+                    collision,
+                    (despawn_hit_bullets, apply_bullet_hits),
+                )
+                    .chain(),
+            );
+
+        let fly = app
+            .world_mut()
+            .spawn((
+                GameEntity {
+                    id: 1,
+                    entity_type: EntityType::Fly,
+                },
+                Fly,
+                Transform::from_xyz(0.0, 200.0, 0.0).with_scale(Vec3::splat(50.0)),
+            ))
+            .id();
+        app.world_mut().spawn((
+            Bullet { owner: BulletOwner::Player },
+            Velocity(Vec2::new(0.0, 2400.0)),
+            Transform::from_scale(Vec3::splat(10.0)),
+        ));
+
+        for _ in 0..5 {
+            app.update();
+        }
+
+        assert!(app.world().get_entity(fly).is_err());
+    }
+
+    #[test]
+    fn colliding_when_edges_touch() {
+        // A 10x10 bullet whose right edge sits exactly on a 50x50 fly's left edge
+        let bullet = Vec2::new(-30.0, 0.0);
+        let fly = Vec2::ZERO;
+        assert!(is_colliding(bullet, Vec2::splat(10.0), fly, Vec2::splat(50.0)));
+    }
+
+    #[test]
+    fn colliding_when_fully_overlapping() {
+        let bullet = Vec2::new(5.0, -5.0);
+        let fly = Vec2::ZERO;
+        assert!(is_colliding(bullet, Vec2::splat(10.0), fly, Vec2::splat(50.0)));
+        assert!(is_colliding(fly, Vec2::splat(50.0), bullet, Vec2::splat(10.0)));
+    }
+
+    #[test]
+    fn not_colliding_when_clearly_apart() {
+        let fly = Vec2::ZERO;
+        assert!(!is_colliding(Vec2::new(-31.0, 0.0), Vec2::splat(10.0), fly, Vec2::splat(50.0)));
+        assert!(!is_colliding(Vec2::new(0.0, 100.0), Vec2::splat(10.0), fly, Vec2::splat(50.0)));
+        assert!(!is_colliding(Vec2::new(40.0, 40.0), Vec2::splat(10.0), fly, Vec2::splat(50.0)));
+    }
+
+    #[test]
+    fn layers_do_not_change_what_collides() {
+        let on_layer = Transform::from_xyz(10.0, 20.0, layer::PLAYER).with_scale(Vec3::splat(50.0));
+        let flat = Transform::from_xyz(10.0, 20.0, 0.0).with_scale(Vec3::splat(50.0));
+        assert_eq!(hitbox(&on_layer, true), hitbox(&flat, true));
+
+        // Each bullet just touches the edge of its target on screen, drawn on another layer
+        let mut app = hit_app();
+        let fly = app
+            .world_mut()
+            .spawn((
+                GameEntity {
+                    id: 1,
+                    entity_type: EntityType::Fly,
+                },
+                Fly,
+                Transform::from_xyz(0.0, 100.0, layer::ENEMIES).with_scale(Vec3::splat(50.0)),
+            ))
+            .id();
+        let player = app
+            .world_mut()
+            .spawn((
+                GameEntity {
+                    id: 2,
+                    entity_type: EntityType::Player,
+                },
+                Player,
+                Transform::from_xyz(0.0, -250.0, layer::PLAYER).with_scale(Vec3::splat(50.0)),
+            ))
+            .id();
+        let player_bullet = app
+            .world_mut()
+            .spawn((
+                Bullet { owner: BulletOwner::Player },
+                Transform::from_xyz(0.0, 70.0, layer::BULLETS).with_scale(Vec3::splat(10.0)),
+            ))
+            .id();
+        let enemy_bullet = app
+            .world_mut()
+            .spawn((
+                Bullet { owner: BulletOwner::Enemy },
+                Transform::from_xyz(30.0, -250.0, layer::BULLETS).with_scale(Vec3::splat(10.0)),
+            ))
+            .id();
+        app.update();
+
+        assert!(app.world().get_entity(fly).is_err());
+        assert!(app.world().get_entity(player_bullet).is_err());
+        assert!(app.world().get_entity(enemy_bullet).is_err());
+        assert!(app.world().get_entity(player).is_ok());
+        let hits: Vec<EntityType> = app
+            .world()
+            .resource::<Events<BulletHit>>()
+            .iter_current_update_events()
+            .map(|hit| hit.target_type)
+            .collect();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.contains(&EntityType::Fly) && hits.contains(&EntityType::Player));
+    }
+
+    #[test]
+    fn collision_box_is_the_sprite_and_spans_a_dual_fighter() {
+        let transform = Transform::from_xyz(10.0, 20.0, 0.0).with_scale(Vec3::new(50.0, 40.0, 1.0));
+        let single = collision_box(&transform, false);
+        assert_eq!((single.center(), single.half_size()), (Vec2::new(10.0, 20.0), Vec2::new(25.0, 20.0)));
+
+        let (position, size) = hitbox(&transform, true);
+        let dual = collision_box(&transform, true);
+        assert_eq!((dual.center(), dual.half_size() * 2.0), (position, size));
+    }
+}
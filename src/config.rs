@@ -0,0 +1,489 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use ron::Value;
+use serde::de::DeserializeOwned;
+use crate::{
+    bullet::BULLET_SPEED,
+    collision::BulletClash,
+    fly_logic::{FlySpawnTimer, FLY_SCALE, FLY_SPAWN_INTERVAL},
+    player_input::{ShootCooldown, PLAYER_SPEED, SHOOT_COOLDOWN},
+    palette_preset::PalettePreset,
+    wave::NEXT_WAVE_DELAY,
+    BulletSpeed, ColorsPalette, Fly, PlayField,
+};
+
+/// Tuning file read at startup unless `--config` names another one
+pub const GAME_CONFIG_PATH: &str = "assets/config/game.ron";
+
+/// Folder the asset server reads from. Only config files inside it can be hot-reloaded.
+const ASSETS_DIR: &str = "assets";
+
+/// Colors and gameplay tunables read from a RON file, so they can be tweaked
+/// without recompiling. Built with the `hot_reload` feature, edits to the file
+/// are applied while the game runs.
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let file = app.world().get_resource::<GameConfigFile>().cloned().unwrap_or_default();
+        let config = GameConfig::load(&file.0);
+
+        app.insert_resource(config.palette_preset.palette(&config.palette))
+            .insert_resource(config.palette_preset)
+            .insert_resource(BulletSpeed(config.bullet_speed))
+            .insert_resource(BulletClash(config.bullet_clash))
+            .insert_resource(config.play_field)
+            .insert_resource(config)
+            .insert_resource(file);
+
+        if cfg!(feature = "hot_reload") {
+            app.init_asset::<GameConfigAsset>()
+                .register_asset_loader(GameConfigLoader)
+                .add_systems(Startup, watch_config_file)
+                .add_systems(Update, reload_config);
+        }
+    }
+}
+
+/// Config file the game was started with
+#[derive(Resource, Clone)]
+pub struct GameConfigFile(pub PathBuf);
+
+impl Default for GameConfigFile {
+    fn default() -> Self {
+        GameConfigFile(PathBuf::from(GAME_CONFIG_PATH))
+    }
+}
+
+/// Every value the config file can set. Anything the file leaves out keeps
+/// the compiled default. The play field size only changes on restart, every
+/// other value can be hot-reloaded.
+#[derive(Resource, Clone)]
+pub struct GameConfig {
+    pub palette: ColorsPalette,
+    pub palette_preset: PalettePreset, // Colors actually drawn, `Classic` being `palette`
+    pub play_field: PlayField,
+    pub bullet_speed: f32,           // Units per second
+    pub player_speed: f32,           // Units per second
+    pub fly_scale: f32,              // Size of a fly sprite in units
+    pub shoot_cooldown: f32,         // Seconds between two player shots
+    pub fly_spawn_interval: f32,     // Seconds between two groups of a wave
+    pub next_wave_delay: f32,        // Seconds between a cleared wave and the next one
+    pub bullet_clash: bool,          // Whether player bullets shoot down enemy bullets
+    pub interpolation: bool,         // Whether sprites are drawn between simulation steps
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            palette: ColorsPalette::default(),
+            palette_preset: PalettePreset::default(),
+            play_field: PlayField::default(),
+            bullet_speed: BULLET_SPEED,
+            player_speed: PLAYER_SPEED,
+            fly_scale: FLY_SCALE,
+            shoot_cooldown: SHOOT_COOLDOWN,
+            fly_spawn_interval: FLY_SPAWN_INTERVAL,
+            next_wave_delay: NEXT_WAVE_DELAY,
+            bullet_clash: false,
+            interpolation: true,
+        }
+    }
+}
+
+/// Why a config file couldn't be used
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(io::Error),
+    Syntax(ron::error::SpannedError),
+    NotAStruct(Option<String>), // The file, or the named section, isn't a `( ... )` struct
+    UnknownField(String),
+    InvalidValue { field: String, error: ron::Error },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read(error) => write!(f, "could not be read: {}", error),
+            ConfigError::Syntax(error) => write!(f, "not valid RON: {}", error),
+            ConfigError::NotAStruct(None) => f.write_str("expected a `( ... )` struct of settings"),
+            ConfigError::NotAStruct(Some(field)) => write!(f, "`{}` must be a `( ... )` struct", field),
+            ConfigError::UnknownField(field) => write!(f, "unknown field `{}`", field),
+            ConfigError::InvalidValue { field, error } => write!(f, "invalid value for `{}`: {}", field, error),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl GameConfig {
+    /// Reads the config at `path`. A missing file keeps every default, a file
+    /// that can't be used is reported and keeps every default as well, so a bad
+    /// tuning never stops the game from starting.
+    pub fn load(path: &Path) -> Self {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                warn!("No game config at {}, using the defaults: {}", path.display(), error);
+                return GameConfig::default();
+            }
+        };
+
+        match GameConfig::from_ron(&contents) {
+            Ok((config, missing)) => {
+                if !missing.is_empty() {
+                    warn!("{} leaves out {}, using their defaults", path.display(), missing.join(", "));
+                }
+                config
+            }
+            Err(error) => {
+                error!("Ignoring game config {}: {}", path.display(), error);
+                GameConfig::default()
+            }
+        }
+    }
+
+    /// Applies the fields in `contents` to the defaults. Also returns the
+    /// fields that were left out, palette colors as `palette.<name>`.
+    pub fn from_ron(contents: &str) -> Result<(GameConfig, Vec<String>), ConfigError> {
+        let value: Value = ron::from_str(contents).map_err(ConfigError::Syntax)?;
+        let mut fields = struct_fields(value, None)?;
+        let mut config = GameConfig::default();
+        let mut missing = Vec::new();
+
+        match fields.remove("palette") {
+            Some(value) => {
+                let mut colors = struct_fields(value, Some("palette"))?;
+                let palette = &mut config.palette;
+                for (name, color) in [
+                    ("player", &mut palette.player_color),
+                    ("fly", &mut palette.fly_color),
+                    ("bee", &mut palette.bee_color),
+                    ("butterfly", &mut palette.butterfly_color),
+                    ("boss", &mut palette.boss_color),
+                    ("boss_damaged", &mut palette.boss_damaged_color),
+                    ("mothership", &mut palette.mothership_color),
+                    ("mothership_enraged", &mut palette.mothership_enraged_color),
+                    ("player_bullet", &mut palette.player_bullet_color),
+                    ("enemy_bullet", &mut palette.enemy_bullet_color),
+                    ("powerup", &mut palette.powerup_color),
+                    ("bomb", &mut palette.bomb_color),
+                    ("shield", &mut palette.shield_color),
+                    ("coop_player", &mut palette.coop_player_color),
+                    ("hit_flash", &mut palette.hit_flash_color),
+                    ("background", &mut palette.background_color),
+                ] {
+                    let field = format!("palette.{}", name);
+                    match colors.remove(name) {
+                        Some(value) => {
+                            let (r, g, b): (f32, f32, f32) = parse_field(&field, value)?;
+                            *color = Color::srgb(r, g, b);
+                        }
+                        None => missing.push(field),
+                    }
+                }
+                if let Some(name) = colors.into_keys().next() {
+                    return Err(ConfigError::UnknownField(format!("palette.{}", name)));
+                }
+            }
+            None => missing.push("palette".to_string()),
+        }
+
+        match fields.remove("palette_preset") {
+            Some(value) => {
+                let name: String = parse_field("palette_preset", value)?;
+                config.palette_preset = name.parse().map_err(|_| ConfigError::InvalidValue {
+                    field: "palette_preset".to_string(),
+                    error: ron::Error::Message(format!("no palette preset named `{}`", name)),
+                })?;
+            }
+            None => missing.push("palette_preset".to_string()),
+        }
+
+        match fields.remove("play_field") {
+            Some(value) => {
+                let (width, height) = parse_field("play_field", value)?;
+                config.play_field = PlayField { width, height };
+            }
+            None => missing.push("play_field".to_string()),
+        }
+
+        for (name, tunable) in [
+            ("bullet_speed", &mut config.bullet_speed),
+            ("player_speed", &mut config.player_speed),
+            ("fly_scale", &mut config.fly_scale),
+            ("shoot_cooldown", &mut config.shoot_cooldown),
+            ("fly_spawn_interval", &mut config.fly_spawn_interval),
+            ("next_wave_delay", &mut config.next_wave_delay),
+        ] {
+            match fields.remove(name) {
+                Some(value) => *tunable = parse_field(name, value)?,
+                None => missing.push(name.to_string()),
+            }
+        }
+
+        for (name, switch) in [
+            ("bullet_clash", &mut config.bullet_clash),
+            ("interpolation", &mut config.interpolation),
+        ] {
+            match fields.remove(name) {
+                Some(value) => *switch = parse_field(name, value)?,
+                None => missing.push(name.to_string()),
+            }
+        }
+
+        if let Some(name) = fields.into_keys().next() {
+            return Err(ConfigError::UnknownField(name));
+        }
+
+        Ok((config, missing))
+    }
+}
+
+/// Splits a `( ... )` struct into its fields by name. `section` names the
+/// field holding it, for nested structs.
+fn struct_fields(value: Value, section: Option<&str>) -> Result<BTreeMap<String, Value>, ConfigError> {
+    let not_a_struct = || ConfigError::NotAStruct(section.map(str::to_string));
+    let Value::Map(map) = value else {
+        return Err(not_a_struct());
+    };
+
+    map.iter()
+        .map(|(key, value)| match key {
+            Value::String(name) => Ok((name.clone(), value.clone())),
+            _ => Err(not_a_struct()),
+        })
+        .collect()
+}
+
+/// Converts the value of `field`, naming the field if it has the wrong type
+fn parse_field<T: DeserializeOwned>(field: &str, value: Value) -> Result<T, ConfigError> {
+    value.into_rust().map_err(|error| ConfigError::InvalidValue {
+        field: field.to_string(),
+        error,
+    })
+}
+
+/// A config file as read by the asset server, which can watch it for edits
+#[derive(Asset, TypePath)]
+pub struct GameConfigAsset {
+    pub config: GameConfig,
+    pub missing: Vec<String>, // Fields the file leaves out
+}
+
+/// Parses config files for the asset server
+struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+    type Asset = GameConfigAsset;
+    type Settings = ();
+    type Error = ConfigError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<GameConfigAsset, ConfigError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.map_err(ConfigError::Read)?;
+        let contents = String::from_utf8(bytes)
+            .map_err(|error| ConfigError::Read(io::Error::new(io::ErrorKind::InvalidData, error)))?;
+        let (config, missing) = GameConfig::from_ron(&contents)?;
+        Ok(GameConfigAsset { config, missing })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+/// The config file as watched by the asset server
+#[derive(Resource)]
+struct GameConfigHandle(Handle<GameConfigAsset>);
+
+/// Asks the asset server for the config file so edits to it are noticed. A
+/// file outside the assets folder can't be watched and needs a restart instead.
+fn watch_config_file(mut commands: Commands, asset_server: Res<AssetServer>, file: Res<GameConfigFile>) {
+    let Ok(asset_path) = file.0.strip_prefix(ASSETS_DIR) else {
+        info!("{} is outside the assets folder, edits to it need a restart", file.0.display());
+        return;
+    };
+
+    info!("Watching {} for tuning changes", file.0.display());
+    commands.insert_resource(GameConfigHandle(asset_server.load(asset_path.to_path_buf())));
+}
+
+/// Applies an edited config file while the game runs: the palette and its
+/// preset (re-tinting sprites already on screen), bullet speed, bullet clash,
+/// shot cooldown, fly spawn interval and fly size. The other values are read
+/// from `GameConfig` whenever they are used, so they apply from their next
+/// use. A file that can't be parsed is reported by the asset server and
+/// changes nothing.
+#[allow(clippy::too_many_arguments)]
+fn reload_config(
+    mut asset_events: EventReader<AssetEvent<GameConfigAsset>>,
+    handle: Option<Res<GameConfigHandle>>,
+    config_assets: Res<Assets<GameConfigAsset>>,
+    mut config: ResMut<GameConfig>,
+    mut color_palette: ResMut<ColorsPalette>,
+    mut palette_preset: ResMut<PalettePreset>,
+    mut bullet_speed: ResMut<BulletSpeed>,
+    mut bullet_clash: ResMut<BulletClash>,
+    mut shoot_cooldown: ResMut<ShootCooldown>,
+    mut spawn_timer: ResMut<FlySpawnTimer>,
+    mut sprite_query: Query<&mut Sprite>,
+    mut fly_query: Query<&mut Transform, With<Fly>>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+
+    if asset_events.read().filter(|event| event.is_modified(&handle.0)).count() == 0 {
+        return;
+    }
+
+    let Some(reloaded) = config_assets.get(&handle.0) else {
+        return;
+    };
+
+    if !reloaded.missing.is_empty() {
+        warn!("Reloaded config leaves out {}, using their defaults", reloaded.missing.join(", "));
+    }
+
+    let reloaded = keep_restart_only_values(&config, &reloaded.config);
+    let palette = reloaded.palette_preset.palette(&reloaded.palette);
+
+    for mut sprite in sprite_query.iter_mut() {
+        if let Some(color) = retinted(sprite.color, &color_palette, &palette) {
+            sprite.color = color;
+        }
+    }
+
+    if reloaded.fly_scale != config.fly_scale {
+        for mut transform in fly_query.iter_mut() {
+            transform.scale = Vec3::splat(reloaded.fly_scale);
+        }
+    }
+
+    *color_palette = palette;
+    palette_preset.set_if_neq(reloaded.palette_preset);
+    bullet_speed.0 = reloaded.bullet_speed;
+    bullet_clash.set_if_neq(BulletClash(reloaded.bullet_clash));
+    shoot_cooldown.set_duration(reloaded.shoot_cooldown);
+    spawn_timer.0.set_duration(Duration::from_secs_f32(reloaded.fly_spawn_interval));
+    *config = reloaded;
+    info!("Applied the edited game config");
+}
+
+/// `reloaded` with the values that only change on restart put back to the
+/// `current` ones, logging each one that was skipped
+fn keep_restart_only_values(current: &GameConfig, reloaded: &GameConfig) -> GameConfig {
+    let mut config = reloaded.clone();
+
+    if config.play_field != current.play_field {
+        info!(
+            "The play field size only changes on restart, keeping {}x{}",
+            current.play_field.width, current.play_field.height
+        );
+        config.play_field = current.play_field;
+    }
+
+    config
+}
+
+/// Every color of the palette, in a fixed order
+pub(crate) fn palette_colors(palette: &ColorsPalette) -> [Color; 16] {
+    [
+        palette.player_color,
+        palette.fly_color,
+        palette.bee_color,
+        palette.butterfly_color,
+        palette.boss_color,
+        palette.boss_damaged_color,
+        palette.mothership_color,
+        palette.mothership_enraged_color,
+        palette.player_bullet_color,
+        palette.enemy_bullet_color,
+        palette.powerup_color,
+        palette.bomb_color,
+        palette.shield_color,
+        palette.coop_player_color,
+        palette.hit_flash_color,
+        palette.background_color,
+    ]
+}
+
+/// New color for a sprite drawn in `color`, if that is a palette color that
+/// changed. Sprites are matched by their exact color, so mid-fade effects
+/// keep theirs and are never re-tinted.
+pub(crate) fn retinted(color: Color, old: &ColorsPalette, new: &ColorsPalette) -> Option<Color> {
+    palette_colors(old)
+        .into_iter()
+        .zip(palette_colors(new))
+        .find(|(old, new)| *old == color && old != new)
+        .map(|(_, new)| new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_fields_override_and_the_rest_are_reported_missing() {
+        let (config, missing) =
+            GameConfig::from_ron("(bullet_speed: 450.0, next_wave_delay: 3.0, palette: (bee: (0.0, 0.0, 1.0)))")
+                .unwrap();
+
+        assert_eq!(config.bullet_speed, 450.0);
+        assert_eq!(config.next_wave_delay, 3.0);
+        assert_eq!(config.palette.bee_color, Color::srgb(0.0, 0.0, 1.0));
+        assert_eq!(config.player_speed, GameConfig::default().player_speed);
+        assert!(missing.contains(&"player_speed".to_string()));
+        assert!(missing.contains(&"palette.boss".to_string()));
+        assert!(!missing.contains(&"bullet_speed".to_string()));
+    }
+
+    #[test]
+    fn bad_values_name_the_offending_field() {
+        let error = GameConfig::from_ron(r#"(player_speed: "fast")"#).unwrap_err();
+        assert!(error.to_string().contains("`player_speed`"), "{}", error);
+
+        let error = GameConfig::from_ron("(palette: (boss: (1.0, 0.0)))").unwrap_err();
+        assert!(error.to_string().contains("`palette.boss`"), "{}", error);
+
+        let error = GameConfig::from_ron(r#"(palette_preset: "Sepia")"#).unwrap_err();
+        assert!(error.to_string().contains("`palette_preset`"), "{}", error);
+
+        let error = GameConfig::from_ron("(bulet_speed: 1.0)").unwrap_err();
+        assert!(error.to_string().contains("`bulet_speed`"), "{}", error);
+
+        assert!(matches!(GameConfig::from_ron("(fly_scale: "), Err(ConfigError::Syntax(_))));
+    }
+
+    #[test]
+    fn reload_keeps_the_play_field_and_retints_only_changed_colors() {
+        let current = GameConfig::default();
+        let (reloaded, _) = GameConfig::from_ron(
+            "(play_field: (800.0, 600.0), bullet_speed: 500.0, palette: (bee: (0.0, 0.0, 1.0)))",
+        )
+        .unwrap();
+
+        let applied = keep_restart_only_values(&current, &reloaded);
+        assert_eq!(applied.play_field, current.play_field);
+        assert_eq!(applied.bullet_speed, 500.0);
+
+        let (old, new) = (&current.palette, &applied.palette);
+        assert_eq!(retinted(old.bee_color, old, new), Some(Color::srgb(0.0, 0.0, 1.0)));
+        assert_eq!(retinted(old.boss_color, old, new), None);
+        assert_eq!(retinted(old.bee_color.with_alpha(0.5), old, new), None);
+    }
+}
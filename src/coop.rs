@@ -0,0 +1,388 @@
+use bevy::prelude::*;
+use crate::{
+    bullet::BulletPool,
+    config::GameConfig,
+    difficulty::DifficultyConfig,
+    game_assets::GameAssets,
+    game_state::{GameState, PauseState},
+    hud::score_label,
+    keybindings::KeyBindings,
+    layer,
+    lives::{DeathSequence, Invulnerable, Lives},
+    player_input::{clamp_player_to_bounds, shoot_bullet, PlayerSpeed, MAX_PLAYER_BULLETS},
+    resize::FieldAnchor,
+    run_intro::RunIntro,
+    sound::{Sfx, SfxEvent},
+    wave::Wave,
+    Bullet, BulletSpeed, ColorsPalette, EnemyKilled, EntityIdCounter, EntityType, FiredBy, GameEntity,
+    InputSet, PlayerHit,
+};
+
+/// Where the co-op ship (re)appears, to the right of the first one
+pub const COOP_START: Vec3 = Vec3::new(100.0, -250.0, 0.0);
+
+/// Index of the co-op ship's player in `FiredBy` and `EnemyKilled`
+pub const COOP_PLAYER: usize = 1;
+
+/// Stick deflection below which the second gamepad doesn't steer
+const STICK_DEAD_ZONE: f32 = 0.2;
+
+/// Local co-op: a second ship on screen alongside the first, on its own keys
+/// or the second gamepad, with its own lives and score. The run only ends once
+/// both ships are out of lives.
+pub struct CoopPlugin;
+
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Menu), end_coop)
+            .add_systems(OnEnter(GameState::Playing), start_coop_run)
+            .add_systems(
+                Update,
+                (
+                    drive_coop_player
+                        .in_set(InputSet)
+                        .before(clamp_player_to_bounds)
+                        .run_if(not(resource_exists::<DeathSequence>))
+                        .run_if(not(resource_exists::<RunIntro>)),
+                    handle_coop_hit,
+                    respawn_coop_player.after(handle_coop_hit),
+                    update_coop_score,
+                    update_coop_text.after(handle_coop_hit).after(update_coop_score),
+                )
+                    .run_if(in_state(PauseState::Running).and(resource_exists::<CoopRun>)),
+            );
+    }
+}
+
+/// Present while the run in progress is a co-op one, picked on the title screen
+#[derive(Resource)]
+pub struct CoopRun;
+
+/// Ships the co-op player has left, including the one currently in play
+#[derive(Resource, Debug, PartialEq)]
+pub struct CoopLives(pub u32);
+
+/// Points scored by the co-op ship this run
+#[derive(Resource, Debug, Default, PartialEq)]
+pub struct CoopScore(pub u32);
+
+/// Marks the co-op ship. It is no `Player`: systems meant for the first ship
+/// never pick it up. Ships never collide with each other, and player bullets
+/// only ever hit flies.
+#[derive(Component)]
+pub struct CoopPlayer;
+
+/// Minimum time between two shots of the co-op ship
+#[derive(Component)]
+struct CoopGun(Timer);
+
+/// Shows the co-op player's score and lives
+#[derive(Component)]
+struct CoopText;
+
+/// Back on the menu the next run is a single ship one again
+fn end_coop(mut commands: Commands) {
+    commands.remove_resource::<CoopRun>();
+}
+
+/// Brings in the co-op ship with a full set of lives at the start of a co-op
+/// run, and clears what a previous one left behind otherwise
+fn start_coop_run(
+    mut commands: Commands,
+    coop_run: Option<Res<CoopRun>>,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    difficulty: Res<DifficultyConfig>,
+    config: Res<GameConfig>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+) {
+    if coop_run.is_none() {
+        commands.remove_resource::<CoopLives>();
+        commands.remove_resource::<CoopScore>();
+        return;
+    }
+
+    let coop_lives = CoopLives(difficulty.starting_lives);
+    spawn_coop_text(&mut commands, &game_assets, &color_palette, &coop_lives, &CoopScore(0));
+    commands.insert_resource(coop_lives);
+    commands.insert_resource(CoopScore(0));
+    spawn_coop_player(&mut commands, &mut entity_ids, &config, &color_palette, &game_assets);
+}
+
+/// Spawns the co-op ship at `COOP_START`, ready to fire
+fn spawn_coop_player(
+    commands: &mut Commands,
+    entity_ids: &mut EntityIdCounter,
+    config: &GameConfig,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+) -> Entity {
+    let mut gun = Timer::from_seconds(config.shoot_cooldown, TimerMode::Once);
+    gun.tick(gun.duration());
+
+    commands
+        .spawn((
+            CoopPlayer,
+            CoopGun(gun),
+            GameEntity {
+                id: entity_ids.next_id(),
+                entity_type: EntityType::Player,
+            },
+            StateScoped(GameState::Playing),
+            GameAssets::sprite(&game_assets.player, color_palette.coop_player_color),
+            Transform {
+                translation: COOP_START.with_z(layer::PLAYER),
+                scale: Vec3::splat(50.0),
+                ..Default::default()
+            },
+        ))
+        .id()
+}
+
+/// Spawns the co-op player's score and lives along the bottom of the play
+/// field, under the first player's HUD bar
+fn spawn_coop_text(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    color_palette: &ColorsPalette,
+    coop_lives: &CoopLives,
+    coop_score: &CoopScore,
+) {
+    commands
+        .spawn((
+            StateScoped(GameState::Playing),
+            FieldAnchor { margin: 10.0 },
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                CoopText,
+                Text::new(coop_label(coop_score, coop_lives)),
+                TextFont {
+                    font: game_assets.font.clone(),
+                    font_size: 24.0,
+                    ..Default::default()
+                },
+                TextColor(color_palette.coop_player_color),
+            ));
+        });
+}
+
+fn coop_label(coop_score: &CoopScore, coop_lives: &CoopLives) -> String {
+    format!("{}  Lives: {}", score_label(coop_score.0, COOP_PLAYER, 2), coop_lives.0)
+}
+
+/// Moves the co-op ship with its keys or the second gamepad's d-pad and left
+/// stick, and fires on its fire key or the gamepad's south button. The first
+/// gamepad is left to the first ship. It has its own cooldown and bullet cap.
+#[allow(clippy::too_many_arguments)]
+fn drive_coop_player(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    gamepads: Query<&Gamepad>,
+    mut ship_query: Query<(&mut Transform, &mut CoopGun), With<CoopPlayer>>,
+    bullet_query: Query<&FiredBy, With<Bullet>>,
+    player_speed: Res<PlayerSpeed>,
+    bullet_speed: Res<BulletSpeed>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    mut bullet_pool: ResMut<BulletPool>,
+    mut sfx_events: EventWriter<SfxEvent>,
+    time: Res<Time>,
+) {
+    let Ok((mut transform, mut gun)) = ship_query.get_single_mut() else {
+        return; // Destroyed, back on the next wave
+    };
+    let gamepad = gamepads.iter().nth(1);
+
+    let mut axis: f32 = 0.0;
+    if keyboard_input.any_pressed(key_bindings.coop_left.iter().copied()) {
+        axis -= 1.0;
+    }
+    if keyboard_input.any_pressed(key_bindings.coop_right.iter().copied()) {
+        axis += 1.0;
+    }
+    if let Some(gamepad) = gamepad {
+        if gamepad.pressed(GamepadButton::DPadLeft) {
+            axis -= 1.0;
+        }
+        if gamepad.pressed(GamepadButton::DPadRight) {
+            axis += 1.0;
+        }
+        let stick = gamepad.left_stick().x;
+        if stick.abs() > STICK_DEAD_ZONE {
+            axis += stick;
+        }
+    }
+    transform.translation.x += axis.clamp(-1.0, 1.0) * player_speed.0 * time.delta_secs();
+
+    gun.0.tick(time.delta());
+
+    let fire_pressed = keyboard_input.any_just_pressed(key_bindings.coop_fire.iter().copied())
+        || gamepad.is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::South));
+    let coop_bullets = bullet_query
+        .iter()
+        .filter(|fired_by| fired_by.0 == COOP_PLAYER)
+        .count();
+    if fire_pressed && gun.0.finished() && coop_bullets < MAX_PLAYER_BULLETS {
+        gun.0.reset();
+        let bullet = shoot_bullet(
+            &mut commands,
+            &mut bullet_pool,
+            transform.translation,
+            bullet_speed.0,
+            &color_palette,
+            &game_assets,
+        );
+        commands.entity(bullet).insert(FiredBy(COOP_PLAYER));
+        sfx_events.send(SfxEvent(Sfx::Shoot));
+    }
+}
+
+/// Takes a co-op life when the co-op ship is hit. The run ends once neither
+/// ship has lives left.
+pub fn handle_coop_hit(
+    mut commands: Commands,
+    mut player_hit_events: EventReader<PlayerHit>,
+    ship_query: Query<Entity, (With<CoopPlayer>, Without<Invulnerable>)>,
+    mut coop_lives: ResMut<CoopLives>,
+    lives: Res<Lives>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Several hits in the same frame only cost a single ship
+    let Some(hit) = player_hit_events.read().filter(|hit| ship_query.contains(hit.ship)).last() else {
+        return;
+    };
+
+    commands.entity(hit.ship).despawn_recursive();
+    coop_lives.0 = coop_lives.0.saturating_sub(1);
+    info!(lives = coop_lives.0, cause = ?hit.cause, "Co-op ship destroyed");
+
+    if coop_lives.0 == 0 && lives.0 == 0 {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Brings a destroyed co-op ship back when the next wave starts, or right away
+/// once the first ship is out of lives and nobody else is left to play
+#[allow(clippy::too_many_arguments)]
+fn respawn_coop_player(
+    mut commands: Commands,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    ship_query: Query<(), With<CoopPlayer>>,
+    coop_lives: Res<CoopLives>,
+    lives: Res<Lives>,
+    wave: Res<Wave>,
+    config: Res<GameConfig>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+) {
+    if coop_lives.0 == 0 || !ship_query.is_empty() {
+        return;
+    }
+    if !wave.is_changed() && lives.0 > 0 {
+        return;
+    }
+
+    let ship = spawn_coop_player(&mut commands, &mut entity_ids, &config, &color_palette, &game_assets);
+    commands.entity(ship).insert(Invulnerable::default());
+    info!(wave = wave.0, "Co-op ship back");
+}
+
+/// Adds the points of every enemy the co-op ship killed to its score
+fn update_coop_score(mut enemy_killed_events: EventReader<EnemyKilled>, mut coop_score: ResMut<CoopScore>) {
+    for event in enemy_killed_events.read().filter(|event| event.player == COOP_PLAYER) {
+        coop_score.0 += event.points;
+    }
+}
+
+/// Refreshes the co-op text whenever the co-op score or lives change
+fn update_coop_text(
+    coop_score: Res<CoopScore>,
+    coop_lives: Res<CoopLives>,
+    mut query: Query<&mut Text, With<CoopText>>,
+) {
+    if !coop_score.is_changed() && !coop_lives.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.0 = coop_label(&coop_score, &coop_lives);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fly_logic::EnemyKind, update_score, Score};
+
+    #[test]
+    fn kills_are_credited_to_the_ship_that_fired() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Score(0))
+            .init_resource::<CoopScore>()
+            .add_event::<EnemyKilled>()
+            .add_systems(Update, (update_score, update_coop_score));
+
+        for player in [0, COOP_PLAYER, COOP_PLAYER] {
+            app.world_mut().send_event(EnemyKilled {
+                entity: Entity::PLACEHOLDER,
+                points: 100,
+                position: Vec3::ZERO,
+                kind: EnemyKind::Bee,
+                player,
+            });
+        }
+        app.update();
+
+        assert_eq!(app.world().resource::<Score>().0, 100);
+        assert_eq!(app.world().resource::<CoopScore>().0, 200);
+    }
+
+    fn hit_app(lives: u32, coop_lives: u32) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<GameState>()
+            .insert_resource(Lives(lives))
+            .insert_resource(CoopLives(coop_lives))
+            .add_event::<PlayerHit>()
+            .add_systems(Update, handle_coop_hit);
+        let ship = app.world_mut().spawn((CoopPlayer, Transform::default())).id();
+        app.world_mut().send_event(PlayerHit {
+            cause: Entity::PLACEHOLDER,
+            ship,
+        });
+        (app, ship)
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world().resource::<State<GameState>>().get()
+    }
+
+    #[test]
+    fn run_goes_on_while_the_first_ship_has_lives() {
+        let (mut app, ship) = hit_app(2, 1);
+        app.update();
+        app.update();
+
+        assert!(app.world().get_entity(ship).is_err());
+        assert_eq!(*app.world().resource::<CoopLives>(), CoopLives(0));
+        assert_ne!(state(&app), GameState::GameOver);
+    }
+
+    #[test]
+    fn run_ends_once_both_ships_are_out() {
+        let (mut app, _) = hit_app(0, 1);
+        app.update();
+        app.update();
+
+        assert_eq!(state(&app), GameState::GameOver);
+    }
+}
@@ -0,0 +1,268 @@
+use bevy::{
+    color::palettes::css::{BLUE, GRAY, LIME, ORANGE, RED, YELLOW},
+    math::bounding::BoundingVolume,
+    prelude::*,
+    utils::HashSet,
+};
+use crate::{
+    capture::DualFighter,
+    collision::{collision_box, CollisionSet},
+    coop::CoopPlayer,
+    dive::Diving,
+    fly_logic::{FlyState, Health},
+    formation::{quadratic_bezier, Formation, FormationSlot},
+    game_assets::GameAssets,
+    game_state::GameState,
+    layer,
+    lives::Invulnerable,
+    Bullet, BulletHit, DebugSettings, Fly, Player, PlayerHit,
+};
+
+/// Labels float this far above the middle of their fly
+const LABEL_OFFSET: f32 = 36.0;
+
+/// Labels are drawn above the sprites and the score popups
+const LABEL_Z: f32 = layer::EFFECTS + 1.0;
+
+/// Size of the square marking a formation slot
+const SLOT_MARKER_SIZE: f32 = 56.0;
+
+/// Straight segments each drawn dive curve is made of
+const CURVE_SEGMENTS: usize = 16;
+
+/// Debug drawing. F4 toggles the balance overlay: every enemy's hit points
+/// and behavior above it, the formation slots and the curves of running
+/// dives. F5 toggles the collision boxes. Nothing but the toggles runs while
+/// both are off.
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, toggle_debug_drawing)
+            .add_systems(
+                Update,
+                (update_debug_labels, draw_formation_slots, draw_dive_paths)
+                    .after(toggle_debug_drawing)
+                    .run_if(overlay_enabled)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                remove_debug_labels
+                    .after(toggle_debug_drawing)
+                    .run_if(resource_changed::<DebugSettings>)
+                    .run_if(not(overlay_enabled)),
+            )
+            .add_systems(
+                FixedUpdate,
+                // Where everything was when hits were checked, before any of it is despawned
+                draw_collision_debug
+                    .after(CollisionSet::Detect)
+                    .before(CollisionSet::Resolve)
+                    .run_if(collisions_enabled),
+            );
+    }
+}
+
+/// Text above a fly, naming the fly it belongs to
+#[derive(Component)]
+struct DebugLabel {
+    target: Entity,
+}
+
+fn overlay_enabled(debug_settings: Res<DebugSettings>) -> bool {
+    debug_settings.overlay
+}
+
+fn collisions_enabled(debug_settings: Res<DebugSettings>) -> bool {
+    debug_settings.collisions
+}
+
+/// Flips the overlay on and off when F4 is pressed, the collision boxes with F5
+fn toggle_debug_drawing(keyboard_input: Res<ButtonInput<KeyCode>>, mut debug_settings: ResMut<DebugSettings>) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        debug_settings.overlay = !debug_settings.overlay;
+        info!(overlay = debug_settings.overlay, "Toggled debug overlay");
+    }
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        debug_settings.collisions = !debug_settings.collisions;
+        info!(collisions = debug_settings.collisions, "Toggled collision boxes");
+    }
+}
+
+/// What the label of a fly with `health` hits left says. The mothership has
+/// no `FlyState`, so only shows its hit points.
+fn label_text(health: u32, state: Option<FlyState>) -> String {
+    match state {
+        Some(state) => format!("{} HP\n{:?}", health, state),
+        None => format!("{} HP", health),
+    }
+}
+
+/// Keeps a label above every fly, up to date with its health and state.
+/// Labels are separate entities, as flies are despawned without their
+/// children, and are removed once their fly is gone.
+fn update_debug_labels(
+    mut commands: Commands,
+    fly_query: Query<(Entity, &Transform, &Health, Option<&FlyState>), With<Fly>>,
+    mut label_query: Query<(Entity, &DebugLabel, &mut Text2d, &mut Transform), Without<Fly>>,
+    game_assets: Res<GameAssets>,
+) {
+    let mut labelled = HashSet::new();
+
+    for (label_entity, label, mut text, mut transform) in label_query.iter_mut() {
+        let Ok((_, fly_transform, health, state)) = fly_query.get(label.target) else {
+            commands.entity(label_entity).despawn();
+            continue;
+        };
+
+        let content = label_text(health.0, state.copied());
+        if text.0 != content {
+            text.0 = content;
+        }
+        transform.translation = fly_transform.translation.with_z(LABEL_Z) + Vec3::Y * LABEL_OFFSET;
+        labelled.insert(label.target);
+    }
+
+    for (fly, fly_transform, health, state) in fly_query.iter() {
+        if labelled.contains(&fly) {
+            continue;
+        }
+
+        commands.spawn((
+            DebugLabel { target: fly },
+            StateScoped(GameState::Playing),
+            Text2d::new(label_text(health.0, state.copied())),
+            TextFont {
+                font: game_assets.font.clone(),
+                font_size: 12.0,
+                ..Default::default()
+            },
+            TextColor(Color::WHITE),
+            Transform::from_translation(fly_transform.translation.with_z(LABEL_Z) + Vec3::Y * LABEL_OFFSET),
+        ));
+    }
+}
+
+/// Clears the labels once the overlay is turned off
+fn remove_debug_labels(mut commands: Commands, label_query: Query<Entity, With<DebugLabel>>) {
+    for label in label_query.iter() {
+        commands.entity(label).despawn();
+    }
+}
+
+/// Outlines every formation slot where it is right now, green while a fly
+/// holds it and grey while it is free
+fn draw_formation_slots(
+    mut gizmos: Gizmos,
+    formation: Res<Formation>,
+    slot_query: Query<&FormationSlot, With<Fly>>,
+) {
+    let taken: HashSet<(u32, u32)> = slot_query.iter().map(|slot| (slot.row, slot.col)).collect();
+
+    for slot in formation.slots() {
+        let color = if taken.contains(&(slot.row, slot.col)) { LIME } else { GRAY };
+        let position = formation.slot_position(&slot).truncate();
+        gizmos.rect_2d(Isometry2d::from_translation(position), Vec2::splat(SLOT_MARKER_SIZE), color);
+    }
+}
+
+/// Draws the whole curve of every running dive
+fn draw_dive_paths(mut gizmos: Gizmos, dive_query: Query<&Diving>) {
+    for diving in dive_query.iter() {
+        let points = (0..=CURVE_SEGMENTS).map(|segment| {
+            let t = segment as f32 / CURVE_SEGMENTS as f32;
+            quadratic_bezier(diving.start, diving.control, diving.end, t).truncate()
+        });
+        gizmos.linestrip_2d(points, ORANGE);
+    }
+}
+
+/// Color of a collision box: blue for player ships, red for flies, green for
+/// bullets, and yellow on the step something was hit
+fn collision_color(hit: bool, bullet: bool, fly: bool) -> Srgba {
+    match (hit, bullet, fly) {
+        (true, _, _) => YELLOW,
+        (_, true, _) => LIME,
+        (_, _, true) => RED,
+        _ => BLUE,
+    }
+}
+
+/// Draws the box every hit check of this step used, from `collision_box`.
+/// Invulnerable ships are skipped, as bullets and flies pass through them.
+#[allow(clippy::type_complexity)]
+fn draw_collision_debug(
+    mut gizmos: Gizmos,
+    mut bullet_hit_events: EventReader<BulletHit>,
+    mut player_hit_events: EventReader<PlayerHit>,
+    shape_query: Query<
+        (Entity, &Transform, Has<DualFighter>, Has<Bullet>, Has<Fly>),
+        (Or<(With<Player>, With<CoopPlayer>, With<Fly>, With<Bullet>)>, Without<Invulnerable>),
+    >,
+) {
+    let mut hit = HashSet::new();
+    for event in bullet_hit_events.read() {
+        hit.extend([event.bullet, event.target]);
+    }
+    for event in player_hit_events.read() {
+        hit.extend([event.cause, event.ship]);
+    }
+
+    for (entity, transform, dual, bullet, fly) in shape_query.iter() {
+        let shape = collision_box(transform, dual);
+        let color = collision_color(hit.contains(&entity), bullet, fly);
+        gizmos.rect_2d(Isometry2d::from_translation(shape.center()), shape.half_size() * 2.0, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_show_health_and_state() {
+        assert_eq!(label_text(2, Some(FlyState::InFormation)), "2 HP\nInFormation");
+        assert_eq!(label_text(30, None), "30 HP");
+    }
+
+    #[test]
+    fn collision_boxes_are_colored_by_faction_and_hits() {
+        assert_eq!(collision_color(false, true, false), LIME);
+        assert_eq!(collision_color(false, false, true), RED);
+        assert_eq!(collision_color(false, false, false), BLUE);
+        assert_eq!(collision_color(true, false, true), YELLOW);
+    }
+
+    #[test]
+    fn labels_follow_their_fly_and_go_with_it() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameAssets>()
+            .add_systems(Update, update_debug_labels);
+
+        let fly = app
+            .world_mut()
+            .spawn((Fly, Health(1), FlyState::Diving, Transform::from_xyz(10.0, 20.0, 0.0)))
+            .id();
+        app.update();
+
+        let labels = |app: &mut App| -> Vec<(String, Vec3)> {
+            app.world_mut()
+                .query_filtered::<(&Text2d, &Transform), With<DebugLabel>>()
+                .iter(app.world())
+                .map(|(text, transform)| (text.0.clone(), transform.translation))
+                .collect()
+        };
+        let above = Vec3::new(10.0, 20.0 + LABEL_OFFSET, LABEL_Z);
+        assert_eq!(labels(&mut app), vec![("1 HP\nDiving".to_string(), above)]);
+
+        app.world_mut().entity_mut(fly).insert(FlyState::Entering);
+        app.update();
+        assert_eq!(labels(&mut app)[0].0, "1 HP\nEntering");
+
+        app.world_mut().despawn(fly);
+        app.update();
+        assert!(labels(&mut app).is_empty());
+    }
+}
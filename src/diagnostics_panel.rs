@@ -0,0 +1,181 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use crate::{game_assets::GameAssets, wave::Wave, Bullet, DebugSettings, Fly};
+
+/// Seconds between two refreshes of the panel
+const REFRESH_INTERVAL: f32 = 0.5;
+
+/// Frame rate and entity counts in the bottom left corner, toggled with F3.
+/// The panel only exists while it is shown, and nothing but the toggle runs
+/// while it is off.
+pub struct DiagnosticsPanelPlugin;
+
+impl Plugin for DiagnosticsPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(FrameTimeDiagnosticsPlugin)
+            .insert_resource(DiagnosticsRefresh(Timer::from_seconds(REFRESH_INTERVAL, TimerMode::Repeating)))
+            .add_systems(
+                Update,
+                (
+                    toggle_diagnostics_panel,
+                    show_diagnostics_panel.run_if(resource_changed::<DebugSettings>),
+                    refresh_diagnostics_panel.run_if(diagnostics_enabled),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Brings the next refresh of the panel, on real time so it keeps going while paused
+#[derive(Resource)]
+struct DiagnosticsRefresh(Timer);
+
+/// Marks the text of the diagnostics panel
+#[derive(Component)]
+struct DiagnosticsPanel;
+
+fn diagnostics_enabled(debug_settings: Res<DebugSettings>) -> bool {
+    debug_settings.diagnostics
+}
+
+/// Shows or hides the panel when F3 is pressed on its own. Shift + F3 is verbose logging.
+fn toggle_diagnostics_panel(keyboard_input: Res<ButtonInput<KeyCode>>, mut debug_settings: ResMut<DebugSettings>) {
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keyboard_input.just_pressed(KeyCode::F3) && !shift {
+        debug_settings.diagnostics = !debug_settings.diagnostics;
+        info!(diagnostics = debug_settings.diagnostics, "Toggled diagnostics panel");
+    }
+}
+
+/// Spawns the panel when it is turned on and despawns it when it is turned
+/// off, so nothing of it is left in screenshots or behind the pause overlay
+fn show_diagnostics_panel(
+    mut commands: Commands,
+    debug_settings: Res<DebugSettings>,
+    panel_query: Query<Entity, With<DiagnosticsPanel>>,
+    game_assets: Res<GameAssets>,
+    mut refresh: ResMut<DiagnosticsRefresh>,
+) {
+    if !debug_settings.diagnostics {
+        for panel in panel_query.iter() {
+            commands.entity(panel).despawn_recursive();
+        }
+        return;
+    }
+
+    if !panel_query.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        DiagnosticsPanel,
+        Text::default(),
+        TextFont {
+            font: game_assets.font.clone(),
+            font_size: 16.0,
+            ..Default::default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            ..Default::default()
+        },
+        GlobalZIndex(i32::MAX),
+    ));
+    // Filled in right away rather than half a second later
+    let duration = refresh.0.duration();
+    refresh.0.set_elapsed(duration);
+}
+
+/// Lines of the panel
+fn diagnostics_text(fps: Option<f64>, entities: usize, bullets: usize, flies: usize, wave: u32) -> String {
+    let fps = fps.map_or("-".to_string(), |fps| format!("{:.0}", fps));
+    format!(
+        "FPS {}\nEntities {}\nBullets {}\nFlies {}\nWave {}",
+        fps, entities, bullets, flies, wave
+    )
+}
+
+/// Rewrites the panel every `REFRESH_INTERVAL` seconds
+#[allow(clippy::too_many_arguments)]
+fn refresh_diagnostics_panel(
+    mut refresh: ResMut<DiagnosticsRefresh>,
+    mut panel_query: Query<&mut Text, With<DiagnosticsPanel>>,
+    entity_query: Query<()>,
+    bullet_query: Query<(), With<Bullet>>,
+    fly_query: Query<(), With<Fly>>,
+    diagnostics: Res<DiagnosticsStore>,
+    wave: Res<Wave>,
+    time: Res<Time<Real>>,
+) {
+    // A timer set to its end finishes on any tick, even one without elapsed time
+    if !refresh.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed());
+    let content = diagnostics_text(
+        fps,
+        entity_query.iter().count(),
+        bullet_query.iter().count(),
+        fly_query.iter().count(),
+        wave.0,
+    );
+
+    for mut text in panel_query.iter_mut() {
+        text.0.clone_from(&content);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panel_lists_every_count() {
+        assert_eq!(
+            diagnostics_text(Some(59.6), 120, 3, 12, 4),
+            "FPS 60\nEntities 120\nBullets 3\nFlies 12\nWave 4"
+        );
+        assert!(diagnostics_text(None, 0, 0, 0, 1).starts_with("FPS -\n"));
+    }
+
+    #[test]
+    fn panel_exists_only_while_shown() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameAssets>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<DiagnosticsStore>()
+            .insert_resource(Wave(2))
+            .insert_resource(DiagnosticsRefresh(Timer::from_seconds(REFRESH_INTERVAL, TimerMode::Repeating)))
+            .add_systems(Update, (show_diagnostics_panel, refresh_diagnostics_panel).chain());
+        app.world_mut().spawn(Fly);
+        app.update();
+
+        let panel = |app: &mut App| -> Vec<String> {
+            app.world_mut()
+                .query_filtered::<&Text, With<DiagnosticsPanel>>()
+                .iter(app.world())
+                .map(|text| text.0.clone())
+                .collect()
+        };
+        assert!(panel(&mut app).is_empty());
+
+        app.world_mut().resource_mut::<DebugSettings>().diagnostics = true;
+        app.update();
+        let shown = panel(&mut app);
+        assert_eq!(shown.len(), 1);
+        assert!(shown[0].contains("Flies 1\n") && shown[0].ends_with("Wave 2"), "{}", shown[0]);
+
+        app.world_mut().resource_mut::<DebugSettings>().diagnostics = false;
+        app.update();
+        assert!(panel(&mut app).is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+use std::{fmt, str::FromStr};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::powerup::DropChance;
+
+/// Difficulty presets and the gameplay parameters they scale
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .init_resource::<DifficultyConfig>()
+            .add_systems(Update, apply_difficulty.run_if(resource_changed::<Difficulty>));
+    }
+}
+
+/// How hard runs are. It can only be changed from the menu, never mid-run, so
+/// every score of a run was made on a single difficulty.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Parameters of this preset
+    pub fn config(self) -> DifficultyConfig {
+        match self {
+            Difficulty::Easy => DifficultyConfig::easy(),
+            Difficulty::Normal => DifficultyConfig::normal(),
+            Difficulty::Hard => DifficultyConfig::hard(),
+        }
+    }
+
+    /// The preset `step` places further along `ALL`, wrapping around at both ends
+    pub fn step(self, step: isize) -> Difficulty {
+        let index = Difficulty::ALL.iter().position(|difficulty| *difficulty == self).unwrap_or(0);
+        let len = Difficulty::ALL.len() as isize;
+        Difficulty::ALL[(index as isize + step).rem_euclid(len) as usize]
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+/// Every gameplay parameter a difficulty changes. The interval and speed
+/// fields multiply the per-wave values, so later waves still get harder.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyConfig {
+    pub starting_lives: u32,
+    pub fire_interval: f32,      // Factor on the time between enemy shots
+    pub dive_interval: f32,      // Factor on the time between dives
+    pub fly_speed: f32,          // Factor on how fast flies march and dive
+    pub drop_chance: f32,        // Chance in `[0, 1]` of a pickup per kill
+    pub enemy_bullet_speed: f32, // Units per second
+    pub player_speed: f32,       // Factor on the ship speed from the game config
+    pub thinning_speedup: f32,   // How much more aggressive a thinned out formation gets, see `thinning_factor`
+    pub lead_time: f32,          // Seconds ahead of the player enemy fire aims, once it leads at all
+    pub lead_from_wave: u32,     // First wave enemy fire leads the player on, see `lead_time_on`
+}
+
+impl DifficultyConfig {
+    pub fn easy() -> Self {
+        DifficultyConfig {
+            starting_lives: 5,
+            fire_interval: 1.5,
+            dive_interval: 1.5,
+            fly_speed: 0.8,
+            drop_chance: 0.2,
+            enemy_bullet_speed: 220.0,
+            player_speed: 1.1,
+            thinning_speedup: 1.5,
+            lead_time: 0.0,
+            lead_from_wave: u32::MAX,
+        }
+    }
+
+    pub fn normal() -> Self {
+        DifficultyConfig {
+            starting_lives: 3,
+            fire_interval: 1.0,
+            dive_interval: 1.0,
+            fly_speed: 1.0,
+            drop_chance: 0.1,
+            enemy_bullet_speed: 300.0,
+            player_speed: 1.0,
+            thinning_speedup: 2.0,
+            lead_time: 0.0,
+            lead_from_wave: u32::MAX,
+        }
+    }
+
+    pub fn hard() -> Self {
+        DifficultyConfig {
+            starting_lives: 2,
+            fire_interval: 0.7,
+            dive_interval: 0.6,
+            fly_speed: 1.3,
+            drop_chance: 0.05,
+            enemy_bullet_speed: 400.0,
+            player_speed: 0.9,
+            thinning_speedup: 3.0,
+            lead_time: 0.4,
+            lead_from_wave: 1,
+        }
+    }
+
+    /// Factor on the march speed, breathing and dive rate of a formation with
+    /// `alive_fraction` of its wave left. 1.0 while the whole wave is alive,
+    /// rising ever faster to `1 + thinning_speedup` as the last flies go, so
+    /// the last few dive almost without a break.
+    pub fn thinning_factor(&self, alive_fraction: f32) -> f32 {
+        let destroyed = 1.0 - alive_fraction.clamp(0.0, 1.0);
+        1.0 + self.thinning_speedup * destroyed * destroyed
+    }
+
+    /// How far ahead of the player enemy fire aims on `wave`, in seconds. Zero
+    /// aims where the ship is, as Easy and Normal always do. Hard leads from
+    /// the start.
+    pub fn lead_time_on(&self, wave: u32) -> f32 {
+        if wave >= self.lead_from_wave {
+            self.lead_time
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        DifficultyConfig::normal()
+    }
+}
+
+/// Loads the parameters of the selected difficulty
+fn apply_difficulty(
+    difficulty: Res<Difficulty>,
+    mut config: ResMut<DifficultyConfig>,
+    mut drop_chance: ResMut<DropChance>,
+) {
+    *config = difficulty.config();
+    drop_chance.0 = config.drop_chance;
+    info!(difficulty = %*difficulty, "Difficulty set");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_get_harder_in_order() {
+        let [easy, normal, hard] = Difficulty::ALL.map(Difficulty::config);
+
+        assert!(easy.starting_lives > normal.starting_lives);
+        assert!(normal.starting_lives > hard.starting_lives);
+        assert!(easy.fire_interval > normal.fire_interval);
+        assert!(normal.fire_interval > hard.fire_interval);
+        assert!(easy.enemy_bullet_speed < hard.enemy_bullet_speed);
+        assert!(easy.player_speed > hard.player_speed);
+        assert!(easy.thinning_factor(0.0) < hard.thinning_factor(0.0));
+        assert_eq!(easy.lead_time_on(1000), 0.0);
+        assert_eq!(normal.lead_time_on(1000), 0.0);
+        assert!(hard.lead_time_on(1) > 0.0);
+        assert_eq!(Difficulty::Hard.step(1), Difficulty::Easy);
+        assert_eq!(Difficulty::Easy.step(-1), Difficulty::Hard);
+    }
+
+    #[test]
+    fn thinning_out_only_ever_speeds_up() {
+        let normal = DifficultyConfig::normal();
+        assert_eq!(normal.thinning_factor(1.0), 1.0);
+        assert_eq!(normal.thinning_factor(0.0), 1.0 + normal.thinning_speedup);
+        assert_eq!(normal.thinning_factor(-0.5), normal.thinning_factor(0.0));
+
+        // Each kill counts for more than the one before
+        let first = normal.thinning_factor(0.75) - normal.thinning_factor(1.0);
+        let last = normal.thinning_factor(0.0) - normal.thinning_factor(0.25);
+        assert!(first > 0.0 && last > first);
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, PrimaryWindow, WindowMode},
+};
+
+/// Switching the primary window between windowed and borderless fullscreen
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DisplaySettings>()
+            .add_systems(Update, toggle_fullscreen);
+    }
+}
+
+/// How the game window is shown. Debug builds start windowed so the console and
+/// editor stay visible, release builds start fullscreen.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct DisplaySettings {
+    pub fullscreen: bool,
+    pub windowed_size: Vec2, // Restored when leaving fullscreen
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            fullscreen: !cfg!(debug_assertions),
+            windowed_size: Vec2::new(1200.0, 800.0),
+        }
+    }
+}
+
+impl DisplaySettings {
+    /// Window mode matching the settings
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    /// Switches `window` to the other mode, remembering the windowed size so
+    /// it comes back unchanged
+    pub fn toggle_fullscreen(&mut self, window: &mut Window) {
+        if self.fullscreen {
+            self.fullscreen = false;
+            window.resolution.set(self.windowed_size.x, self.windowed_size.y);
+        } else {
+            self.fullscreen = true;
+            self.windowed_size = window.resolution.size();
+        }
+        window.mode = self.window_mode();
+    }
+}
+
+/// Flips between windowed and fullscreen on F11 or Alt+Enter
+fn toggle_fullscreen(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut display_settings: ResMut<DisplaySettings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let alt_enter = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        && keyboard_input.just_pressed(KeyCode::Enter);
+    if !keyboard_input.just_pressed(KeyCode::F11) && !alt_enter {
+        return;
+    }
+
+    if let Ok(mut window) = window_query.get_single_mut() {
+        display_settings.toggle_fullscreen(&mut window);
+    }
+}
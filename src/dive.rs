@@ -0,0 +1,297 @@
+use bevy::{prelude::*, utils::HashSet};
+use rand::seq::IteratorRandom;
+use crate::{
+    bullet::BulletPool,
+    capture::BeamDive,
+    difficulty::DifficultyConfig,
+    fly_logic::{aim_at_player, spawn_enemy_bullet, AnimationTimer, FlyState},
+    game_assets::GameAssets,
+    game_rng::GameRng,
+    formation::{quadratic_bezier, EnteringFormation, Formation, FormationSlot},
+    practice::{enemy_fire_on, PracticeRun},
+    wave::Wave,
+    ColorsPalette, Fly, PlayField, PlayerPosition, PlayerVelocity,
+};
+
+/// Seconds between dives on wave 1
+const BASE_DIVE_INTERVAL: f32 = 4.0;
+
+/// Dives never happen more often than this, however late the wave
+const MIN_DIVE_INTERVAL: f32 = 0.75;
+
+/// Seconds a full dive from the formation to below the screen takes
+const DIVE_DURATION: f32 = 2.5;
+
+/// How much faster flies flap their wings while diving
+const DIVE_FLAP_RATE: f32 = 2.0;
+
+/// Points along the dive (as path progress) where the fly opens fire
+const DIVE_SHOTS: [f32; 2] = [0.3, 0.55];
+
+/// Timer deciding when the next fly leaves the formation to attack
+#[derive(Resource)]
+pub struct DiveTimer(pub Timer);
+
+impl Default for DiveTimer {
+    fn default() -> Self {
+        let interval = dive_interval(1, &DifficultyConfig::normal());
+        DiveTimer(Timer::from_seconds(interval, TimerMode::Repeating))
+    }
+}
+
+/// Seconds between dives for a wave, shrinking as waves go up
+pub fn dive_interval(wave: u32, difficulty: &DifficultyConfig) -> f32 {
+    let wave_factor = 1.0 + 0.25 * wave.saturating_sub(1) as f32;
+    (BASE_DIVE_INTERVAL / wave_factor).max(MIN_DIVE_INTERVAL) * difficulty.dive_interval
+}
+
+/// A fly that left the formation and swoops down toward the player
+#[derive(Component)]
+pub struct Diving {
+    pub start: Vec3,
+    pub control: Vec3,
+    pub end: Vec3,
+    pub progress: f32, // 0.0 at `start`, 1.0 below the bottom edge
+    pub shots_fired: usize,
+}
+
+impl Diving {
+    /// Dive from `start` curving out to the side and down through `target_x`
+    fn toward(start: Vec3, target_x: f32, play_field: &PlayField) -> Self {
+        let half_size = play_field.half_size();
+        // Swing out away from the player first, like the arcade loop
+        let side = if target_x >= start.x { -1.0 } else { 1.0 };
+        Diving {
+            start,
+            control: Vec3::new(start.x + side * 200.0, start.y - 100.0, start.z),
+            end: Vec3::new(target_x, -half_size.y - 60.0, start.z),
+            progress: 0.0,
+            shots_fired: 0,
+        }
+    }
+}
+
+/// Every time the dive timer fires, sends a random settled formation fly diving
+/// toward the player's current column. Dives come quicker as the formation thins out.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn start_dives(
+    mut commands: Commands,
+    mut dive_timer: ResMut<DiveTimer>,
+    wave: Res<Wave>,
+    mut fly_query: Query<
+        (Entity, &Transform, Option<&mut AnimationTimer>),
+        (
+            With<Fly>,
+            With<FormationSlot>,
+            Without<EnteringFormation>,
+            Without<Diving>,
+            Without<BeamDive>,
+        ),
+    >,
+    player_position: Res<PlayerPosition>,
+    play_field: Res<PlayField>,
+    formation: Res<Formation>,
+    difficulty: Res<DifficultyConfig>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    // The fewer flies are left, the sooner the next one dives
+    let interval = dive_interval(wave.0, &difficulty) / difficulty.thinning_factor(formation.alive_fraction);
+    let interval = std::time::Duration::from_secs_f32(interval);
+    if dive_timer.0.duration() != interval {
+        dive_timer.0.set_duration(interval);
+    }
+
+    if !dive_timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let diver = fly_query.iter_mut().choose(&mut *game_rng);
+
+    if let Some((entity, transform, animation)) = diver {
+        let diving = Diving::toward(transform.translation, player_position.0.x, &play_field);
+        commands.entity(entity).insert((diving, FlyState::Diving));
+
+        if let Some(mut animation) = animation {
+            animation.rate = DIVE_FLAP_RATE;
+        }
+    }
+}
+
+/// Slot a fly re-entering after a dive settles into: its own one, unless
+/// another fly took it over meanwhile, then the free slot nearest to it.
+/// `taken` holds the slots of every other fly. With the whole grid taken the
+/// fly goes back to its own slot regardless.
+pub fn reentry_slot(own: FormationSlot, taken: &HashSet<FormationSlot>, formation: &Formation) -> FormationSlot {
+    if !taken.contains(&own) {
+        return own;
+    }
+
+    let own_position = formation.slot_position(&own);
+    formation
+        .slots()
+        .filter(|slot| !taken.contains(slot))
+        .min_by(|a, b| {
+            let distance_a = formation.slot_position(a).distance_squared(own_position);
+            let distance_b = formation.slot_position(b).distance_squared(own_position);
+            distance_a.total_cmp(&distance_b)
+        })
+        .unwrap_or(own)
+}
+
+/// Moves diving flies along their curve, firing on the way down, led like
+/// formation fire, unless a practice run turned enemy fire off. A fly that
+/// makes it past the bottom edge is `Reentering`: it reappears above the top
+/// and glides back to its slot, or the nearest free one if its own was taken.
+/// This runs in `MovementSet`, so the fly is back on the field before the
+/// out-of-bounds cleanup looks at it.
+#[allow(clippy::too_many_arguments)]
+pub fn dive_flies(
+    mut commands: Commands,
+    mut fly_query: Query<(
+        Entity,
+        &mut Transform,
+        &mut Diving,
+        &FormationSlot,
+        Option<&mut AnimationTimer>,
+    )>,
+    slot_query: Query<(Entity, &FormationSlot), With<Fly>>,
+    formation: Res<Formation>,
+    wave: Res<Wave>,
+    player_position: Res<PlayerPosition>,
+    player_velocity: Res<PlayerVelocity>,
+    difficulty: Res<DifficultyConfig>,
+    play_field: Res<PlayField>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    mut bullet_pool: ResMut<BulletPool>,
+    practice: Option<Res<PracticeRun>>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    let step = time.delta_secs() * difficulty.fly_speed / DIVE_DURATION;
+    let fire = enemy_fire_on(practice);
+    let lead_time = difficulty.lead_time_on(wave.0);
+    let bottom = -play_field.half_size().y;
+    // Slots handed out this step, not yet on the flies that re-enter into them
+    let mut claimed = HashSet::new();
+
+    for (entity, mut transform, mut diving, slot, animation) in fly_query.iter_mut() {
+        diving.progress = (diving.progress + step).min(1.0);
+        transform.translation = quadratic_bezier(diving.start, diving.control, diving.end, diving.progress);
+
+        if fire && diving.shots_fired < DIVE_SHOTS.len() && diving.progress >= DIVE_SHOTS[diving.shots_fired] {
+            diving.shots_fired += 1;
+            let target = aim_at_player(player_position.0, player_velocity.0, lead_time, &mut *game_rng);
+            spawn_enemy_bullet(
+                &mut commands,
+                &mut bullet_pool,
+                transform.translation,
+                target,
+                difficulty.enemy_bullet_speed,
+                &color_palette,
+                &game_assets,
+            );
+        }
+
+        let below_bottom = transform.translation.y + transform.scale.y / 2.0 < bottom;
+        if !below_bottom && diving.progress < 1.0 {
+            continue;
+        }
+
+        let taken: HashSet<FormationSlot> = slot_query
+            .iter()
+            .filter(|(other, _)| *other != entity)
+            .map(|(_, other_slot)| *other_slot)
+            .chain(claimed.iter().copied())
+            .collect();
+        let slot = reentry_slot(*slot, &taken, &formation);
+        claimed.insert(slot);
+
+        let entering = EnteringFormation::from_top(formation.slot_position(&slot), &play_field);
+        transform.translation = entering.start;
+        commands
+            .entity(entity)
+            .remove::<Diving>()
+            .insert((entering, slot, FlyState::Reentering));
+
+        if let Some(mut animation) = animation {
+            animation.rate = 1.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dives_get_more_frequent_on_later_waves() {
+        let normal = DifficultyConfig::normal();
+        assert_eq!(dive_interval(1, &normal), BASE_DIVE_INTERVAL);
+        assert!(dive_interval(2, &normal) < dive_interval(1, &normal));
+        assert!(dive_interval(5, &normal) < dive_interval(2, &normal));
+        assert_eq!(dive_interval(100, &normal), MIN_DIVE_INTERVAL);
+        assert!(dive_interval(1, &DifficultyConfig::hard()) < dive_interval(1, &normal));
+    }
+
+    #[test]
+    fn taken_slots_send_the_fly_to_the_nearest_free_one() {
+        let formation = Formation::default();
+        let own = FormationSlot { row: 1, col: 2 };
+        assert_eq!(reentry_slot(own, &HashSet::new(), &formation), own);
+
+        // Rows are closer together than columns, and of two slots as near as each other the top one wins
+        let taken = HashSet::from_iter([own, FormationSlot { row: 1, col: 1 }]);
+        assert_eq!(reentry_slot(own, &taken, &formation), FormationSlot { row: 0, col: 2 });
+
+        let full: HashSet<FormationSlot> = formation.slots().collect();
+        assert_eq!(reentry_slot(own, &full, &formation), own);
+    }
+
+    #[test]
+    fn fly_past_the_bottom_reenters_from_the_top() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Formation>()
+            .insert_resource(Wave(1))
+            .insert_resource(PlayerPosition(Vec3::ZERO))
+            .init_resource::<PlayerVelocity>()
+            .insert_resource(DifficultyConfig::normal())
+            .insert_resource(GameRng::from_seed(0))
+            .init_resource::<PlayField>()
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<BulletPool>()
+            .add_systems(Update, dive_flies);
+
+        let slot = FormationSlot { row: 0, col: 0 };
+        let half_size = app.world().resource::<PlayField>().half_size();
+        let start = Vec3::new(0.0, -half_size.y + 10.0, 0.0);
+        let fly = app
+            .world_mut()
+            .spawn((
+                Fly,
+                slot,
+                FlyState::Diving,
+                Transform::from_translation(start).with_scale(Vec3::splat(50.0)),
+                Diving {
+                    start,
+                    control: start,
+                    end: start - Vec3::Y * 100.0,
+                    progress: 0.9,
+                    shots_fired: DIVE_SHOTS.len(),
+                },
+            ))
+            .id();
+        // Another fly moved into the slot while it was away
+        app.world_mut().spawn((Fly, slot));
+        app.update();
+
+        let entity = app.world().entity(fly);
+        assert!(!entity.contains::<Diving>());
+        assert_eq!(entity.get::<FlyState>(), Some(&FlyState::Reentering));
+        assert_eq!(entity.get::<FormationSlot>(), Some(&FormationSlot { row: 1, col: 0 }));
+        assert!(entity.get::<Transform>().unwrap().translation.y > half_size.y);
+    }
+}
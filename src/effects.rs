@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use crate::{
+    bullet::apply_velocity,
+    explosion::{despawn_expired, Lifetime},
+    fly_logic::{EnemyKind, Health},
+    game_state::GameState,
+    layer,
+    Bullet, ColorsPalette,
+};
+
+/// Seconds a muzzle flash stays on screen
+const FLASH_LIFETIME: f32 = 0.08;
+
+/// Size of a muzzle flash, a little wider than the bullet
+const FLASH_SIZE: f32 = 18.0;
+
+/// Fixed steps between two afterimages of the same bullet
+const TRAIL_EVERY_STEPS: u32 = 2;
+
+/// Afterimages following each bullet at once
+const TRAIL_LENGTH: u32 = 4;
+
+/// Opacity an afterimage starts fading from
+const TRAIL_ALPHA: f32 = 0.5;
+
+/// Seconds a sprite flashes for after surviving a hit
+const HIT_FLASH_DURATION: f32 = 0.15;
+
+/// Shooting polish: a flash where each bullet appears and a short trail of
+/// fading afterimages behind it, both of which can be switched off on slow
+/// devices, plus a flash on anything that survives a hit.
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VisualEffectsSettings>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Before bullets move, so flashes sit on the origin and trails lag behind
+                    spawn_muzzle_flashes.run_if(flashes_enabled),
+                    spawn_bullet_trails.run_if(trails_enabled),
+                )
+                    .before(apply_velocity),
+            )
+            .add_systems(Update, (fade_effects.after(despawn_expired), flash_hits));
+    }
+}
+
+/// Which shooting effects are drawn
+#[derive(Resource)]
+pub struct VisualEffectsSettings {
+    pub trails: bool,
+    pub flashes: bool,
+}
+
+impl Default for VisualEffectsSettings {
+    fn default() -> Self {
+        VisualEffectsSettings {
+            trails: true,
+            flashes: true,
+        }
+    }
+}
+
+fn flashes_enabled(settings: Res<VisualEffectsSettings>) -> bool {
+    settings.flashes
+}
+
+fn trails_enabled(settings: Res<VisualEffectsSettings>) -> bool {
+    settings.trails
+}
+
+/// Purely visual sprite fading out from `alpha` over its lifetime. It is never
+/// a bullet or a target and isn't despawned out of bounds, only its lifetime
+/// ends it.
+#[derive(Component)]
+pub struct FadingEffect {
+    pub alpha: f32, // Opacity when spawned
+}
+
+/// Flashes in the bullet's color where every new bullet appears
+fn spawn_muzzle_flashes(
+    mut commands: Commands,
+    bullet_query: Query<(&Transform, Option<&Sprite>), Added<Bullet>>,
+) {
+    for (transform, sprite) in bullet_query.iter() {
+        let color = sprite.map_or(Color::WHITE, |sprite| sprite.color);
+        spawn_fading_effect(
+            &mut commands,
+            transform.translation.with_z(layer::EFFECTS),
+            Vec3::splat(FLASH_SIZE),
+            color,
+            1.0,
+            FLASH_LIFETIME,
+        );
+    }
+}
+
+/// Leaves an afterimage of every bullet where it is every `TRAIL_EVERY_STEPS`
+/// fixed steps. Each lasts long enough for `TRAIL_LENGTH` of them to follow it.
+fn spawn_bullet_trails(
+    mut commands: Commands,
+    bullet_query: Query<(&Transform, Option<&Sprite>), With<Bullet>>,
+    time: Res<Time>,
+    mut steps: Local<u32>,
+) {
+    *steps = steps.wrapping_add(1);
+    if *steps % TRAIL_EVERY_STEPS != 0 {
+        return;
+    }
+
+    let lifetime = time.delta_secs() * (TRAIL_EVERY_STEPS * TRAIL_LENGTH) as f32;
+    for (transform, sprite) in bullet_query.iter() {
+        let color = sprite.map_or(Color::WHITE, |sprite| sprite.color);
+        spawn_fading_effect(
+            &mut commands,
+            transform.translation - Vec3::new(0.0, 0.0, 0.1),
+            transform.scale,
+            color,
+            TRAIL_ALPHA,
+            lifetime,
+        );
+    }
+}
+
+/// Spawns a plain sprite fading from `alpha` to nothing over `lifetime` seconds
+fn spawn_fading_effect(
+    commands: &mut Commands,
+    position: Vec3,
+    scale: Vec3,
+    color: Color,
+    alpha: f32,
+    lifetime: f32,
+) {
+    commands.spawn((
+        FadingEffect { alpha },
+        Lifetime(Timer::from_seconds(lifetime, TimerMode::Once)),
+        StateScoped(GameState::Playing),
+        Sprite {
+            color: color.with_alpha(alpha),
+            ..Default::default()
+        },
+        Transform {
+            translation: position,
+            scale,
+            ..Default::default()
+        },
+    ));
+}
+
+/// Fades effects out over their lifetime
+fn fade_effects(mut effect_query: Query<(&FadingEffect, &Lifetime, &mut Sprite)>) {
+    for (effect, lifetime, mut sprite) in effect_query.iter_mut() {
+        sprite.color.set_alpha(effect.alpha * (1.0 - lifetime.0.fraction()));
+    }
+}
+
+/// Tints a sprite toward `hit_flash_color` for a moment after a hit it survived.
+/// Inserting it again mid-flash starts the flash over.
+#[derive(Component)]
+pub struct HitFlash(pub Timer);
+
+impl Default for HitFlash {
+    fn default() -> Self {
+        HitFlash(Timer::from_seconds(HIT_FLASH_DURATION, TimerMode::Once))
+    }
+}
+
+/// Fades flashing sprites from the flash color back to their base color, then
+/// ends the flash. The base color is worked out from scratch every frame, so a
+/// boss hit again mid-flash still ends on its damaged color.
+#[allow(clippy::type_complexity)]
+fn flash_hits(
+    mut commands: Commands,
+    mut flash_query: Query<(
+        Entity,
+        &mut HitFlash,
+        &mut Sprite,
+        Option<&EnemyKind>,
+        Option<&Health>,
+    )>,
+    color_palette: Res<ColorsPalette>,
+    time: Res<Time>,
+) {
+    for (entity, mut flash, mut sprite, kind, health) in flash_query.iter_mut() {
+        let base = base_color(kind, health, &color_palette);
+
+        if flash.0.tick(time.delta()).finished() {
+            sprite.color = base;
+            commands.entity(entity).remove::<HitFlash>();
+        } else {
+            sprite.color = base.mix(&color_palette.hit_flash_color, 1.0 - flash.0.fraction());
+        }
+    }
+}
+
+/// Color of a sprite when it isn't flashing: the enemy's color for the health
+/// it has left, or the ship color for anything that isn't an enemy
+fn base_color(
+    kind: Option<&EnemyKind>,
+    health: Option<&Health>,
+    color_palette: &ColorsPalette,
+) -> Color {
+    let Some(kind) = kind else {
+        return color_palette.player_color;
+    };
+
+    match health {
+        Some(health) => kind.color_at(health.0, color_palette),
+        None => kind.color(color_palette),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulletOwner, DespawnOutOfBounds};
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    fn effects_app(settings: VisualEffectsSettings) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(settings)
+            .add_systems(
+                Update,
+                (
+                    spawn_muzzle_flashes.run_if(flashes_enabled),
+                    spawn_bullet_trails.run_if(trails_enabled),
+                ),
+            );
+        app.world_mut().spawn((
+            Bullet { owner: BulletOwner::Player },
+            Transform::from_xyz(0.0, 100.0, 0.0).with_scale(Vec3::splat(10.0)),
+        ));
+        app
+    }
+
+    fn effect_count(app: &mut App) -> usize {
+        app.world_mut().query::<&FadingEffect>().iter(app.world()).count()
+    }
+
+    #[test]
+    fn effects_are_never_bullets_or_despawned_out_of_bounds() {
+        let mut app = effects_app(VisualEffectsSettings::default());
+
+        for _ in 0..TRAIL_EVERY_STEPS {
+            app.update();
+        }
+
+        // One flash plus one afterimage
+        assert_eq!(effect_count(&mut app), 2);
+        let leaked = app
+            .world_mut()
+            .query_filtered::<(), (With<FadingEffect>, Or<(With<Bullet>, With<DespawnOutOfBounds>)>)>()
+            .iter(app.world())
+            .count();
+        assert_eq!(leaked, 0);
+    }
+
+    #[test]
+    fn disabled_effects_spawn_nothing() {
+        let mut app = effects_app(VisualEffectsSettings {
+            trails: false,
+            flashes: false,
+        });
+
+        for _ in 0..TRAIL_EVERY_STEPS * 2 {
+            app.update();
+        }
+
+        assert_eq!(effect_count(&mut app), 0);
+    }
+
+    #[test]
+    fn second_hit_mid_flash_still_ends_on_the_damaged_color() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .init_resource::<ColorsPalette>()
+            .add_systems(Update, flash_hits);
+        let palette = ColorsPalette::default();
+        let boss = app
+            .world_mut()
+            .spawn((
+                EnemyKind::Boss,
+                Health(1),
+                HitFlash::default(),
+                Sprite::from_color(EnemyKind::Boss.damaged_color(&palette), Vec2::ONE),
+            ))
+            .id();
+
+        app.update();
+        app.update();
+        let color = |app: &App| app.world().get::<Sprite>(boss).unwrap().color;
+        assert_ne!(color(&app), EnemyKind::Boss.damaged_color(&palette));
+
+        // Hit again while still flashing
+        app.world_mut().entity_mut(boss).insert(HitFlash::default());
+        for _ in 0..5 {
+            app.update();
+        }
+
+        assert_eq!(color(&app), EnemyKind::Boss.damaged_color(&palette));
+        assert!(app.world().get::<HitFlash>(boss).is_none());
+    }
+}
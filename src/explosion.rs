@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use rand::Rng;
+use crate::{
+    coop::{handle_coop_hit, CoopPlayer},
+    fly_logic::EnemyKind,
+    game_rng::GameRng,
+    game_state::GameState,
+    layer,
+    lives::{handle_player_hit, Invulnerable},
+    mothership::MOTHERSHIP_SCALE,
+    powerup::Shield,
+    ColorsPalette, EnemyKilled, Player, PlayerHit,
+};
+
+/// Sprites flying out of a single explosion
+const PARTICLES_PER_EXPLOSION: usize = 8;
+
+/// Seconds an explosion stays on screen
+const EXPLOSION_LIFETIME: f32 = 0.4;
+
+/// Short particle bursts where flies and the player ship are destroyed
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_enemy_explosions,
+                spawn_player_explosion.before(handle_player_hit).before(handle_coop_hit),
+                move_particles,
+                spin_particles,
+                despawn_expired,
+            ),
+        );
+    }
+}
+
+/// Entities carrying this are removed once the timer finishes
+#[derive(Component)]
+pub struct Lifetime(pub Timer);
+
+/// Drifts at a constant velocity, like explosion fragments and score popups
+#[derive(Component)]
+pub struct Particle {
+    pub velocity: Vec3, // Units per second
+}
+
+/// Turns at a constant rate, like the pieces of a destroyed player ship
+#[derive(Component)]
+pub struct Spin(pub f32); // Radians per second, counterclockwise
+
+/// Explodes every enemy destroyed this frame in its own color. The mothership
+/// goes up in five bursts spread over its body.
+fn spawn_enemy_explosions(
+    mut commands: Commands,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    color_palette: Res<ColorsPalette>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    for event in enemy_killed_events.read() {
+        let color = event.kind.color(&color_palette);
+        spawn_explosion(&mut commands, &mut game_rng, event.position, color);
+
+        if event.kind == EnemyKind::Mothership {
+            let reach = MOTHERSHIP_SCALE / 4.0;
+            for (x, y) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                let position = event.position + Vec3::new(x * reach, y * reach, 0.0);
+                spawn_explosion(&mut commands, &mut game_rng, position, color);
+            }
+        }
+    }
+}
+
+/// Explodes a player ship when a hit is about to destroy it, unless a shield
+/// takes the hit. Runs before
+/// `handle_player_hit` so the ship is still around to take the position from.
+#[allow(clippy::type_complexity)]
+fn spawn_player_explosion(
+    mut commands: Commands,
+    mut player_hit_events: EventReader<PlayerHit>,
+    player_query: Query<
+        (&Transform, Has<CoopPlayer>),
+        (Or<(With<Player>, With<CoopPlayer>)>, Without<Invulnerable>, Without<Shield>),
+    >,
+    color_palette: Res<ColorsPalette>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    // Several hits in the same frame still only destroy each ship once
+    let mut exploded = Vec::new();
+    for hit in player_hit_events.read() {
+        if exploded.contains(&hit.ship) {
+            continue;
+        }
+        let Ok((player_transform, coop)) = player_query.get(hit.ship) else {
+            continue;
+        };
+
+        let color = if coop { color_palette.coop_player_color } else { color_palette.player_color };
+        spawn_explosion(&mut commands, &mut game_rng, player_transform.translation, color);
+        exploded.push(hit.ship);
+    }
+}
+
+/// Spawns a burst of small sprites flying outward from `position`
+pub fn spawn_explosion(commands: &mut Commands, rng: &mut GameRng, position: Vec3, color: Color) {
+    for _ in 0..PARTICLES_PER_EXPLOSION {
+        let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+        let speed = rng.gen_range(60.0..180.0);
+
+        commands.spawn((
+            Particle {
+                velocity: (direction * speed).extend(0.0),
+            },
+            Lifetime(Timer::from_seconds(EXPLOSION_LIFETIME, TimerMode::Once)),
+            StateScoped(GameState::Playing),
+            Sprite {
+                color,
+                ..Default::default()
+            },
+            Transform {
+                translation: position.with_z(layer::EFFECTS),
+                scale: Vec3::splat(6.0),
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+/// Moves explosion fragments and score popups along their velocity
+fn move_particles(mut particle_query: Query<(&mut Transform, &Particle)>, time: Res<Time>) {
+    for (mut transform, particle) in particle_query.iter_mut() {
+        transform.translation += particle.velocity * time.delta_secs();
+    }
+}
+
+/// Turns spinning fragments by their rate
+fn spin_particles(mut spin_query: Query<(&mut Transform, &Spin)>, time: Res<Time>) {
+    for (mut transform, spin) in spin_query.iter_mut() {
+        transform.rotate_z(spin.0 * time.delta_secs());
+    }
+}
+
+/// Despawns every entity whose lifetime has run out
+pub fn despawn_expired(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Lifetime)>,
+    time: Res<Time>,
+) {
+    for (entity, mut lifetime) in query.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn expired_lifetime_is_despawned() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_systems(Update, despawn_expired);
+
+        let short = app
+            .world_mut()
+            .spawn(Lifetime(Timer::from_seconds(0.0, TimerMode::Once)))
+            .id();
+        let long = app
+            .world_mut()
+            .spawn(Lifetime(Timer::new(Duration::from_secs(60), TimerMode::Once)))
+            .id();
+        app.update();
+
+        assert!(app.world().get_entity(short).is_err());
+        assert!(app.world().get_entity(long).is_ok());
+    }
+}
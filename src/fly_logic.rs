@@ -1,52 +1,796 @@
 use bevy::prelude::*;
-use crate::{ColorsPalette, EntityType, GameEntity};
+use rand::{seq::IteratorRandom, Rng};
+use serde::Deserialize;
+use crate::{
+    bullet::{checkout_bullet, BulletPool},
+    challenge::{ChallengeStage, CHALLENGE_FLIES},
+    config::GameConfig,
+    difficulty::DifficultyConfig,
+    dive::{dive_flies, start_dives, DiveTimer},
+    formation::{
+        breathe_formation, follow_entry_path, hold_formation, march_formation, track_alive_fraction,
+        EnteringFormation, Formation,
+    },
+    game_assets::{GameAssets, FLY_FRAMES},
+    game_rng::GameRng,
+    game_state::{GameState, PauseState},
+    lives::DeathSequence,
+    run_intro::RunIntro,
+    mothership::Mothership,
+    paths::{FollowPath, PathLibrary},
+    players::{CurrentPlayer, PlayerSlots},
+    practice::enemy_fire_on,
+    spawn::{spawn_game_entity, SpawnSpec},
+    wave::{NextWaveTimer, Wave, WaveIntroTimer, WaveKind, WaveRemaining},
+    wave_plan::{SpawnGroup, WavePlan},
+    BulletOwner, ColorsPalette, EntityIdCounter, EntityType, Fly, MovementSet, PlayField, PlayerPosition,
+    PlayerVelocity,
+};
 
-/// Spawns a single fly at the given position
+/// Seconds between two enemy shots on wave 1
+const ENEMY_FIRE_INTERVAL: f32 = 1.5;
+
+/// Largest miss of a led shot, as a fraction of how far ahead of the player it is led
+const AIM_ERROR: f32 = 0.2;
+
+/// Enemy shots never come faster than this, however late the wave
+const MIN_ENEMY_FIRE_INTERVAL: f32 = 0.4;
+
+/// Flies: wave spawning, movement and enemy fire
+pub struct FlyPlugin;
+
+impl Plugin for FlyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EnemyFireTimer::default())
+            .insert_resource(FlySpawnTimer::default())
+            .insert_resource(MaxFlies::default())
+            .insert_resource(Formation::default())
+            .insert_resource(DiveTimer::default())
+            // The first flies come in once the wave intro is over
+            .add_systems(OnEnter(GameState::Playing), reset_fly_timers)
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Challenge stages bring their own flies and they never shoot. Nobody
+                    // fires at the pieces of a destroyed player ship either, or during
+                    // the run intro, or in a practice run without enemy fire.
+                    enemy_fire_system
+                        .run_if(enemy_fire_on)
+                        .run_if(not(resource_exists::<ChallengeStage>))
+                        .run_if(not(resource_exists::<WaveIntroTimer>))
+                        .run_if(not(resource_exists::<DeathSequence>))
+                        .run_if(not(resource_exists::<RunIntro>)),
+                    // Thinned out formations march, breathe and dive harder
+                    (march_formation, breathe_formation).after(track_alive_fraction),
+                    track_alive_fraction,
+                    // Slots are final for this step once the grid has marched and breathed
+                    follow_entry_path.after(march_formation).after(breathe_formation),
+                    hold_formation.after(march_formation).after(breathe_formation),
+                    dive_flies.after(march_formation).after(breathe_formation),
+                )
+                    .in_set(MovementSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    fly_spawner_system
+                        .run_if(not(resource_exists::<ChallengeStage>))
+                        .run_if(not(resource_exists::<WaveIntroTimer>))
+                        // The rest of a mothership wave waits until it is down
+                        .run_if(not(any_with_component::<Mothership>)),
+                    spawn_group_members,
+                    start_dives.run_if(not(resource_exists::<RunIntro>)),
+                    animate_flies,
+                )
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// Default seconds between two fly waves
+pub(crate) const FLY_SPAWN_INTERVAL: f32 = 5.0;
+
+/// Default maximum number of flies alive at once
+const MAX_FLIES: usize = 12;
+
+/// Default size of a fly sprite in units
+pub(crate) const FLY_SCALE: f32 = 50.0;
+
+/// Repeating timer that brings in the next group of flies of the current wave
+#[derive(Resource)]
+pub struct FlySpawnTimer(pub Timer);
+
+impl FlySpawnTimer {
+    /// Creates a timer firing every `seconds`
+    pub fn new(seconds: f32) -> Self {
+        FlySpawnTimer(Timer::from_seconds(seconds, TimerMode::Repeating))
+    }
+}
+
+impl Default for FlySpawnTimer {
+    fn default() -> Self {
+        FlySpawnTimer::new(FLY_SPAWN_INTERVAL)
+    }
+}
+
+/// Upper bound on living flies, groups are held back while it is reached
+#[derive(Resource)]
+pub struct MaxFlies(pub usize);
+
+impl Default for MaxFlies {
+    fn default() -> Self {
+        MaxFlies(MAX_FLIES)
+    }
+}
+
+/// Global timer deciding when the next fly opens fire
+#[derive(Resource)]
+pub struct EnemyFireTimer(pub Timer);
+
+impl Default for EnemyFireTimer {
+    fn default() -> Self {
+        let interval = enemy_fire_interval(1, &DifficultyConfig::normal());
+        EnemyFireTimer(Timer::from_seconds(interval, TimerMode::Repeating))
+    }
+}
+
+/// Seconds between enemy shots for a wave, shrinking as waves go up
+pub fn enemy_fire_interval(wave: u32, difficulty: &DifficultyConfig) -> f32 {
+    let wave_factor = 1.0 + 0.2 * wave.saturating_sub(1) as f32;
+    (ENEMY_FIRE_INTERVAL / wave_factor).max(MIN_ENEMY_FIRE_INTERVAL) * difficulty.fire_interval
+}
+
+/// The different enemies making up a wave
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum EnemyKind {
+    #[default]
+    Bee,
+    Butterfly,
+    Boss,
+    /// The lone giant of every 5th wave, see `mothership`
+    Mothership,
+}
+
+impl EnemyKind {
+    /// Points awarded for destroying this enemy
+    pub fn points(&self) -> u32 {
+        match self {
+            EnemyKind::Bee => 50,
+            EnemyKind::Butterfly => 80,
+            EnemyKind::Boss => 150,
+            EnemyKind::Mothership => 5000,
+        }
+    }
+
+    /// Hits needed to destroy this enemy
+    pub fn max_health(&self) -> u32 {
+        match self {
+            EnemyKind::Bee | EnemyKind::Butterfly => 1,
+            EnemyKind::Boss => 2,
+            EnemyKind::Mothership => 30,
+        }
+    }
+
+    /// Sprite color at full health
+    pub fn color(&self, color_palette: &ColorsPalette) -> Color {
+        match self {
+            EnemyKind::Bee => color_palette.bee_color,
+            EnemyKind::Butterfly => color_palette.butterfly_color,
+            EnemyKind::Boss => color_palette.boss_color,
+            EnemyKind::Mothership => color_palette.mothership_color,
+        }
+    }
+
+    /// Sprite color after surviving a hit
+    pub fn damaged_color(&self, color_palette: &ColorsPalette) -> Color {
+        match self {
+            EnemyKind::Boss => color_palette.boss_damaged_color,
+            EnemyKind::Mothership => color_palette.mothership_enraged_color,
+            _ => self.color(color_palette),
+        }
+    }
+
+    /// Sprite color with `health` hits left: the damaged color once hit, or
+    /// for the mothership once it is down to half health
+    pub fn color_at(&self, health: u32, color_palette: &ColorsPalette) -> Color {
+        let damaged = match self {
+            EnemyKind::Mothership => health * 2 <= self.max_health(),
+            _ => health < self.max_health(),
+        };
+        if damaged {
+            self.damaged_color(color_palette)
+        } else {
+            self.color(color_palette)
+        }
+    }
+}
+
+/// What a formation fly is doing, updated by the systems moving it from one
+/// behavior to the next. Challenge flies stay `Entering` until they leave.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlyState {
+    /// Flying its entrance, or back to its slot after a beam
+    #[default]
+    Entering,
+    /// Settled on its slot, marching with the formation
+    InFormation,
+    /// Swooping down at the player, or hovering to beam the ship up
+    Diving,
+    /// Gliding down from above the top edge back to its slot, having made it
+    /// past the bottom of the screen on a dive
+    Reentering,
+}
+
+/// Seconds each wing frame stays up at the normal flap rate
+const FLAP_FRAME_TIME: f32 = 0.25;
+
+/// Wing flap animation, stepping through the frames of the fly sprite sheet
+#[derive(Component)]
+pub struct AnimationTimer {
+    pub timer: Timer,
+    pub rate: f32, // Flap speed multiplier, raised while diving
+}
+
+impl Default for AnimationTimer {
+    fn default() -> Self {
+        AnimationTimer {
+            timer: Timer::from_seconds(FLAP_FRAME_TIME, TimerMode::Repeating),
+            rate: 1.0,
+        }
+    }
+}
+
+/// Hits an enemy can still take before being destroyed
+#[derive(Component)]
+pub struct Health(pub u32);
+
+/// Points awarded for destroying this fly instead of the default of its kind
+#[derive(Component)]
+pub struct Points(pub u32);
+
+/// Puts the wave counter on the wave of the player in play, and restarts the
+/// formation and the spawn, fire and dive timers for a fresh field
+fn reset_fly_timers(
+    mut commands: Commands,
+    slots: Res<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+    plan: Res<WavePlan>,
+    config: Res<GameConfig>,
+) {
+    // The starting wave on a fresh run, where the player left off on a later turn
+    let wave = Wave(slots.current(&current).wave);
+    if wave.kind() == WaveKind::Challenge {
+        commands.insert_resource(WaveRemaining(CHALLENGE_FLIES));
+        commands.insert_resource(ChallengeStage::default());
+    } else {
+        commands.insert_resource(WaveRemaining(plan.flies_in(&wave)));
+        commands.remove_resource::<ChallengeStage>();
+    }
+
+    commands.insert_resource(wave);
+    commands.insert_resource(EnemyFireTimer::default());
+    commands.insert_resource(FlySpawnTimer::new(config.fly_spawn_interval));
+    commands.remove_resource::<NextWaveTimer>();
+    commands.insert_resource(Formation::default());
+    commands.insert_resource(DiveTimer::default());
+}
+
+/// Spawns a single fly of the given kind at the given position, at the configured size
 pub fn spawn_fly(
     commands: &mut Commands,
+    entity_ids: &mut EntityIdCounter,
     position: Vec3,
-    color_palette: &Res<ColorsPalette>,
+    kind: EnemyKind,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+    config: &GameConfig,
+) -> Entity {
+    let spec = SpawnSpec::new(EntityType::Fly, position, color_palette, game_assets)
+        .with_sprite(game_assets.fly_sprite(kind.color(color_palette)))
+        .with_scale(Vec3::splat(config.fly_scale))
+        .with((kind, Health(kind.max_health()), AnimationTimer::default()));
+    spawn_game_entity(commands, entity_ids, spec)
+}
+
+/// A group of the wave still bringing in its members, one every time its
+/// timer fires, see `spawn_group_members`
+#[derive(Component)]
+pub struct PendingGroup {
+    group: SpawnGroup,
+    spawned: u32, // Members already on their way
+    speed: f32,
+    timer: Timer,
+}
+
+/// Brings in `group` of the wave: its first member flies onto the group's
+/// path right away and the others follow `spawn_delay` seconds apart, all at
+/// once without a delay. `scaling` is the path speed and delay factor of the
+/// wave, from `WavePlan::scaling`. Each fly hops to its slot at the end of the path.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_formation_wave(
+    commands: &mut Commands,
+    entity_ids: &mut EntityIdCounter,
+    group: &SpawnGroup,
+    scaling: (f32, f32),
+    remaining: &mut WaveRemaining,
+    play_field: &PlayField,
+    paths: &PathLibrary,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+    config: &GameConfig,
 ) {
-    commands.spawn((
-        GameEntity {
-            id: 2, // Unique ID for Fly
-            position,
-            entity_type: EntityType::Fly,
-        },
-        SpriteBundle {
-            transform: Transform {
-                translation: position,
-                scale: Vec3::splat(50.0),
-                ..Default::default()
-            },
-            sprite: Sprite {
-                color: color_palette.fly_color,
-                ..Default::default()
+    let (speed, delay_scale) = scaling;
+    let delay = group.spawn_delay * delay_scale;
+    let first_members = if delay > 0.0 { 1 } else { group.count };
+
+    for member in 0..first_members {
+        spawn_group_member(
+            commands,
+            entity_ids,
+            group,
+            member,
+            speed,
+            play_field,
+            paths,
+            color_palette,
+            game_assets,
+            config,
+        );
+        remaining.0 = remaining.0.saturating_sub(1);
+    }
+
+    if first_members < group.count {
+        commands.spawn((
+            PendingGroup {
+                group: group.clone(),
+                spawned: first_members,
+                speed,
+                timer: Timer::from_seconds(delay, TimerMode::Repeating),
             },
-            ..Default::default()
-        },
-    ));
+            StateScoped(GameState::Playing),
+        ));
+    }
 }
 
-/// Spawns multiple flies at predefined positions
-pub fn spawn_three_flies(
+/// Spawns member number `member` of `group` at the start of the group's path,
+/// flying it `speed` times as fast as planned
+#[allow(clippy::too_many_arguments)]
+fn spawn_group_member(
     commands: &mut Commands,
-    base_position: Vec3,
-    color_palette: &Res<ColorsPalette>,
+    entity_ids: &mut EntityIdCounter,
+    group: &SpawnGroup,
+    member: u32,
+    speed: f32,
+    play_field: &PlayField,
+    paths: &PathLibrary,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+    config: &GameConfig,
+) {
+    // Paths of the plan were checked against the library when it was loaded
+    let Some(path) = paths.get(&group.path) else {
+        return;
+    };
+    let Some(slot) = group.slots().nth(member as usize) else {
+        return;
+    };
+
+    let start = path.start(play_field);
+    let fly = spawn_fly(commands, entity_ids, start, group.kind, color_palette, game_assets, config);
+    commands.entity(fly).insert((
+        slot,
+        FollowPath::new(path).with_speed(speed),
+        EnteringFormation::from_path_end(start),
+        FlyState::Entering,
+    ));
+}
+
+/// Sends the remaining members of pending groups after the first one, the
+/// group being done once all of them are out
+#[allow(clippy::too_many_arguments)]
+fn spawn_group_members(
+    mut commands: Commands,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    mut pending_query: Query<(Entity, &mut PendingGroup)>,
+    mut remaining: ResMut<WaveRemaining>,
+    play_field: Res<PlayField>,
+    paths: Res<PathLibrary>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    for (entity, mut pending) in pending_query.iter_mut() {
+        pending.timer.tick(time.delta());
+
+        for _ in 0..pending.timer.times_finished_this_tick() {
+            if pending.spawned == pending.group.count {
+                break;
+            }
+            spawn_group_member(
+                &mut commands,
+                &mut entity_ids,
+                &pending.group,
+                pending.spawned,
+                pending.speed,
+                &play_field,
+                &paths,
+                &color_palette,
+                &game_assets,
+                &config,
+            );
+            pending.spawned += 1;
+            remaining.0 = remaining.0.saturating_sub(1);
+        }
+
+        if pending.spawned == pending.group.count {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// System that manages fly spawning: the next group of the `WavePlan` every
+/// time the spawn timer fires, once the previous group is all out and as long
+/// as the group fits under the fly cap. A group bigger than the cap still
+/// comes in on an empty field, or the wave could never finish.
+#[allow(clippy::too_many_arguments)]
+pub fn fly_spawner_system(
+    mut commands: Commands,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    mut spawn_timer: ResMut<FlySpawnTimer>,
+    mut remaining: ResMut<WaveRemaining>,
+    max_flies: Res<MaxFlies>,
+    fly_query: Query<(), With<Fly>>,
+    pending_query: Query<(), With<PendingGroup>>,
+    wave: Res<Wave>,
+    plan: Res<WavePlan>,
+    play_field: Res<PlayField>,
+    paths: Res<PathLibrary>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    if !spawn_timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if remaining.0 == 0 || !pending_query.is_empty() {
+        return; // The whole wave is out already, or a group is still coming in
+    }
+
+    let Some(group) = plan.next_group(&wave, remaining.0) else {
+        return;
+    };
+
+    let live_flies = fly_query.iter().count();
+
+    if live_flies > 0 && live_flies + group.count as usize > max_flies.0 {
+        return;
+    }
+
+    spawn_formation_wave(
+        &mut commands,
+        &mut entity_ids,
+        group,
+        plan.scaling(&wave),
+        &mut remaining,
+        &play_field,
+        &paths,
+        &color_palette,
+        &game_assets,
+        &config,
+    );
+}
+
+/// Advances each fly's wing frame at its own flap rate. Flies drawn without a
+/// sprite sheet have no atlas and are left alone.
+pub fn animate_flies(mut fly_query: Query<(&mut AnimationTimer, &mut Sprite)>, time: Res<Time>) {
+    for (mut animation, mut sprite) in fly_query.iter_mut() {
+        let delta = time.delta().mul_f32(animation.rate);
+        if !animation.timer.tick(delta).just_finished() {
+            continue;
+        }
+
+        if let Some(atlas) = sprite.texture_atlas.as_mut() {
+            atlas.index = (atlas.index + 1) % FLY_FRAMES;
+        }
+    }
+}
+
+/// Periodically makes a random living fly shoot at the player, more often on
+/// later waves, leading it where the difficulty says so. The mothership fires
+/// its own spreads instead.
+#[allow(clippy::too_many_arguments)]
+pub fn enemy_fire_system(
+    mut commands: Commands,
+    mut fire_timer: ResMut<EnemyFireTimer>,
+    wave: Res<Wave>,
+    fly_query: Query<&Transform, (With<Fly>, Without<Mothership>)>,
+    player_position: Res<PlayerPosition>,
+    player_velocity: Res<PlayerVelocity>,
+    difficulty: Res<DifficultyConfig>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    mut bullet_pool: ResMut<BulletPool>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
 ) {
-    let offsets = vec![
-        Vec3::new(0.0, 0.0, 0.0), // First fly at base_position
-        Vec3::new(100.0, 50.0, 0.0), // Second fly slightly offset
-        Vec3::new(-100.0, -50.0, 0.0), // Third fly slightly offset
-    ];
+    if wave.is_changed() || difficulty.is_changed() {
+        let interval = enemy_fire_interval(wave.0, &difficulty);
+        fire_timer.0.set_duration(std::time::Duration::from_secs_f32(interval));
+    }
 
-    for offset in offsets {
-        let position = base_position + offset;
-        spawn_fly(commands, position, color_palette);
+    if !fire_timer.0.tick(time.delta()).just_finished() {
+        return;
     }
+
+    let shooter = fly_query.iter().choose(&mut *game_rng);
+
+    if let Some(fly_transform) = shooter {
+        let lead_time = difficulty.lead_time_on(wave.0);
+        let target = aim_at_player(player_position.0, player_velocity.0, lead_time, &mut *game_rng);
+        spawn_enemy_bullet(
+            &mut commands,
+            &mut bullet_pool,
+            fly_transform.translation,
+            target,
+            difficulty.enemy_bullet_speed,
+            &color_palette,
+            &game_assets,
+        );
+    }
+}
+
+/// Where an enemy shot at the player aims: `lead_time` seconds ahead of the
+/// ship along its `velocity`, off by up to `AIM_ERROR` of that lead so it is
+/// not pixel-perfect. Without a lead, or at a ship standing still, that is
+/// right where the ship is.
+pub fn aim_at_player(position: Vec3, velocity: Vec2, lead_time: f32, rng: &mut impl Rng) -> Vec3 {
+    let lead = velocity * lead_time;
+    if lead == Vec2::ZERO {
+        return position;
+    }
+
+    let error = Vec2::new(rng.gen_range(-AIM_ERROR..=AIM_ERROR), rng.gen_range(-AIM_ERROR..=AIM_ERROR));
+    position + (lead + error * lead.length()).extend(0.0)
 }
 
-/// System that manages fly spawning
-pub fn fly_spawner_system(mut commands: Commands, color_palette: Res<ColorsPalette>) {
-    spawn_three_flies(&mut commands, Vec3::new(0.0, 200.0, 0.0), &color_palette);
+/// Fires an enemy bullet from just below `origin`, flying toward `target` at `speed`
+pub fn spawn_enemy_bullet(
+    commands: &mut Commands,
+    bullet_pool: &mut BulletPool,
+    origin: Vec3,
+    target: Vec3,
+    speed: f32,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+) {
+    let bullet_starting_position = origin - Vec3::new(0.0, 30.0, 0.0);
+    // Always head downward, even if the player somehow ends up above the fly
+    let direction = (target - bullet_starting_position).truncate().normalize_or(Vec2::NEG_Y);
+    let direction = if direction.y < 0.0 { direction } else { Vec2::NEG_Y };
+
+    checkout_bullet(
+        commands,
+        bullet_pool,
+        BulletOwner::Enemy,
+        bullet_starting_position,
+        direction * speed,
+        color_palette,
+        game_assets,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formation::FormationSlot, GameEntity};
+    use bevy::time::TimeUpdateStrategy;
+    use std::{collections::HashSet, time::Duration};
+
+    #[test]
+    fn wave_one_keeps_the_original_setup() {
+        assert_eq!(WavePlan::default().flies_in(&Wave(1)), MAX_FLIES);
+        assert_eq!(enemy_fire_interval(1, &DifficultyConfig::normal()), ENEMY_FIRE_INTERVAL);
+    }
+
+    #[test]
+    fn later_waves_are_bigger_and_shoot_faster() {
+        let plan = WavePlan::default();
+        assert!(plan.flies_in(&Wave(4)) > plan.flies_in(&Wave(1)));
+        assert_eq!(plan.flies_in(&Wave(100)), Formation::default().slots().count());
+        let normal = DifficultyConfig::normal();
+        assert!(enemy_fire_interval(3, &normal) < enemy_fire_interval(1, &normal));
+        assert_eq!(enemy_fire_interval(100, &normal), MIN_ENEMY_FIRE_INTERVAL);
+    }
+
+    #[test]
+    fn stationary_player_is_shot_straight_at() {
+        let mut rng = GameRng::from_seed(7);
+        let position = Vec3::new(120.0, -250.0, 0.0);
+        let hard = DifficultyConfig::hard();
+        assert_eq!(aim_at_player(position, Vec2::ZERO, hard.lead_time_on(1), &mut rng), position);
+
+        // Easy and Normal aim where the ship is, however fast it goes
+        let velocity = Vec2::new(400.0, 0.0);
+        let easy = DifficultyConfig::easy();
+        assert_eq!(aim_at_player(position, velocity, easy.lead_time_on(50), &mut rng), position);
+        let normal = DifficultyConfig::normal();
+        assert_eq!(aim_at_player(position, velocity, normal.lead_time_on(50), &mut rng), position);
+    }
+
+    #[test]
+    fn moving_player_is_led_but_not_pixel_perfect() {
+        let mut rng = GameRng::from_seed(7);
+        let position = Vec3::new(120.0, -250.0, 0.0);
+        let velocity = Vec2::new(400.0, 0.0);
+        let lead_time = DifficultyConfig::hard().lead_time_on(1);
+        let lead = velocity * lead_time;
+
+        let targets: Vec<Vec3> = (0..50).map(|_| aim_at_player(position, velocity, lead_time, &mut rng)).collect();
+        for target in &targets {
+            let miss = (*target - position).truncate() - lead;
+            assert!(miss.abs().max_element() <= AIM_ERROR * lead.length() + 0.001);
+            assert!(target.x > position.x);
+        }
+        assert!(targets.iter().any(|target| *target != targets[0]));
+    }
+
+    #[test]
+    fn every_spawned_fly_gets_its_own_id() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<GameConfig>()
+            .init_resource::<EntityIdCounter>()
+            .add_systems(
+                Update,
+                |mut commands: Commands,
+                 mut entity_ids: ResMut<EntityIdCounter>,
+                 color_palette: Res<ColorsPalette>,
+                 game_assets: Res<GameAssets>,
+                 config: Res<GameConfig>| {
+                    for _ in 0..100 {
+                        spawn_fly(
+                            &mut commands,
+                            &mut entity_ids,
+                            Vec3::ZERO,
+                            EnemyKind::Bee,
+                            &color_palette,
+                            &game_assets,
+                            &config,
+                        );
+                    }
+                },
+            );
+
+        app.update();
+
+        let ids: HashSet<u32> = app
+            .world_mut()
+            .query::<&GameEntity>()
+            .iter(app.world())
+            .map(|entity| entity.id)
+            .collect();
+        assert_eq!(ids.len(), 100);
+    }
+
+    /// Where every fly of the normal waves among the first 10 came in and the
+    /// slot it headed for, in spawn order, played through the spawner of an
+    /// app seeded with `seed`. Challenge stages have a spawner of their own.
+    fn ten_waves_from_seed(seed: u64) -> Vec<(Vec3, FormationSlot)> {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<GameConfig>()
+            .init_resource::<EntityIdCounter>()
+            .insert_resource(GameRng::from_seed(seed))
+            .init_resource::<PathLibrary>()
+            .init_resource::<PlayField>()
+            // Whole waves come in without any fly being shot down
+            .insert_resource(MaxFlies(usize::MAX))
+            .init_resource::<WavePlan>()
+            .insert_resource(Wave(1))
+            .insert_resource(WaveRemaining(0))
+            .insert_resource(FlySpawnTimer::new(0.5))
+            .add_systems(Update, (fly_spawner_system, spawn_group_members).chain());
+
+        let mut spawns = Vec::new();
+        for wave in (1..=10).map(Wave).filter(|wave| wave.kind() == WaveKind::Normal) {
+            let flies = app.world().resource::<WavePlan>().flies_in(&wave);
+            app.insert_resource(WaveRemaining(flies)).insert_resource(wave);
+
+            while app.world().resource::<WaveRemaining>().0 > 0 {
+                app.update();
+            }
+            app.update();
+
+            let mut wave_spawns: Vec<(u32, Vec3, FormationSlot)> = app
+                .world_mut()
+                .query::<(&GameEntity, &EnteringFormation, &FormationSlot)>()
+                .iter(app.world())
+                .map(|(entity, entering, slot)| (entity.id, entering.start, *slot))
+                .collect();
+            wave_spawns.sort_by_key(|(id, _, _)| *id);
+            assert_eq!(wave_spawns.len(), flies);
+            spawns.extend(wave_spawns.into_iter().map(|(_, start, slot)| (start, slot)));
+
+            // Clear the field for the next wave
+            let fly_entities: Vec<Entity> = app
+                .world_mut()
+                .query_filtered::<Entity, With<Fly>>()
+                .iter(app.world())
+                .collect();
+            for fly in fly_entities {
+                app.world_mut().despawn(fly);
+            }
+        }
+        spawns
+    }
+
+    #[test]
+    fn same_seed_spawns_the_same_waves() {
+        let first = ten_waves_from_seed(42);
+        let second = ten_waves_from_seed(42);
+
+        let plan = WavePlan::default();
+        let expected: usize = (1..=10)
+            .map(Wave)
+            .filter(|wave| wave.kind() == WaveKind::Normal)
+            .map(|wave| plan.flies_in(&wave))
+            .sum();
+        assert_eq!(first.len(), expected);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn spawner_brings_in_the_planned_groups_one_member_at_a_time() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<GameConfig>()
+            .init_resource::<EntityIdCounter>()
+            .init_resource::<PathLibrary>()
+            .init_resource::<PlayField>()
+            .init_resource::<MaxFlies>()
+            .init_resource::<WavePlan>()
+            .insert_resource(FlySpawnTimer::new(1.0))
+            .insert_resource(Wave(1))
+            .insert_resource(WaveRemaining(12))
+            .add_systems(Update, (fly_spawner_system, spawn_group_members).chain());
+
+        let flies = |app: &mut App| {
+            let mut flies: Vec<(EnemyKind, u32, u32)> = app
+                .world_mut()
+                .query_filtered::<(&EnemyKind, &FormationSlot), With<Fly>>()
+                .iter(app.world())
+                .map(|(kind, slot)| (*kind, slot.row, slot.col))
+                .collect();
+            flies.sort_by_key(|&(_, row, col)| (row, col));
+            flies
+        };
+
+        for _ in 0..12 {
+            app.update(); // 1.1 seconds, the first update has no elapsed time
+        }
+        // The first group has started, its members following one by one
+        assert_eq!(flies(&mut app).len(), 2);
+
+        for _ in 0..8 {
+            app.update(); // 1.9 seconds
+        }
+        assert_eq!(flies(&mut app), (0..3).map(|col| (EnemyKind::Bee, 2, col)).collect::<Vec<_>>());
+
+        for _ in 0..10 {
+            app.update(); // 2.9 seconds
+        }
+        assert_eq!(flies(&mut app), (0..6).map(|col| (EnemyKind::Bee, 2, col)).collect::<Vec<_>>());
+        assert_eq!(app.world().resource::<WaveRemaining>().0, 6);
+    }
 }
@@ -0,0 +1,369 @@
+use std::f32::consts::TAU;
+use bevy::prelude::*;
+use crate::{
+    capture::BeamDive,
+    difficulty::DifficultyConfig,
+    dive::Diving,
+    fly_logic::FlyState,
+    layer,
+    paths::FollowPath,
+    wave::{Wave, WaveKind, WaveRemaining},
+    wave_plan::WavePlan,
+    Fly, PlayField,
+};
+
+/// Units per second the formation marches at on wave 1
+const BASE_MARCH_SPEED: f32 = 40.0;
+
+/// Later waves never march faster than this, only thinning out speeds the formation up further
+const MAX_MARCH_SPEED: f32 = 120.0;
+
+/// How far the grid breathes on wave 1, as a fraction of each slot's distance to the center
+const BASE_BREATHING_AMPLITUDE: f32 = 0.08;
+
+/// Breathing never grows past this, however late the wave
+const MAX_BREATHING_AMPLITUDE: f32 = 0.16;
+
+/// Seconds for the grid to expand and contract once
+const BREATHING_PERIOD: f32 = 4.0;
+
+/// Grid of slots the flies settle into, marching side to side as a block
+#[derive(Resource)]
+pub struct Formation {
+    pub rows: u32,
+    pub cols: u32,
+    pub spacing: Vec2,        // Distance between neighbouring slots
+    pub origin: Vec3,         // Center of the top row before marching
+    pub march_offset: f32,    // Current horizontal shift of the whole grid
+    pub march_direction: f32, // +1.0 marching right, -1.0 marching left
+    pub march_speed: f32,     // Units per second
+    pub breathing: FormationAnimation,
+    pub alive_fraction: f32, // Share of the wave's formation flies not destroyed yet, 1.0 while all are
+}
+
+/// Idle "breathing" of the formation: the grid slowly expands and contracts
+/// around its center, scaling every slot's offset by `1 + amplitude * sin(phase)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormationAnimation {
+    pub phase: f32,     // Radians, a full breath every `TAU`
+    pub amplitude: f32, // Largest fraction the offsets grow or shrink by
+    pub period: f32,    // Seconds per breath
+}
+
+impl Default for FormationAnimation {
+    fn default() -> Self {
+        FormationAnimation {
+            phase: 0.0,
+            amplitude: breathing_amplitude(1),
+            period: BREATHING_PERIOD,
+        }
+    }
+}
+
+impl FormationAnimation {
+    /// Factor the slot offsets are currently scaled by
+    pub fn scale(&self) -> f32 {
+        1.0 + self.amplitude * self.phase.sin()
+    }
+}
+
+impl Default for Formation {
+    fn default() -> Self {
+        Formation {
+            rows: 3,
+            cols: 6,
+            spacing: Vec2::new(80.0, 60.0),
+            origin: Vec3::new(0.0, 280.0, layer::ENEMIES),
+            march_offset: 0.0,
+            march_direction: 1.0,
+            march_speed: march_speed(1, &DifficultyConfig::normal()),
+            breathing: FormationAnimation::default(),
+            alive_fraction: 1.0,
+        }
+    }
+}
+
+impl Formation {
+    /// Current world position of a slot, including the march offset and breathing
+    pub fn slot_position(&self, slot: &FormationSlot) -> Vec3 {
+        let center_col = (self.cols as f32 - 1.0) / 2.0;
+        let center_row = (self.rows as f32 - 1.0) / 2.0;
+        let center = self.origin + Vec3::new(self.march_offset, -center_row * self.spacing.y, 0.0);
+        let offset = Vec3::new(
+            (slot.col as f32 - center_col) * self.spacing.x,
+            -(slot.row as f32 - center_row) * self.spacing.y,
+            0.0,
+        );
+
+        center + offset * self.breathing.scale()
+    }
+
+    /// Every slot in the grid, top row first
+    pub fn slots(&self) -> impl Iterator<Item = FormationSlot> + '_ {
+        (0..self.rows).flat_map(move |row| (0..self.cols).map(move |col| FormationSlot { row, col }))
+    }
+}
+
+/// The grid slot a fly belongs to
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormationSlot {
+    pub row: u32,
+    pub col: u32,
+}
+
+/// A fly flying in along a quadratic bezier curve that ends on its slot. A
+/// fly still on its entrance path (`FollowPath`) only starts this curve once
+/// the path is over.
+#[derive(Component)]
+pub struct EnteringFormation {
+    pub start: Vec3,
+    pub control: Vec3,
+    pub progress: f32, // 0.0 at `start`, 1.0 on the slot
+    pub duration: f32, // Seconds the whole path takes
+}
+
+/// Seconds a fly needs to reach its slot
+const ENTRY_DURATION: f32 = 2.0;
+
+/// Seconds a fly needs from the end of its entrance path up to its slot
+const PATH_HOP_DURATION: f32 = 0.6;
+
+impl EnteringFormation {
+    /// Short hop from `end`, where an entrance path left the fly, up to its slot
+    pub fn from_path_end(end: Vec3) -> Self {
+        EnteringFormation {
+            start: end,
+            control: end,
+            progress: 0.0,
+            duration: PATH_HOP_DURATION,
+        }
+    }
+
+    /// Path dropping in from just above the top edge straight down onto a slot,
+    /// used by flies re-entering after a dive
+    pub fn from_top(slot_position: Vec3, play_field: &PlayField) -> Self {
+        let start = Vec3::new(slot_position.x, play_field.half_size().y + 50.0, slot_position.z);
+        EnteringFormation {
+            start,
+            control: start.lerp(slot_position, 0.5),
+            progress: 0.0,
+            duration: ENTRY_DURATION / 2.0,
+        }
+    }
+}
+
+/// Marching speed of the formation for a wave, growing as waves go up
+pub fn march_speed(wave: u32, difficulty: &DifficultyConfig) -> f32 {
+    let wave_factor = 1.0 + 0.15 * wave.saturating_sub(1) as f32;
+    (BASE_MARCH_SPEED * wave_factor).min(MAX_MARCH_SPEED) * difficulty.fly_speed
+}
+
+/// Breathing amplitude of the formation for a wave, growing slightly as waves go up
+pub fn breathing_amplitude(wave: u32) -> f32 {
+    (BASE_BREATHING_AMPLITUDE + 0.01 * wave.saturating_sub(1) as f32).min(MAX_BREATHING_AMPLITUDE)
+}
+
+/// Evaluates a quadratic bezier curve at `t` in `[0, 1]`
+pub fn quadratic_bezier(start: Vec3, control: Vec3, end: Vec3, t: f32) -> Vec3 {
+    let a = start.lerp(control, t);
+    let b = control.lerp(end, t);
+    a.lerp(b, t)
+}
+
+/// Keeps `Formation::alive_fraction` up to date: the formation flies still
+/// around and those yet to fly in, out of all the wave brings. Challenge
+/// stages have no formation to thin out and always count as whole.
+pub fn track_alive_fraction(
+    mut formation: ResMut<Formation>,
+    wave: Res<Wave>,
+    remaining: Res<WaveRemaining>,
+    plan: Res<WavePlan>,
+    fly_query: Query<(), (With<Fly>, With<FormationSlot>)>,
+) {
+    let total = plan.flies_in(&wave);
+    let alive_fraction = if wave.kind() == WaveKind::Challenge || total == 0 {
+        1.0
+    } else {
+        ((fly_query.iter().count() + remaining.0) as f32 / total as f32).min(1.0)
+    };
+
+    if formation.alive_fraction != alive_fraction {
+        formation.alive_fraction = alive_fraction;
+    }
+}
+
+/// Shifts the formation sideways, reversing when the outermost fly reaches a wall.
+/// The formation speeds up with every wave, and as it thins out.
+pub fn march_formation(
+    mut formation: ResMut<Formation>,
+    wave: Res<Wave>,
+    fly_query: Query<(&FormationSlot, &Transform), With<Fly>>,
+    play_field: Res<PlayField>,
+    difficulty: Res<DifficultyConfig>,
+    time: Res<Time>,
+) {
+    formation.march_speed = march_speed(wave.0, &difficulty) * difficulty.thinning_factor(formation.alive_fraction);
+
+    let delta = formation.march_direction * formation.march_speed * time.delta_secs();
+    formation.march_offset += delta;
+
+    let half_width = play_field.half_size().x;
+    for (slot, transform) in fly_query.iter() {
+        let slot_x = formation.slot_position(slot).x;
+        let half_fly = transform.scale.x / 2.0;
+
+        if formation.march_direction > 0.0 && slot_x + half_fly >= half_width {
+            formation.march_direction = -1.0;
+            break;
+        }
+        if formation.march_direction < 0.0 && slot_x - half_fly <= -half_width {
+            formation.march_direction = 1.0;
+            break;
+        }
+    }
+}
+
+/// Advances the breathing of the formation, breathing deeper on later waves
+/// and as it thins out
+pub fn breathe_formation(
+    mut formation: ResMut<Formation>,
+    wave: Res<Wave>,
+    difficulty: Res<DifficultyConfig>,
+    time: Res<Time>,
+) {
+    formation.breathing.amplitude = breathing_amplitude(wave.0) * difficulty.thinning_factor(formation.alive_fraction);
+
+    let breathing = &mut formation.breathing;
+    breathing.phase = (breathing.phase + TAU * time.delta_secs() / breathing.period) % TAU;
+}
+
+/// Moves entering flies along their curve, handing them over to the formation
+/// at the end. Flies still on their entrance path are left to `follow_paths`.
+pub fn follow_entry_path(
+    mut commands: Commands,
+    mut fly_query: Query<
+        (Entity, &mut Transform, &mut EnteringFormation, &FormationSlot),
+        Without<FollowPath>,
+    >,
+    formation: Res<Formation>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut entering, slot) in fly_query.iter_mut() {
+        entering.progress = (entering.progress + time.delta_secs() / entering.duration).min(1.0);
+
+        // The slot keeps marching, so the curve always ends where the slot is right now
+        let end = formation.slot_position(slot);
+        transform.translation = quadratic_bezier(entering.start, entering.control, end, entering.progress);
+
+        if entering.progress >= 1.0 {
+            commands.entity(entity).remove::<EnteringFormation>().insert(FlyState::InFormation);
+        }
+    }
+}
+
+/// Keeps settled flies locked onto their marching, breathing slot. The slot
+/// moves continuously, so the flies glide along with it. Diving flies are left
+/// alone and pick a slot up again through their entry path as they re-enter.
+#[allow(clippy::type_complexity)]
+pub fn hold_formation(
+    mut fly_query: Query<
+        (&mut Transform, &FormationSlot),
+        (Without<EnteringFormation>, Without<Diving>, Without<BeamDive>),
+    >,
+    formation: Res<Formation>,
+) {
+    for (mut transform, slot) in fly_query.iter_mut() {
+        transform.translation = formation.slot_position(slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_never_overlap() {
+        let formation = Formation::default();
+        let positions: Vec<Vec3> = formation.slots().map(|slot| formation.slot_position(&slot)).collect();
+
+        assert_eq!(positions.len(), (formation.rows * formation.cols) as usize);
+        for (i, a) in positions.iter().enumerate() {
+            for b in positions.iter().skip(i + 1) {
+                // Flies are 50 units wide, slots must be further apart than that
+                assert!((a.x - b.x).abs() >= 50.0 || (a.y - b.y).abs() >= 50.0);
+            }
+        }
+    }
+
+    #[test]
+    fn entry_path_ends_on_the_slot() {
+        let formation = Formation::default();
+        let slot = FormationSlot { row: 1, col: 2 };
+        let entering = EnteringFormation::from_path_end(Vec3::new(0.0, 120.0, 0.0));
+        let end = formation.slot_position(&slot);
+
+        assert_eq!(quadratic_bezier(entering.start, entering.control, end, 0.0), entering.start);
+        assert_eq!(quadratic_bezier(entering.start, entering.control, end, 1.0), end);
+    }
+
+    #[test]
+    fn breathing_scales_slots_around_the_center() {
+        let mut formation = Formation::default();
+        let corner = FormationSlot { row: 0, col: 0 };
+        let center = formation.origin - Vec3::new(0.0, formation.spacing.y, 0.0);
+        let resting = formation.slot_position(&corner) - center;
+
+        formation.breathing.phase = TAU / 4.0;
+        let expanded = formation.slot_position(&corner) - center;
+        assert!((expanded - resting * (1.0 + formation.breathing.amplitude)).length() < 1e-3);
+
+        formation.breathing.phase = TAU * 3.0 / 4.0;
+        let contracted = formation.slot_position(&corner) - center;
+        assert!(contracted.length() < resting.length());
+
+        assert!(breathing_amplitude(5) > breathing_amplitude(1));
+        assert_eq!(breathing_amplitude(100), MAX_BREATHING_AMPLITUDE);
+    }
+
+    #[test]
+    fn formation_marches_faster_on_later_waves() {
+        let normal = DifficultyConfig::normal();
+        assert_eq!(march_speed(1, &normal), BASE_MARCH_SPEED);
+        assert!(march_speed(4, &normal) > march_speed(1, &normal));
+        assert_eq!(march_speed(100, &normal), MAX_MARCH_SPEED);
+    }
+
+    #[test]
+    fn formation_marches_faster_with_every_kill() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Formation>()
+            .insert_resource(Wave(1))
+            .insert_resource(WaveRemaining(0))
+            .init_resource::<WavePlan>()
+            .init_resource::<PlayField>()
+            .insert_resource(DifficultyConfig::normal())
+            .add_systems(Update, (track_alive_fraction, march_formation).chain());
+
+        let total = app.world().resource::<WavePlan>().flies_in(&Wave(1));
+        let slots: Vec<FormationSlot> = app.world().resource::<Formation>().slots().take(total).collect();
+        let flies: Vec<Entity> = slots
+            .into_iter()
+            .map(|slot| app.world_mut().spawn((Fly, slot, Transform::default())).id())
+            .collect();
+        app.update();
+        assert_eq!(app.world().resource::<Formation>().march_speed, BASE_MARCH_SPEED);
+
+        let mut speed = BASE_MARCH_SPEED;
+        for fly in flies {
+            app.world_mut().despawn(fly);
+            app.update();
+
+            let faster = app.world().resource::<Formation>().march_speed;
+            assert!(faster > speed, "{} after {}", faster, speed);
+            speed = faster;
+        }
+        let normal = DifficultyConfig::normal();
+        assert_eq!(speed, BASE_MARCH_SPEED * (1.0 + normal.thinning_speedup));
+    }
+}
@@ -0,0 +1,335 @@
+use bevy::{
+    asset::{LoadState, RenderAssetUsages, UntypedAssetId},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use crate::game_state::GameState;
+
+/// Every texture, sound and font: requested once at startup while a loading
+/// screen is shown, with missing textures swapped for plain colored sprites
+pub struct GameAssetsPlugin;
+
+impl Plugin for GameAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameAssets>()
+            .init_resource::<AfterLoading>()
+            .init_resource::<LoadingTimer>()
+            .add_systems(Startup, load_game_assets)
+            .add_systems(OnEnter(GameState::Loading), spawn_loading_screen)
+            .add_systems(Update, track_loading.run_if(in_state(GameState::Loading)))
+            .add_systems(Update, fall_back_to_plain_sprites);
+    }
+}
+
+/// Seconds to wait for assets before starting anyway with whatever is missing
+const LOADING_TIMEOUT: f32 = 10.0;
+
+/// State entered once loading is done, the menu unless it is skipped
+#[derive(Resource)]
+pub struct AfterLoading(pub GameState);
+
+impl Default for AfterLoading {
+    fn default() -> Self {
+        AfterLoading(GameState::Menu)
+    }
+}
+
+/// Time spent on the loading screen so far
+#[derive(Resource)]
+struct LoadingTimer(Timer);
+
+impl Default for LoadingTimer {
+    fn default() -> Self {
+        LoadingTimer(Timer::from_seconds(LOADING_TIMEOUT, TimerMode::Once))
+    }
+}
+
+/// Marks the fill of the loading progress bar
+#[derive(Component)]
+struct LoadingBarFill;
+
+/// Frames in the fly sprite sheet, laid out side by side
+pub const FLY_FRAMES: usize = 2;
+
+/// Size in pixels of a single fly frame
+const FLY_FRAME_SIZE: UVec2 = UVec2::new(32, 32);
+
+/// Width and height in pixels of the generated shield disc
+const SHIELD_TEXTURE_SIZE: u32 = 64;
+
+/// Handles to every asset the game uses, so nothing is requested from the
+/// asset server mid-run. A default image handle draws a plain square, so the
+/// palette color alone decides how the sprite looks.
+#[derive(Resource, Default)]
+pub struct GameAssets {
+    pub player: Handle<Image>,
+    pub fly: Handle<Image>, // Sprite sheet with `FLY_FRAMES` wing positions
+    pub fly_layout: Handle<TextureAtlasLayout>,
+    pub bullet: Handle<Image>,
+    pub shield: Handle<Image>, // Generated at startup, never loaded from a file
+    pub font: Handle<Font>,
+    pub music: Handle<AudioSource>,
+    pub intro_jingle: Handle<AudioSource>, // Played before the music at the start of a run
+    pub shoot_sound: Handle<AudioSource>,
+    pub enemy_death_sound: Handle<AudioSource>,
+    pub shield_break_sound: Handle<AudioSource>,
+}
+
+impl GameAssets {
+    /// Sprite drawing `image` tinted with `color`. The image is stretched to a
+    /// unit square, so the transform scale alone sets the on-screen size (and
+    /// the collision box) whatever the texture resolution.
+    pub fn sprite(image: &Handle<Image>, color: Color) -> Sprite {
+        Sprite {
+            image: image.clone(),
+            color,
+            custom_size: Some(Vec2::ONE),
+            ..Default::default()
+        }
+    }
+
+    /// Fly sprite on the first frame of its sheet. Without a fly texture it is a
+    /// plain square with no atlas, so there is nothing to animate.
+    pub fn fly_sprite(&self, color: Color) -> Sprite {
+        let mut sprite = GameAssets::sprite(&self.fly, color);
+        if self.fly != Handle::default() {
+            sprite.texture_atlas = Some(TextureAtlas {
+                layout: self.fly_layout.clone(),
+                index: 0,
+            });
+        }
+        sprite
+    }
+
+    /// Ids of every loaded file, for checking on their progress
+    fn loading_ids(&self) -> [UntypedAssetId; 9] {
+        [
+            self.player.id().untyped(),
+            self.fly.id().untyped(),
+            self.bullet.id().untyped(),
+            self.font.id().untyped(),
+            self.music.id().untyped(),
+            self.intro_jingle.id().untyped(),
+            self.shoot_sound.id().untyped(),
+            self.enemy_death_sound.id().untyped(),
+            self.shield_break_sound.id().untyped(),
+        ]
+    }
+}
+
+/// Requests every asset once, builds the fly sheet layout and draws the shield disc
+fn load_game_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let fly_layout = TextureAtlasLayout::from_grid(FLY_FRAME_SIZE, FLY_FRAMES as u32, 1, None, None);
+
+    commands.insert_resource(GameAssets {
+        player: asset_server.load("textures/player.png"),
+        fly: asset_server.load("textures/fly.png"),
+        fly_layout: layouts.add(fly_layout),
+        bullet: asset_server.load("textures/bullet.png"),
+        shield: images.add(disc_image(SHIELD_TEXTURE_SIZE)),
+        font: asset_server.load("fonts/Unibody.ttf"),
+        music: asset_server.load("sounds/galaga.ogg"),
+        intro_jingle: asset_server.load("sounds/intro.ogg"),
+        shoot_sound: asset_server.load("sounds/shooting.ogg"),
+        enemy_death_sound: asset_server.load("sounds/enemy_death.ogg"),
+        shield_break_sound: asset_server.load("sounds/shield_break.ogg"),
+    });
+}
+
+/// White disc on a transparent square of `size` pixels, tinted like any other
+/// sprite. A plain sprite would draw a square, so the round shield is drawn here.
+fn disc_image(size: u32) -> Image {
+    let radius = size as f32 / 2.0;
+    let data = (0..size * size)
+        .flat_map(|index| {
+            let pixel = Vec2::new((index % size) as f32, (index / size) as f32) + Vec2::splat(0.5);
+            let alpha = if pixel.distance(Vec2::splat(radius)) <= radius { 255 } else { 0 };
+            [255, 255, 255, alpha]
+        })
+        .collect();
+
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Shows "LOADING" above an empty progress bar
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            StateScoped(GameState::Loading),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(24.0),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            // The game font is still loading, so this uses the built-in one
+            parent.spawn((
+                Text::new("LOADING"),
+                TextFont {
+                    font_size: 40.0,
+                    ..Default::default()
+                },
+            ));
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(400.0),
+                        height: Val::Px(16.0),
+                        ..Default::default()
+                    },
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.3)),
+                ))
+                .with_children(|bar| {
+                    bar.spawn((
+                        LoadingBarFill,
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..Default::default()
+                        },
+                        BackgroundColor(Color::WHITE),
+                    ));
+                });
+        });
+}
+
+/// Fills the progress bar as assets finish loading, failed ones included, and
+/// moves on once all are done. After `LOADING_TIMEOUT` of real time it moves on anyway,
+/// logging what is still missing and drawing those textures as plain sprites.
+fn track_loading(
+    asset_server: Res<AssetServer>,
+    mut game_assets: ResMut<GameAssets>,
+    mut timer: ResMut<LoadingTimer>,
+    time: Res<Time<Real>>,
+    after_loading: Res<AfterLoading>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut fill_query: Query<&mut Node, With<LoadingBarFill>>,
+) {
+    let ids = game_assets.loading_ids();
+    let pending: Vec<UntypedAssetId> = ids
+        .into_iter()
+        .filter(|id| !is_done(&asset_server, *id))
+        .collect();
+
+    for mut node in fill_query.iter_mut() {
+        node.width = Val::Percent(100.0 * (ids.len() - pending.len()) as f32 / ids.len() as f32);
+    }
+
+    timer.0.tick(time.delta());
+    if !pending.is_empty() && !timer.0.finished() {
+        return;
+    }
+
+    if !pending.is_empty() {
+        for id in &pending {
+            warn!("Asset {:?} still not loaded, starting without it", asset_server.get_path(*id));
+        }
+
+        let GameAssets { player, fly, bullet, .. } = &mut *game_assets;
+        for handle in [player, fly, bullet] {
+            if pending.contains(&handle.id().untyped()) {
+                *handle = Handle::default();
+            }
+        }
+    }
+
+    info!(missing = pending.len(), "Loading done");
+    next_state.set(after_loading.0);
+}
+
+/// Whether the asset server is done with `id`, either way
+fn is_done(asset_server: &AssetServer, id: UntypedAssetId) -> bool {
+    matches!(asset_server.load_state(id), LoadState::Loaded | LoadState::Failed(_))
+}
+
+/// Replaces textures that failed to load with the default image, both in
+/// `GameAssets` for future spawns and on every sprite already using them.
+/// Those sprites also drop their atlas, a plain square has no frames.
+fn fall_back_to_plain_sprites(
+    asset_server: Res<AssetServer>,
+    mut game_assets: ResMut<GameAssets>,
+    mut sprite_query: Query<&mut Sprite>,
+) {
+    let GameAssets { player, fly, bullet, .. } = &mut *game_assets;
+
+    for handle in [player, fly, bullet] {
+        if !matches!(asset_server.load_state(handle.id()), LoadState::Failed(_)) {
+            continue;
+        }
+
+        warn!("Missing texture {:?}, using a plain colored sprite instead", handle.path());
+        let failed = std::mem::take(handle);
+        for mut sprite in sprite_query.iter_mut() {
+            if sprite.image == failed {
+                sprite.image = Handle::default();
+                sprite.texture_atlas = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn loading_gives_up_on_stuck_assets_after_the_timeout() {
+        let mut app = App::new();
+        let step = Duration::from_secs_f32(LOADING_TIMEOUT / 2.0 + 0.1);
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(step))
+            // Nothing ever loads these
+            .insert_resource(GameAssets {
+                player: Handle::weak_from_u128(1),
+                ..Default::default()
+            })
+            .init_resource::<LoadingTimer>()
+            .init_resource::<AfterLoading>()
+            .init_resource::<NextState<GameState>>()
+            .add_systems(Update, track_loading);
+
+        app.update();
+        app.update();
+        assert!(matches!(*app.world().resource::<NextState<GameState>>(), NextState::Unchanged));
+
+        app.update();
+        assert!(matches!(
+            *app.world().resource::<NextState<GameState>>(),
+            NextState::Pending(GameState::Menu)
+        ));
+        assert_eq!(app.world().resource::<GameAssets>().player, Handle::default());
+    }
+
+    #[test]
+    fn shield_disc_is_opaque_inside_and_clear_in_the_corners() {
+        let image = disc_image(8);
+        let alpha = |x: usize, y: usize| image.data[(y * 8 + x) * 4 + 3];
+
+        assert_eq!(alpha(4, 4), 255);
+        assert_eq!(alpha(0, 4), 255);
+        assert_eq!(alpha(0, 0), 0);
+        assert_eq!(alpha(7, 7), 0);
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+/// The one source of randomness for spawning, dives, drops and effects.
+/// Seeded once at startup, so passing the same `--seed` replays the same run.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seed the generator started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Seed taken from the clock, for runs started without `--seed`
+pub fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Logs the seed so a run worth debugging can be started again with it
+pub fn log_seed(game_rng: Res<GameRng>) {
+    info!(seed = game_rng.seed(), "Random seed (rerun with --seed to replay)");
+}
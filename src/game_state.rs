@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use crate::{
+    coop::CoopScore, game_assets::GameAssets, highscore::HighScores, players::PlayerSlots, stats::RunStats, Score,
+};
+
+/// Top-level flow of the game
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Loading, // Waits for every asset before showing the menu
+    Menu,
+    Playing,
+    TurnChange, // Between two turns of a two-player run, shows who plays next
+    GameOver,
+}
+
+/// Whether a run is in progress or frozen. Only exists while `Playing`, so
+/// input systems gated on `Running` never run on the menu screens. Pausing
+/// itself works by stopping virtual time, see `PausePlugin`.
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Playing)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Which page of the title screen is shown. Only exists while on the `Menu`.
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Menu)]
+pub enum MenuScreen {
+    #[default]
+    Main,
+    Options,
+    HighScores,
+    Practice,
+}
+
+/// Shows the game over screen with the final score, or both in a two-player or
+/// co-op run, the best one so far and the shooting results of the run, like
+/// the arcade. Runs once new high scores are in the table, so a new record
+/// shows up right away.
+pub fn spawn_game_over(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    score: Res<Score>,
+    slots: Res<PlayerSlots>,
+    coop_score: Option<Res<CoopScore>>,
+    high_scores: Res<HighScores>,
+    run_stats: Res<RunStats>,
+) {
+    let scores = if let Some(coop_score) = coop_score {
+        format!("Player 1: {}\nPlayer 2: {}", score.0, coop_score.0)
+    } else if slots.0.len() > 1 {
+        slots
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| format!("Player {}: {}", index + 1, slot.score))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        format!("Score: {}", score.0)
+    };
+
+    let best = match high_scores.entries.first() {
+        Some(best) => format!("{} {} ({})", best.score, best.name, best.difficulty),
+        None => "0".to_string(),
+    };
+
+    spawn_centered_text(
+        &mut commands,
+        &game_assets,
+        GameState::GameOver,
+        &format!(
+            "GAME OVER\n\n{}\nHigh score: {}\n\n{}\n\nPress Enter to play again",
+            scores,
+            best,
+            results_text(&run_stats)
+        ),
+    );
+}
+
+/// The arcade's results: shots fired, hits and the share of shots that hit
+fn results_text(run_stats: &RunStats) -> String {
+    format!(
+        "Shots fired: {}\nNumber of hits: {}\nHit/miss ratio: {:.1} %",
+        run_stats.shots_fired,
+        run_stats.hits,
+        run_stats.accuracy()
+    )
+}
+
+/// Starts a new run when Enter is pressed on the game over screen.
+/// Alt+Enter is left to the fullscreen toggle.
+pub fn start_on_enter(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let alt_held = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    if keyboard_input.just_pressed(KeyCode::Enter) && !alt_held {
+        next_state.set(GameState::Playing);
+    }
+}
+
+/// Spawns a full-screen node with centered text that lives only while in `state`
+pub fn spawn_centered_text<S: States>(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    state: S,
+    message: &str,
+) {
+    commands
+        .spawn((
+            StateScoped(state),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(message),
+                TextFont {
+                    font: game_assets.font.clone(),
+                    font_size: 40.0,
+                    ..Default::default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+        });
+}
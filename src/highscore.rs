@@ -0,0 +1,429 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{
+    attract::DemoRun,
+    coop::CoopScore,
+    difficulty::Difficulty,
+    game_state::GameState,
+    name_entry::{Candidate, NameEntry},
+    players::PlayerSlots,
+    practice::PracticeRun,
+    stats::LifetimeStats,
+};
+
+/// Scores kept in the table
+pub const TABLE_SIZE: usize = 10;
+
+/// The top 10 scores across runs, loaded at startup and saved at the end of
+/// every run along with the lifetime stats
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        let high_score_file = HighScoreFile(high_score_path());
+        let high_scores = high_score_file
+            .0
+            .as_deref()
+            .map(HighScores::load)
+            .unwrap_or_default();
+
+        app.insert_resource(high_scores)
+            .insert_resource(high_score_file)
+            // The demo plays itself and practice runs are played on easier terms, neither is ever a record
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                record_high_score
+                    .run_if(not(resource_exists::<DemoRun>))
+                    .run_if(not(resource_exists::<PracticeRun>)),
+            );
+    }
+}
+
+/// One line of the table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub name: String, // Three initials
+    pub score: u32,
+    #[serde(default)]
+    pub wave: u32, // Wave reached, 0 if unknown
+    #[serde(default)]
+    pub date: String, // YYYY-MM-DD, empty if unknown
+    #[serde(default)]
+    pub difficulty: Difficulty,
+}
+
+/// The best scores ever reached, highest first, saved together with the
+/// totals of every run. Fields missing from older files start out empty.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default)]
+    pub entries: Vec<HighScoreEntry>,
+    #[serde(default)]
+    pub totals: LifetimeStats,
+}
+
+/// High score files from before the table, holding a single record
+#[derive(Deserialize)]
+struct SingleHighScore {
+    score: u32,
+    #[serde(default)]
+    difficulty: Difficulty,
+    #[serde(default)]
+    totals: LifetimeStats,
+}
+
+/// High score files from before the difficulty was recorded
+#[derive(Deserialize)]
+struct LegacyHighScore(u32);
+
+impl From<SingleHighScore> for HighScores {
+    /// The old record becomes the only entry, without initials, wave or date
+    fn from(single: SingleHighScore) -> Self {
+        let mut high_scores = HighScores {
+            totals: single.totals,
+            ..Default::default()
+        };
+        if single.score > 0 {
+            high_scores.insert(HighScoreEntry {
+                name: "---".to_string(),
+                score: single.score,
+                wave: 0,
+                date: String::new(),
+                difficulty: single.difficulty,
+            });
+        }
+        high_scores
+    }
+}
+
+impl HighScores {
+    /// Reads the table from `path`. A missing or corrupt file is an empty
+    /// table. Files from before the table hold a single record, which becomes
+    /// its only entry, counting as Normal if saved without a difficulty.
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return HighScores::default();
+        };
+
+        // The single record first: every field of the table is optional, so
+        // an old file would read as an empty table
+        ron::from_str::<SingleHighScore>(&contents)
+            .map(HighScores::from)
+            .or_else(|_| ron::from_str::<HighScores>(&contents))
+            .or_else(|error| {
+                ron::from_str::<LegacyHighScore>(&contents)
+                    .map(|legacy| {
+                        HighScores::from(SingleHighScore {
+                            score: legacy.0,
+                            difficulty: Difficulty::Normal,
+                            totals: LifetimeStats::default(),
+                        })
+                    })
+                    .map_err(|_| error)
+            })
+            .map(|mut high_scores| {
+                high_scores.entries.sort_by(|a, b| b.score.cmp(&a.score));
+                high_scores.entries.truncate(TABLE_SIZE);
+                high_scores
+            })
+            .unwrap_or_else(|error| {
+                warn!("Ignoring invalid high scores in {}: {}", path.display(), error);
+                HighScores::default()
+            })
+    }
+
+    /// Writes the table to `path`. It goes to a temporary file first and is
+    /// then renamed over the old one, so a crash mid-save never leaves a
+    /// half-written file behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = ron::to_string(self).map_err(io::Error::other)?;
+        let temp_path = path.with_extension("ron.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, path)
+    }
+
+    /// The top score, 0 while the table is empty
+    pub fn best(&self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    /// Whether `score` would make it into the table. Ties with the last
+    /// entry don't push it out.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0 && (self.entries.len() < TABLE_SIZE || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Adds `entry` below every entry with the same score or more, dropping
+    /// whatever falls off the end. Returns its place, if it made it.
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        let place = self.entries.partition_point(|existing| existing.score >= entry.score);
+        if place >= TABLE_SIZE {
+            return None;
+        }
+        self.entries.insert(place, entry);
+        self.entries.truncate(TABLE_SIZE);
+        Some(place)
+    }
+
+    /// Writes the table to `file`, if there is one, logging a failed save
+    pub fn save_to(&self, file: &HighScoreFile) {
+        let Some(path) = file.0.as_deref() else {
+            return;
+        };
+
+        if let Err(error) = self.save(path) {
+            warn!("Could not save high scores to {}: {}", path.display(), error);
+        }
+    }
+}
+
+/// Where the high scores are stored, `None` if no data dir could be found
+#[derive(Resource)]
+pub struct HighScoreFile(pub Option<PathBuf>);
+
+/// `highscore.ron` inside the per-user data dir of the game
+fn high_score_path() -> Option<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+
+    Some(data_dir.join("galaga_bevy").join("highscore.ron"))
+}
+
+/// Today's date in UTC as YYYY-MM-DD
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400);
+    let (year, month, day) = civil_date(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Year, month and day of the date `days` after 1970-01-01, in the proleptic
+/// Gregorian calendar
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    // Counted in 400-year eras starting on 0000-03-01, so leap days end a year
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153; // March is 0
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Asks for initials for every score of the run that makes the table, the
+/// best first. Of a two-player run each player's score counts, of a co-op run
+/// both ships'. Every one of them is of the run's `difficulty`. Without any
+/// the table is saved right away, for the lifetime totals the run was added
+/// to, otherwise once the initials are in.
+pub fn record_high_score(
+    mut commands: Commands,
+    slots: Res<PlayerSlots>,
+    coop_score: Option<Res<CoopScore>>,
+    difficulty: Res<Difficulty>,
+    high_scores: Res<HighScores>,
+    high_score_file: Res<HighScoreFile>,
+) {
+    let mut candidates: Vec<Candidate> = slots
+        .0
+        .iter()
+        .enumerate()
+        .map(|(player, slot)| Candidate {
+            player,
+            score: slot.score,
+            wave: slot.wave,
+            difficulty: *difficulty,
+        })
+        .collect();
+    if let Some(coop_score) = coop_score {
+        candidates.push(Candidate {
+            player: 1,
+            score: coop_score.0,
+            wave: slots.0[0].wave,
+            difficulty: *difficulty,
+        });
+    }
+    candidates.sort_by(|a, b| b.score.cmp(&a.score));
+
+    // Each one has to place with the better ones of the run already in
+    let mut table = high_scores.clone();
+    candidates.retain(|candidate| {
+        let placed = table.qualifies(candidate.score);
+        if placed {
+            table.insert(HighScoreEntry {
+                name: String::new(),
+                score: candidate.score,
+                wave: candidate.wave,
+                date: String::new(),
+                difficulty: candidate.difficulty,
+            });
+        }
+        placed
+    });
+
+    if candidates.is_empty() {
+        high_scores.save_to(&high_score_file);
+        return;
+    }
+
+    info!(scores = candidates.len(), "New high scores");
+    commands.insert_resource(NameEntry::new(candidates));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::players::PlayerSlot;
+
+    /// A fresh directory under the system temp dir, unique to the test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("galaga_bevy_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entry(name: &str, score: u32) -> HighScoreEntry {
+        HighScoreEntry {
+            name: name.to_string(),
+            score,
+            wave: 3,
+            date: "2026-10-16".to_string(),
+            difficulty: Difficulty::Normal,
+        }
+    }
+
+    #[test]
+    fn high_scores_survive_a_save_and_load() {
+        let dir = temp_dir("highscore_round_trip");
+        let path = dir.join("highscore.ron");
+
+        let mut high_scores = HighScores::default();
+        high_scores.insert(entry("ABC", 4200));
+        high_scores.insert(entry("XYZ", 800));
+        high_scores.totals.runs = 7;
+        high_scores.totals.time_played = 95.5;
+        high_scores.save(&path).unwrap();
+        let loaded = HighScores::load(&path);
+        let leftover_temp = path.with_extension("ron.tmp").exists();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(loaded, high_scores);
+        assert!(!leftover_temp);
+    }
+
+    #[test]
+    fn table_keeps_the_best_ten_in_order() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=TABLE_SIZE as u32 {
+            assert!(high_scores.qualifies(score * 100));
+            high_scores.insert(entry("AAA", score * 100));
+        }
+        assert_eq!(high_scores.best(), 1000);
+
+        // A tie with the last entry doesn't place, nor does a score of nothing
+        assert!(!high_scores.qualifies(100));
+        assert!(!high_scores.qualifies(0));
+
+        assert_eq!(high_scores.insert(entry("NEW", 550)), Some(5));
+        assert_eq!(high_scores.insert(entry("TIE", 1000)), Some(1));
+        assert_eq!(high_scores.entries.len(), TABLE_SIZE);
+        assert_eq!(high_scores.entries.last().unwrap().score, 300);
+        assert_eq!(high_scores.insert(entry("LOW", 50)), None);
+    }
+
+    #[test]
+    fn missing_or_corrupt_file_loads_an_empty_table() {
+        let dir = temp_dir("highscore_corrupt");
+        let path = dir.join("highscore.ron");
+        assert_eq!(HighScores::load(&path), HighScores::default());
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "not a high score").unwrap();
+        let loaded = HighScores::load(&path);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(loaded, HighScores::default());
+    }
+
+    #[test]
+    fn single_record_loads_as_the_only_entry() {
+        let dir = temp_dir("highscore_single");
+        let path = dir.join("highscore.ron");
+
+        fs::create_dir_all(&dir).unwrap();
+        let totals = "(runs: 4, shots_fired: 100, hits: 40, flies_killed: 30, waves_cleared: 5, time_played: 600.0)";
+        fs::write(&path, format!("(score: 900, difficulty: Hard, totals: {})", totals)).unwrap();
+        let loaded = HighScores::load(&path);
+        fs::write(&path, "(1500)").unwrap();
+        let legacy = HighScores::load(&path);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(loaded.entries.len(), 1);
+        assert_eq!((loaded.best(), loaded.entries[0].difficulty), (900, Difficulty::Hard));
+        assert_eq!(loaded.totals.runs, 4);
+        assert_eq!((legacy.best(), legacy.entries[0].difficulty), (1500, Difficulty::Normal));
+    }
+
+    #[test]
+    fn entries_without_newer_fields_still_load() {
+        let dir = temp_dir("highscore_old_entries");
+        let path = dir.join("highscore.ron");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, r#"(entries: [(name: "ABC", score: 700)])"#).unwrap();
+        let loaded = HighScores::load(&path);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(loaded.entries[0].name, "ABC");
+        assert_eq!((loaded.entries[0].wave, loaded.entries[0].date.as_str()), (0, ""));
+        assert_eq!(loaded.totals, LifetimeStats::default());
+    }
+
+    #[test]
+    fn new_scores_carry_the_difficulty_of_the_run() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(PlayerSlots(vec![PlayerSlot {
+                score: 5000,
+                lives: 0,
+                wave: 4,
+            }]))
+            .init_resource::<HighScores>()
+            .insert_resource(HighScoreFile(None))
+            .insert_resource(Difficulty::Hard)
+            .add_systems(Update, record_high_score);
+
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<NameEntry>(),
+            &NameEntry::new(vec![Candidate {
+                player: 0,
+                score: 5000,
+                wave: 4,
+                difficulty: Difficulty::Hard,
+            }])
+        );
+    }
+
+    #[test]
+    fn dates_count_from_1970() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(20_742), (2026, 10, 16));
+    }
+}
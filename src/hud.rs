@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use crate::{
+    bomb::Bombs,
+    coop::CoopRun,
+    game_assets::GameAssets,
+    game_state::GameState,
+    highscore::HighScores,
+    lives::Lives,
+    players::{CurrentPlayer, PlayerSlots},
+    powerup::WeaponUpgrade,
+    resize::FieldAnchor,
+    wave::Wave,
+    ColorsPalette, Player, Score,
+};
+
+/// Opacity of the score of the player waiting for their turn
+const WAITING_PLAYER_ALPHA: f32 = 0.5;
+
+/// On-screen score, lives, wave and bomb counters shown while playing
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_hud)
+            .add_systems(
+                Update,
+                (
+                    update_score_text,
+                    update_high_score_text,
+                    update_lives_text,
+                    update_wave_text,
+                    update_upgrade_text,
+                    update_bombs_text,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Shows the score of the player in play
+#[derive(Component)]
+pub struct ScoreText;
+
+/// Shows the best score so far, including the run in progress
+#[derive(Component)]
+pub struct HighScoreText;
+
+#[derive(Component)]
+pub struct LivesText;
+
+#[derive(Component)]
+pub struct WaveText;
+
+/// Shows the time left on the twin shot, empty while it is not active
+#[derive(Component)]
+pub struct UpgradeText;
+
+#[derive(Component)]
+pub struct BombsText;
+
+/// Spawns the HUD bar across the top of the play field. It is state scoped, so it
+/// disappears as soon as the game leaves Playing, and is spawned again for
+/// every turn. A two-player run shows both scores, the waiting one dimmed. In
+/// co-op this is the first ship's, `coop` shows the second one's.
+#[allow(clippy::too_many_arguments)]
+fn spawn_hud(
+    mut commands: Commands,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    score: Res<Score>,
+    slots: Res<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+    high_scores: Res<HighScores>,
+    lives: Res<Lives>,
+    wave: Res<Wave>,
+    bombs: Res<Bombs>,
+    coop_run: Option<Res<CoopRun>>,
+) {
+    let players = if coop_run.is_some() { 2 } else { slots.0.len() };
+    let text_font = TextFont {
+        font: game_assets.font.clone(),
+        font_size: 24.0,
+        ..Default::default()
+    };
+
+    commands
+        .spawn((
+            StateScoped(GameState::Playing),
+            // Spans the play field, not the whole window, when there are bars beside it
+            FieldAnchor { margin: 0.0 },
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                top: Val::Px(0.0),
+                justify_content: JustifyContent::SpaceBetween,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            for (player, slot) in slots.0.iter().enumerate() {
+                if player == current.0 {
+                    parent.spawn((
+                        ScoreText,
+                        Text::new(score_label(score.0, player, players)),
+                        text_font.clone(),
+                        TextColor(color_palette.player_bullet_color),
+                    ));
+                } else {
+                    // Nobody scores for the waiting player until their turn, so it is never updated
+                    parent.spawn((
+                        Text::new(score_label(slot.score, player, players)),
+                        text_font.clone(),
+                        TextColor(Color::srgba(1.0, 1.0, 1.0, WAITING_PLAYER_ALPHA)),
+                    ));
+                }
+            }
+            parent.spawn((
+                HighScoreText,
+                Text::new(high_score_label(&score, &high_scores)),
+                text_font.clone(),
+                TextColor(color_palette.player_bullet_color),
+            ));
+            parent.spawn((
+                WaveText,
+                Text::new(wave_label(&wave)),
+                text_font.clone(),
+                TextColor(color_palette.fly_color),
+            ));
+            parent.spawn((
+                UpgradeText,
+                Text::default(),
+                text_font.clone(),
+                TextColor(color_palette.powerup_color),
+            ));
+            parent.spawn((
+                BombsText,
+                Text::new(bombs_label(&bombs)),
+                text_font.clone(),
+                TextColor(color_palette.bomb_color),
+            ));
+            parent.spawn((
+                LivesText,
+                Text::new(lives_label(&lives)),
+                text_font,
+                TextColor(color_palette.player_color),
+            ));
+        });
+}
+
+/// "Score: N" alone, "1UP: N" and "2UP: N" with two players or in co-op
+pub fn score_label(score: u32, player: usize, players: usize) -> String {
+    if players > 1 {
+        format!("{}UP: {}", player + 1, score)
+    } else {
+        format!("Score: {}", score)
+    }
+}
+
+fn high_score_label(score: &Score, high_scores: &HighScores) -> String {
+    format!("Hi: {}", high_scores.best().max(score.0))
+}
+
+fn lives_label(lives: &Lives) -> String {
+    format!("Lives: {}", lives.0)
+}
+
+fn wave_label(wave: &Wave) -> String {
+    format!("Wave: {}", wave.0)
+}
+
+fn bombs_label(bombs: &Bombs) -> String {
+    format!("Bombs: {}", bombs.0)
+}
+
+fn upgrade_label(upgrade: Option<&WeaponUpgrade>) -> String {
+    match upgrade {
+        Some(upgrade) => format!("Twin: {:.0}s", upgrade.0.remaining_secs().ceil()),
+        None => String::new(),
+    }
+}
+
+/// Refreshes the score text whenever the score changes
+fn update_score_text(
+    score: Res<Score>,
+    slots: Res<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+    coop_run: Option<Res<CoopRun>>,
+    mut query: Query<&mut Text, With<ScoreText>>,
+) {
+    if !score.is_changed() {
+        return;
+    }
+
+    let players = if coop_run.is_some() { 2 } else { slots.0.len() };
+    for mut text in query.iter_mut() {
+        text.0 = score_label(score.0, current.0, players);
+    }
+}
+
+/// Refreshes the high score text whenever the score or the record changes
+fn update_high_score_text(
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    mut query: Query<&mut Text, With<HighScoreText>>,
+) {
+    if !score.is_changed() && !high_scores.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.0 = high_score_label(&score, &high_scores);
+    }
+}
+
+/// Refreshes the lives text whenever a life is lost
+fn update_lives_text(lives: Res<Lives>, mut query: Query<&mut Text, With<LivesText>>) {
+    if !lives.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.0 = lives_label(&lives);
+    }
+}
+
+/// Refreshes the wave text whenever a new wave starts
+fn update_wave_text(wave: Res<Wave>, mut query: Query<&mut Text, With<WaveText>>) {
+    if !wave.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.0 = wave_label(&wave);
+    }
+}
+
+/// Refreshes the bombs text whenever a bomb is dropped or picked up
+fn update_bombs_text(bombs: Res<Bombs>, mut query: Query<&mut Text, With<BombsText>>) {
+    if !bombs.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.0 = bombs_label(&bombs);
+    }
+}
+
+/// Shows the countdown of the twin shot while the player has it
+fn update_upgrade_text(
+    upgrade_query: Query<&WeaponUpgrade, With<Player>>,
+    mut query: Query<&mut Text, With<UpgradeText>>,
+) {
+    let label = upgrade_label(upgrade_query.get_single().ok());
+
+    for mut text in query.iter_mut() {
+        if text.0 != label {
+            text.0 = label.clone();
+        }
+    }
+}
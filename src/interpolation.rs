@@ -0,0 +1,154 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use crate::{config::GameConfig, Fly, Velocity};
+
+/// Moves longer than this in a single step are jumps, like a fly going back to
+/// the top after a dive, and are drawn where they end rather than smeared out
+const MAX_INTERPOLATED_STEP: f32 = 100.0;
+
+/// Draws what the fixed-rate simulation moves part way between its last two
+/// steps, so motion stays smooth on screens refreshing faster than it ticks.
+/// Only the rendered `GlobalTransform` is touched: `Transform` keeps the true
+/// position of the last step, which is all collision and gameplay ever read.
+/// Turned off with `interpolation` in the config.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedFirst, (start_interpolating, store_previous_translations).chain())
+            .add_systems(
+                PostUpdate,
+                // Once `GlobalTransform` holds the true positions, before anything is drawn
+                interpolate_rendered_translations
+                    .after(TransformSystem::TransformPropagate)
+                    .run_if(interpolation_enabled),
+            );
+    }
+}
+
+/// Where the entity was at the end of the step before the last one
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct PreviousTransform(pub Vec3);
+
+fn interpolation_enabled(config: Res<GameConfig>) -> bool {
+    config.interpolation
+}
+
+/// Starts tracking everything the simulation moves: bullets, missiles and
+/// pickups by their velocity, and the flies. The player ship is steered in
+/// `Update`, every frame, so it needs none.
+#[allow(clippy::type_complexity)]
+fn start_interpolating(
+    mut commands: Commands,
+    moving_query: Query<(Entity, &Transform), (Or<(With<Velocity>, With<Fly>)>, Without<PreviousTransform>)>,
+) {
+    for (entity, transform) in moving_query.iter() {
+        commands.entity(entity).insert(PreviousTransform(transform.translation));
+    }
+}
+
+/// Remembers where everything is before the coming step moves it
+fn store_previous_translations(mut query: Query<(&Transform, &mut PreviousTransform)>) {
+    for (transform, mut previous) in query.iter_mut() {
+        previous.0 = transform.translation;
+    }
+}
+
+/// Where to draw something that moved from `previous` to `current` in the
+/// last step, `alpha` of the way into the next one
+fn rendered_translation(previous: Vec3, current: Vec3, alpha: f32) -> Vec3 {
+    if previous.distance(current) > MAX_INTERPOLATED_STEP {
+        return current;
+    }
+    previous.lerp(current, alpha)
+}
+
+/// Sets the rendered position of every tracked entity between its last two
+/// steps, by how far time has run into the next one, and carries its children
+/// along. Written outright every frame: propagation leaves a `GlobalTransform`
+/// alone while its `Transform` doesn't change.
+fn interpolate_rendered_translations(
+    fixed_time: Res<Time<Fixed>>,
+    moving_query: Query<(Entity, &Transform, &PreviousTransform), Without<Parent>>,
+    hierarchy_query: Query<(&Transform, Option<&Children>)>,
+    mut global_query: Query<&mut GlobalTransform>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+
+    for (entity, transform, previous) in moving_query.iter() {
+        let rendered = Transform {
+            translation: rendered_translation(previous.0, transform.translation, alpha),
+            ..*transform
+        };
+        set_rendered_transform(entity, GlobalTransform::from(rendered), &hierarchy_query, &mut global_query);
+    }
+}
+
+/// Gives `entity` the rendered transform `global` and its descendants theirs relative to it
+fn set_rendered_transform(
+    entity: Entity,
+    global: GlobalTransform,
+    hierarchy_query: &Query<(&Transform, Option<&Children>)>,
+    global_query: &mut Query<&mut GlobalTransform>,
+) {
+    if let Ok(mut current) = global_query.get_mut(entity) {
+        *current = global;
+    }
+
+    let Ok((_, Some(children))) = hierarchy_query.get(entity) else {
+        return;
+    };
+    for child in children.iter() {
+        if let Ok((child_transform, _)) = hierarchy_query.get(*child) {
+            set_rendered_transform(*child, global.mul_transform(*child_transform), hierarchy_query, global_query);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jumps_are_drawn_where_they_end() {
+        let previous = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(rendered_translation(previous, Vec3::new(10.0, 0.0, 1.0), 0.25), Vec3::new(2.5, 0.0, 1.0));
+
+        let far = Vec3::new(0.0, MAX_INTERPOLATED_STEP * 2.0, 1.0);
+        assert_eq!(rendered_translation(previous, far, 0.25), far);
+    }
+
+    #[test]
+    fn only_the_rendered_position_is_interpolated() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_systems(Update, interpolate_rendered_translations);
+
+        let timestep = app.world().resource::<Time<Fixed>>().timestep();
+        app.world_mut().resource_mut::<Time<Fixed>>().accumulate(timestep / 2);
+
+        let child = app
+            .world_mut()
+            .spawn((Transform::from_xyz(0.0, 5.0, 0.0), GlobalTransform::default()))
+            .id();
+        let fly = app
+            .world_mut()
+            .spawn((
+                Fly,
+                Transform::from_xyz(10.0, 0.0, 0.0),
+                GlobalTransform::default(),
+                PreviousTransform(Vec3::ZERO),
+            ))
+            .add_child(child)
+            .id();
+        app.update();
+
+        let translation = |app: &App, entity| app.world().get::<GlobalTransform>(entity).unwrap().translation();
+        assert_eq!(translation(&app, fly), Vec3::new(5.0, 0.0, 0.0));
+        assert_eq!(translation(&app, child), Vec3::new(5.0, 5.0, 0.0));
+        assert_eq!(app.world().get::<Transform>(fly).unwrap().translation, Vec3::new(10.0, 0.0, 0.0));
+
+        // Written again on a frame the fly doesn't move, not drifting any further
+        app.update();
+        assert_eq!(translation(&app, fly), Vec3::new(5.0, 0.0, 0.0));
+    }
+}
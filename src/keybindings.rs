@@ -0,0 +1,163 @@
+use std::{fs, path::Path};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Optional file overriding the default key bindings
+const KEY_BINDINGS_PATH: &str = "assets/config/keybindings.ron";
+
+/// Keys that can be named in the key bindings file
+const BINDABLE_KEYS: [KeyCode; 56] = [
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+    KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+    KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+    KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+    KeyCode::KeyY, KeyCode::KeyZ,
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    KeyCode::ArrowLeft, KeyCode::ArrowRight, KeyCode::ArrowUp, KeyCode::ArrowDown,
+    KeyCode::Space, KeyCode::Enter, KeyCode::Escape, KeyCode::Tab, KeyCode::Backspace,
+    KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight,
+    KeyCode::AltLeft, KeyCode::AltRight,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon, KeyCode::Backquote,
+];
+
+/// Which keys trigger each player action
+pub struct KeyBindingsPlugin;
+
+impl Plugin for KeyBindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KeyBindings::load(Path::new(KEY_BINDINGS_PATH)));
+    }
+}
+
+/// Keys bound to each action, any of them triggers it
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    pub left: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub fire: Vec<KeyCode>,
+    pub bomb: Vec<KeyCode>,
+    pub missile: Vec<KeyCode>,
+    pub pause: Vec<KeyCode>,
+    pub coop_left: Vec<KeyCode>, // The co-op ship's controls
+    pub coop_right: Vec<KeyCode>,
+    pub coop_fire: Vec<KeyCode>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            left: vec![KeyCode::ArrowLeft, KeyCode::KeyA],
+            right: vec![KeyCode::ArrowRight, KeyCode::KeyD],
+            fire: vec![KeyCode::Space],
+            bomb: vec![KeyCode::KeyB],
+            missile: vec![KeyCode::ControlLeft],
+            pause: vec![KeyCode::Escape, KeyCode::KeyP],
+            coop_left: vec![KeyCode::KeyA],
+            coop_right: vec![KeyCode::KeyD],
+            coop_fire: vec![KeyCode::ShiftLeft],
+        }
+    }
+}
+
+/// Layout of the key bindings file. Actions left out keep their defaults.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct KeyBindingsOverrides {
+    left: Option<Vec<String>>,
+    right: Option<Vec<String>>,
+    fire: Option<Vec<String>>,
+    bomb: Option<Vec<String>>,
+    missile: Option<Vec<String>>,
+    pause: Option<Vec<String>>,
+    coop_left: Option<Vec<String>>,
+    coop_right: Option<Vec<String>>,
+    coop_fire: Option<Vec<String>>,
+}
+
+impl KeyBindings {
+    /// Reads overrides from `path`. Without a file the defaults are used.
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(contents) => KeyBindings::from_ron(&contents),
+            Err(_) => KeyBindings::default(),
+        }
+    }
+
+    /// Applies the overrides in `contents` to the defaults. A file that can't be
+    /// parsed, or an action naming an unknown key, keeps the defaults.
+    pub fn from_ron(contents: &str) -> Self {
+        let mut bindings = KeyBindings::default();
+
+        let overrides = match ron::from_str::<KeyBindingsOverrides>(contents) {
+            Ok(overrides) => overrides,
+            Err(error) => {
+                warn!("Ignoring invalid key bindings: {}", error);
+                return bindings;
+            }
+        };
+
+        for (action, names, keys) in [
+            ("left", overrides.left, &mut bindings.left),
+            ("right", overrides.right, &mut bindings.right),
+            ("fire", overrides.fire, &mut bindings.fire),
+            ("bomb", overrides.bomb, &mut bindings.bomb),
+            ("missile", overrides.missile, &mut bindings.missile),
+            ("pause", overrides.pause, &mut bindings.pause),
+            ("coop_left", overrides.coop_left, &mut bindings.coop_left),
+            ("coop_right", overrides.coop_right, &mut bindings.coop_right),
+            ("coop_fire", overrides.coop_fire, &mut bindings.coop_fire),
+        ] {
+            let Some(names) = names else {
+                continue;
+            };
+
+            let parsed: Result<Vec<KeyCode>, &String> =
+                names.iter().map(|name| key_from_name(name).ok_or(name)).collect();
+            match parsed {
+                Ok(parsed) => *keys = parsed,
+                Err(name) => warn!("Unknown key {:?} bound to {}, keeping the defaults", name, action),
+            }
+        }
+
+        bindings
+    }
+
+    /// Whether `key` controls the co-op ship, so the first ship leaves it alone in co-op
+    pub fn is_coop_key(&self, key: KeyCode) -> bool {
+        [&self.coop_left, &self.coop_right, &self.coop_fire]
+            .iter()
+            .any(|keys| keys.contains(&key))
+    }
+}
+
+/// Looks up a key by its `KeyCode` variant name, e.g. "KeyW" or "Space"
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|key| format!("{:?}", key) == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_only_the_listed_actions() {
+        let bindings = KeyBindings::from_ron(r#"(fire: ["KeyJ", "Enter"])"#);
+
+        assert_eq!(bindings.fire, vec![KeyCode::KeyJ, KeyCode::Enter]);
+        assert_eq!(bindings.left, KeyBindings::default().left);
+    }
+
+    #[test]
+    fn unknown_key_keeps_the_defaults() {
+        let bindings = KeyBindings::from_ron(r#"(left: ["KeyQ", "Banana"], right: ["KeyE"])"#);
+
+        assert_eq!(bindings.left, KeyBindings::default().left);
+        assert_eq!(bindings.right, vec![KeyCode::KeyE]);
+    }
+
+    #[test]
+    fn unparsable_file_keeps_the_defaults() {
+        assert_eq!(KeyBindings::from_ron("not ron at all"), KeyBindings::default());
+    }
+}
@@ -0,0 +1,25 @@
+//! Depth of everything drawn in the play field, back to front. Sprites are
+//! spawned on their layer so the draw order never depends on spawn order.
+//! Collision only ever looks at x and y, so layers don't change what hits.
+//! UI is drawn by bevy_ui on top of all of them.
+
+/// The starfield
+pub const BACKGROUND: f32 = 0.0;
+
+/// The translucent play field container
+pub const CONTAINER: f32 = 1.0;
+
+/// Flies, the mothership and their tractor beams
+pub const ENEMIES: f32 = 5.0;
+
+/// Bullets, missiles and falling pickups, above the flies firing them
+pub const BULLETS: f32 = 6.0;
+
+/// The player and co-op ships
+pub const PLAYER: f32 = 7.0;
+
+/// Explosions, muzzle flashes and score popups
+pub const EFFECTS: f32 = 8.0;
+
+/// Full screen flashes and the pause dimmer, in front of everything else
+pub const OVERLAY: f32 = 10.0;
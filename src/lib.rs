@@ -0,0 +1,683 @@
+pub mod player_input;
+pub mod fly_logic;
+pub mod formation;
+pub mod dive;
+pub mod bullet;
+pub mod game_state;
+pub mod lives;
+pub mod hud;
+mod explosion;
+pub mod sound;
+mod music;
+pub mod audio_settings;
+pub mod powerup;
+pub mod wave;
+pub mod wave_plan;
+pub mod highscore;
+pub mod name_entry;
+pub mod keybindings;
+mod pause;
+pub mod display;
+pub mod resize;
+mod starfield;
+pub mod game_assets;
+pub mod game_rng;
+mod score_popup;
+pub mod challenge;
+mod menu;
+pub mod capture;
+pub mod spatial_grid;
+pub mod collision;
+mod effects;
+mod debug_overlay;
+mod diagnostics_panel;
+mod screen_shake;
+mod interpolation;
+mod attract;
+pub mod difficulty;
+pub mod config;
+pub mod bomb;
+pub mod missile;
+pub mod players;
+pub mod coop;
+pub mod replay;
+pub mod mothership;
+pub mod palette_preset;
+pub mod paths;
+pub mod stats;
+pub mod run_intro;
+pub mod layer;
+mod wave_results;
+pub mod practice;
+pub mod spawn;
+
+use bevy::{prelude::*, render::camera::ScalingMode};
+use player_input::PlayerPlugin;
+use game_state::{spawn_game_over, start_on_enter, GameState, MenuScreen, PauseState};
+use lives::PLAYER_START;
+use spawn::{spawn_game_entity, SpawnSpec};
+use fly_logic::{EnemyKind, FlyPlugin, FlyState};
+use bullet::{release_bullet, BulletPlugin, Pooled};
+use collision::{CollisionPlugin, CollisionSet};
+use hud::HudPlugin;
+use explosion::ExplosionPlugin;
+use sound::SoundPlugin;
+use music::MusicPlugin;
+use audio_settings::AudioSettingsPlugin;
+use powerup::PowerUpPlugin;
+use wave::WavePlugin;
+use highscore::{record_high_score, HighScorePlugin};
+use name_entry::{NameEntry, NameEntryPlugin};
+use keybindings::KeyBindingsPlugin;
+use pause::PausePlugin;
+use display::DisplayPlugin;
+use resize::ResizePlugin;
+use starfield::StarfieldPlugin;
+use game_assets::{GameAssets, GameAssetsPlugin};
+use menu::MenuPlugin;
+use game_rng::log_seed;
+use score_popup::ScorePopupPlugin;
+use challenge::ChallengePlugin;
+use capture::CapturePlugin;
+use effects::EffectsPlugin;
+use debug_overlay::DebugOverlayPlugin;
+use diagnostics_panel::DiagnosticsPanelPlugin;
+use screen_shake::ScreenShakePlugin;
+use interpolation::InterpolationPlugin;
+use attract::{AttractPlugin, DemoRun};
+use difficulty::DifficultyPlugin;
+use config::ConfigPlugin;
+use bomb::BombPlugin;
+use missile::MissilePlugin;
+use players::{CurrentPlayer, PlayerSlots, PlayersPlugin};
+use coop::{CoopPlayer, CoopPlugin};
+use practice::PracticePlugin;
+use replay::ReplayPlugin;
+use run_intro::RunIntroPlugin;
+use wave_results::WaveResultsPlugin;
+use mothership::MothershipPlugin;
+use palette_preset::PalettePresetPlugin;
+use paths::PathsPlugin;
+use stats::StatsPlugin;
+
+// --------> Color Palette <---------
+#[derive(Resource, Clone)]
+pub struct ColorsPalette {
+    player_color: Color,
+    fly_color: Color,
+    bee_color: Color,
+    butterfly_color: Color,
+    boss_color: Color,
+    boss_damaged_color: Color,
+    mothership_color: Color,
+    mothership_enraged_color: Color,
+    player_bullet_color: Color,
+    enemy_bullet_color: Color,
+    powerup_color: Color,
+    bomb_color: Color,
+    shield_color: Color,
+    coop_player_color: Color,
+    hit_flash_color: Color,
+    background_color: Color,
+}
+
+impl Default for ColorsPalette {
+    fn default() -> Self {
+        ColorsPalette {
+            player_color: Color::srgb(0.2, 0.6, 1.0),         // Custom Blue
+            fly_color: Color::srgb(1.0, 0.0, 0.0),            // Custom Red
+            bee_color: Color::srgb(1.0, 0.85, 0.1),           // Yellow
+            butterfly_color: Color::srgb(1.0, 0.0, 0.0),      // Red
+            boss_color: Color::srgb(0.1, 0.8, 0.3),           // Green
+            boss_damaged_color: Color::srgb(0.6, 0.2, 0.9),   // Purple once hit
+            mothership_color: Color::srgb(0.85, 0.2, 0.7),    // Deep pink boss of every 5th wave
+            mothership_enraged_color: Color::srgb(1.0, 0.3, 0.1), // Burning orange below half health
+            player_bullet_color: Color::srgb(0.0, 1.0, 0.0),  // Custom Green
+            enemy_bullet_color: Color::srgb(1.0, 0.35, 0.55), // Pinkish red, like the arcade
+            powerup_color: Color::srgb(0.0, 1.0, 1.0),        // Cyan
+            bomb_color: Color::srgb(1.0, 0.55, 0.0),          // Orange bomb pickups
+            shield_color: Color::srgb(0.4, 0.8, 1.0),         // Pale blue shield bubble
+            coop_player_color: Color::srgb(1.0, 0.3, 0.9),    // Magenta second ship in co-op
+            hit_flash_color: Color::WHITE,                    // Flash when surviving a hit
+            background_color: Color::srgb(0.0, 0.0, 0.2),     // Dark Background
+        }
+    }
+}
+
+impl ColorsPalette {
+    /// Color of the bullets fired by `owner`
+    pub fn bullet_color(&self, owner: BulletOwner) -> Color {
+        match owner {
+            BulletOwner::Player => self.player_bullet_color,
+            BulletOwner::Enemy => self.enemy_bullet_color,
+        }
+    }
+}
+
+// --------> Structs <---------
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityType {
+    Player,
+    Fly,
+    Bullet,
+}
+
+#[derive(Component)]
+pub struct GameEntity {
+    pub id: u32, // Unique for the whole session, handed out by `EntityIdCounter`
+    pub entity_type: EntityType,
+}
+
+/// Source of `GameEntity` ids, counting up so an id is never handed out twice
+#[derive(Resource, Default)]
+pub struct EntityIdCounter(u32);
+
+impl EntityIdCounter {
+    /// Takes the next unused id. Ids start at 1.
+    fn next_id(&mut self) -> u32 {
+        self.0 += 1;
+        self.0
+    }
+}
+
+/// Marks the player ship
+#[derive(Component)]
+pub struct Player;
+
+/// Marks every fly enemy
+#[derive(Component)]
+pub struct Fly;
+
+/// Last known position of the player ship, copied from its transform every frame
+#[derive(Resource)]
+pub struct PlayerPosition(pub Vec3);
+
+/// Recent velocity of the player ship in units per second, from how far it
+/// moved between frames. Zero while there is no ship to track.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PlayerVelocity(pub Vec2);
+
+/// Default speed of newly fired bullets, in units per second
+#[derive(Resource)]
+pub struct BulletSpeed(pub f32);
+
+/// Which side fired a bullet, deciding what it is allowed to hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BulletOwner {
+    Player,
+    Enemy,
+}
+
+impl BulletOwner {
+    /// Size of the bullets this side fires. Enemy shots are thin and long, so
+    /// they read apart from the player's square ones at a glance.
+    pub fn bullet_scale(self) -> Vec3 {
+        match self {
+            BulletOwner::Player => Vec3::splat(10.0),
+            BulletOwner::Enemy => Vec3::new(6.0, 14.0, 1.0),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Bullet {
+    pub owner: BulletOwner,
+}
+
+/// Health a bullet takes off a fly it hits. Bullets without it deal 1.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Damage(pub u32);
+
+/// Player credited with what a bullet destroys: 0 for the first ship,
+/// `coop::COOP_PLAYER` for the co-op ship. Bullets without it are the first ship's.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FiredBy(pub usize);
+
+/// Units per second an entity moves by on its own, applied by `apply_velocity`
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Velocity(pub Vec2);
+
+/// Entities that are removed as soon as they leave the play field. The player,
+/// flies flying in or out of the formation, the camera and UI are never marked.
+#[derive(Component)]
+pub struct DespawnOutOfBounds;
+
+/// Systems reading the player's input and turning it into ship movement, shots,
+/// bombs and missiles. They run in `Update`, after the frame's `FixedUpdate`
+/// steps, so whatever they fire starts moving on the next step.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputSet;
+
+/// Systems moving bullets, flies and everything else in a `FixedUpdate` step,
+/// before `CollisionSet` checks the positions they end up at
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MovementSet;
+
+/// Systems removing what is left over at the end of a `FixedUpdate` step,
+/// after `CollisionSet` despawned what was hit
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CleanupSet;
+
+#[derive(Resource, Default)]
+pub struct Score(u32);
+
+/// Runtime debugging switches, toggled with Shift + F3, F3, F4 and F5
+#[derive(Resource, Default)]
+pub struct DebugSettings {
+    verbose: bool,     // Log per-frame details like bullet positions and collisions
+    diagnostics: bool, // Show frame rate and entity counts, see `diagnostics_panel`
+    overlay: bool,     // Draw enemy health and behavior, formation slots and dives, see `debug_overlay`
+    collisions: bool,  // Draw the box every hit check uses, see `draw_collision_debug`
+}
+
+/// Sent when a bullet touches something it is allowed to hit. Only detection
+/// emits it, what the hit does is up to the systems reading it.
+#[derive(Event)]
+pub struct BulletHit {
+    bullet: Entity,
+    target: Entity,
+    target_type: EntityType,
+}
+
+/// Sent whenever an enemy is destroyed, carrying the points it was worth
+#[derive(Event)]
+pub struct EnemyKilled {
+    entity: Entity, // Already despawned when the event is read
+    points: u32,
+    position: Vec3, // Where the enemy was destroyed
+    kind: EnemyKind,
+    player: usize, // Who gets the points, as in `FiredBy`
+}
+
+/// Sent when a player ship is hit by an enemy bullet or a ramming fly
+#[derive(Event)]
+pub struct PlayerHit {
+    cause: Entity, // The bullet or fly that hit the ship
+    ship: Entity,  // The first ship or the co-op ship
+}
+
+/// Narrowest a window can squeeze the play field, with room left for the formation to march
+const MIN_PLAY_FIELD_WIDTH: f32 = 600.0;
+
+/// Logical size of the playing area, centered on the origin
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PlayField {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for PlayField {
+    fn default() -> Self {
+        PlayField {
+            width: 1200.0,
+            height: 800.0,
+        }
+    }
+}
+
+impl PlayField {
+    /// Half the width and height, i.e. the distance from the center to each edge
+    fn half_size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height) / 2.0
+    }
+
+    /// The `design` field fitted to a window of `window_size`: as tall as
+    /// designed, and as wide as the window's shape allows, but never wider
+    /// than designed nor narrower than `MIN_PLAY_FIELD_WIDTH`
+    fn fitting(design: PlayField, window_size: Vec2) -> PlayField {
+        let aspect = window_size.x / window_size.y.max(1.0);
+        let width = (design.height * aspect).min(design.width).max(MIN_PLAY_FIELD_WIDTH.min(design.width));
+        PlayField {
+            width,
+            height: design.height,
+        }
+    }
+
+    /// Bars left beside and above the field on a window of `window_size`, in
+    /// logical pixels, with the camera fitted as in `scaling_mode`
+    fn screen_insets(&self, window_size: Vec2) -> Vec2 {
+        let size = Vec2::new(self.width, self.height);
+        let scale = (window_size / size).min_element();
+        ((window_size - size * scale) / 2.0).max(Vec2::ZERO)
+    }
+
+    /// `position` moved just far enough for something of `size` there to be
+    /// wholly inside the field
+    fn clamp(&self, position: Vec2, size: Vec2) -> Vec2 {
+        let limit = (self.half_size() - size / 2.0).max(Vec2::ZERO);
+        position.clamp(-limit, limit)
+    }
+
+    /// Camera scaling that always shows the whole play field as large as the
+    /// window allows, leaving bars on the sides that don't fit its aspect ratio
+    fn scaling_mode(&self) -> ScalingMode {
+        ScalingMode::AutoMin {
+            min_width: self.width,
+            min_height: self.height,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct OutlineContainer {
+    pub width: f32,
+    pub height: f32,
+}
+
+// --------> Plugins <---------
+
+/// The whole game, every gameplay and presentation plugin. The binary adds it
+/// on top of `DefaultPlugins`, after inserting anything picked on the command
+/// line (seed, difficulty, starting wave) so the plugins keep those values.
+pub struct GalagaPlugin;
+
+impl Plugin for GalagaPlugin {
+    fn build(&self, app: &mut App) {
+        // The config comes first, other plugins read it while they build
+        app.add_plugins(ConfigPlugin);
+
+        // Gameplay and presentation are added separately, a single tuple holds at most 15 plugins
+        app.add_plugins((
+            CorePlugin,
+            PlayerPlugin,
+            FlyPlugin,
+            BulletPlugin,
+            CollisionPlugin,
+            // The paths flies enter along, then the waves whose plan uses them
+            (PathsPlugin, WavePlugin),
+            // Waves that play differently from the rest
+            (ChallengePlugin, MothershipPlugin),
+            CapturePlugin,
+            PowerUpPlugin,
+            // Where the ship's input comes from: the player, or a replay
+            (KeyBindingsPlugin, ReplayPlugin),
+            // What holds play up: pausing, the intro of a run or a new ship, and the results of a wave
+            (PausePlugin, RunIntroPlugin, WaveResultsPlugin),
+            // Who is playing this run and whether it is for practice, the best scores of all runs and their stats
+            (PlayersPlugin, CoopPlugin, PracticePlugin, HighScorePlugin, NameEntryPlugin, StatsPlugin),
+            MenuPlugin,
+            DifficultyPlugin,
+            // Weapons beyond the main gun
+            (BombPlugin, MissilePlugin),
+        ))
+        .add_plugins((
+            HudPlugin,
+            ExplosionPlugin,
+            ScorePopupPlugin,
+            EffectsPlugin,
+            // Where things are drawn: shaken, and smoothed between simulation steps
+            (ScreenShakePlugin, InterpolationPlugin),
+            StarfieldPlugin,
+            SoundPlugin,
+            MusicPlugin,
+            AudioSettingsPlugin,
+            // Screen and colors
+            (DisplayPlugin, ResizePlugin, PalettePresetPlugin),
+            GameAssetsPlugin,
+            AttractPlugin,
+            // Debugging aids, all off until toggled
+            (DebugOverlayPlugin, DiagnosticsPanelPlugin),
+        ));
+    }
+}
+
+/// Steps per second of the gameplay simulation in `FixedUpdate`, whatever the frame rate
+const SIMULATION_HZ: f64 = 60.0;
+
+/// Shared setup: score, game states and the scene itself, sized to the play field.
+///
+/// Every `FixedUpdate` step runs `MovementSet`, then `CollisionSet::Detect` and
+/// `CollisionSet::Resolve`, then `CleanupSet`. Commands are applied between
+/// sets, so a bullet moving onto a fly hits it the same step and the cleanup
+/// never sees what collision already despawned. `InputSet` runs in `Update`,
+/// once the frame's steps are done.
+struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .insert_resource(Score(0))
+            .init_resource::<DebugSettings>()
+            .init_resource::<EntityIdCounter>()
+            .init_state::<GameState>()
+            .enable_state_scoped_entities::<GameState>()
+            .add_sub_state::<PauseState>()
+            .enable_state_scoped_entities::<PauseState>()
+            .add_sub_state::<MenuScreen>()
+            .enable_state_scoped_entities::<MenuScreen>()
+            .configure_sets(
+                FixedUpdate,
+                (MovementSet, CollisionSet::Detect, CollisionSet::Resolve, CleanupSet).chain(),
+            )
+            .add_systems(Startup, (setup_camera, log_seed))
+            .add_systems(Update, (toggle_verbose_logging, fit_camera_to_play_field))
+            .add_systems(OnEnter(GameState::Playing), setup_scene)
+            // Shown right away, or once the initials of every new high score are in
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                spawn_game_over
+                    .after(record_high_score)
+                    .run_if(not(resource_exists::<DemoRun>).and(not(resource_exists::<NameEntry>))),
+            )
+            .add_systems(
+                Update,
+                (
+                    spawn_game_over.run_if(resource_removed::<NameEntry>),
+                    start_on_enter.run_if(not(resource_exists::<NameEntry>)),
+                )
+                    .run_if(in_state(GameState::GameOver)),
+            )
+            .add_systems(FixedUpdate, despawn_out_of_bounds_entities.in_set(CleanupSet))
+            .add_systems(Update, update_score.run_if(in_state(PauseState::Running)));
+    }
+}
+
+// --------> Functions <---------
+
+/// Adds the points of every enemy the first ship killed to the score. The
+/// co-op ship's kills go to `CoopScore`.
+fn update_score(mut enemy_killed_events: EventReader<EnemyKilled>, mut score: ResMut<Score>) {
+    for event in enemy_killed_events.read().filter(|event| event.player == 0) {
+        debug!(enemy = ?event.entity, points = event.points, "Enemy killed");
+        score.0 += event.points;
+    }
+}
+
+/// Flips verbose per-frame logging on and off when Shift + F3 is pressed. F3
+/// alone shows the diagnostics panel.
+fn toggle_verbose_logging(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut debug_settings: ResMut<DebugSettings>,
+) {
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keyboard_input.just_pressed(KeyCode::F3) && shift {
+        debug_settings.verbose = !debug_settings.verbose;
+        info!(verbose = debug_settings.verbose, "Toggled verbose logging");
+    }
+}
+
+/// Despawns entities marked with `DespawnOutOfBounds` once they leave the
+/// boundaries of the play field. Player ships are never culled, even if marked:
+/// nothing would bring a lost one back, so one found outside, e.g. after a
+/// resize, is put back against the nearest wall instead. Flies diving or
+/// re-entering are left alone too: they leave the field on purpose and come
+/// back on their own. Pooled bullets go back to the pool instead of being despawned.
+#[allow(clippy::type_complexity)]
+pub fn despawn_out_of_bounds_entities(
+    mut commands: Commands,
+    query: Query<
+        (Entity, &Transform, Option<&FlyState>, Has<Pooled>),
+        (With<DespawnOutOfBounds>, Without<Player>, Without<CoopPlayer>),
+    >,
+    mut player_query: Query<
+        &mut Transform,
+        (Or<(With<Player>, With<CoopPlayer>)>, Without<DespawnOutOfBounds>),
+    >,
+    play_field: Res<PlayField>,
+    debug_settings: Res<DebugSettings>,
+) {
+    let half_size = play_field.half_size();
+    let container_width = half_size.x;
+    let container_height = half_size.y;
+
+    for (entity, transform, state, pooled) in query.iter() {
+        if matches!(state, Some(FlyState::Diving | FlyState::Reentering)) {
+            continue;
+        }
+
+        let pos = transform.translation;
+        if pos.x < -container_width
+            || pos.x > container_width
+            || pos.y < -container_height
+            || pos.y > container_height
+        {
+            if debug_settings.verbose {
+                debug!(position = ?pos, "Despawning entity outside bounds");
+            }
+            if pooled {
+                release_bullet(&mut commands, entity);
+            } else {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    for mut transform in player_query.iter_mut() {
+        let pos = transform.translation.truncate();
+        let clamped = play_field.clamp(pos, transform.scale.truncate());
+        if clamped != pos {
+            warn!(position = ?pos, "Player ship found outside the play field, moving it back in");
+            transform.translation = clamped.extend(transform.translation.z);
+        }
+    }
+}
+
+/// Spawns the camera, shared by every game state. It is scaled so the logical
+/// play field fills the window whatever its resolution.
+fn setup_camera(mut commands: Commands, play_field: Res<PlayField>) {
+    commands.spawn((
+        Camera2d,
+        OrthographicProjection {
+            scaling_mode: play_field.scaling_mode(),
+            ..OrthographicProjection::default_2d()
+        },
+    ));
+}
+
+/// Re-fits the camera to the play field whenever it changes, e.g. once a
+/// resized window has settled and the field was fitted to it. In between the
+/// camera keeps showing the field it has, scaled to the window as it goes.
+fn fit_camera_to_play_field(
+    play_field: Res<PlayField>,
+    mut projection_query: Query<&mut OrthographicProjection, With<Camera2d>>,
+) {
+    if !play_field.is_changed() {
+        return;
+    }
+
+    for mut projection in projection_query.iter_mut() {
+        projection.scaling_mode = play_field.scaling_mode();
+    }
+}
+
+/// Sets up a fresh run (player, boundary, score, etc.) when entering Playing.
+/// Everything spawned here is removed again when leaving Playing.
+fn setup_scene(
+    mut commands: Commands,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    play_field: Res<PlayField>,
+    slots: Res<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+) {
+    // Zero on a fresh run, what the player had so far on a later turn
+    commands.insert_resource(Score(slots.current(&current).score));
+
+    spawn_outline_container(&mut commands, Vec3::new(0.0, 0.0, layer::CONTAINER), &play_field);
+
+    spawn_game_entity(
+        &mut commands,
+        &mut entity_ids,
+        SpawnSpec::new(EntityType::Player, PLAYER_START, &color_palette, &game_assets),
+    );
+}
+
+/// Spawns the visible boundary container, sized to the play field
+fn spawn_outline_container(commands: &mut Commands, position: Vec3, play_field: &PlayField) {
+    let PlayField { width, height } = *play_field;
+
+    commands.spawn((
+        OutlineContainer { width, height },
+        StateScoped(GameState::Playing),
+        Sprite {
+            color: Color::srgba(0.0, 0.0, 0.0, 0.2),
+            ..Default::default()
+        },
+        Transform {
+            translation: position,
+            scale: Vec3::new(width, height, 1.0),
+            ..Default::default()
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_marked_entities_are_culled() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(PlayField {
+                width: 1200.0,
+                height: 800.0,
+            })
+            .init_resource::<DebugSettings>()
+            .add_systems(Update, despawn_out_of_bounds_entities);
+
+        let outside = Transform::from_xyz(0.0, 500.0, 0.0);
+        let marked = app.world_mut().spawn((DespawnOutOfBounds, outside)).id();
+        let unmarked = app.world_mut().spawn(outside).id();
+        let diving = app.world_mut().spawn((DespawnOutOfBounds, FlyState::Diving, outside)).id();
+        let inside = app
+            .world_mut()
+            .spawn((DespawnOutOfBounds, Transform::from_xyz(0.0, 100.0, 0.0)))
+            .id();
+
+        app.update();
+
+        assert!(app.world().get_entity(marked).is_err());
+        assert!(app.world().get_entity(unmarked).is_ok());
+        assert!(app.world().get_entity(diving).is_ok());
+        assert!(app.world().get_entity(inside).is_ok());
+    }
+
+    #[test]
+    fn player_outside_the_field_is_clamped_not_culled() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<PlayField>()
+            .init_resource::<DebugSettings>()
+            .add_systems(Update, despawn_out_of_bounds_entities);
+
+        let player = app
+            .world_mut()
+            .spawn((Player, Transform::from_xyz(700.0, -250.0, 0.0).with_scale(Vec3::splat(50.0))))
+            .id();
+
+        app.update();
+
+        let transform = app.world().get::<Transform>(player).expect("player was despawned");
+        assert_eq!(transform.translation, Vec3::new(575.0, -250.0, 0.0));
+    }
+
+    #[test]
+    fn enemy_bullets_look_different_from_the_players() {
+        let palette = ColorsPalette::default();
+
+        assert_ne!(palette.bullet_color(BulletOwner::Player), palette.bullet_color(BulletOwner::Enemy));
+        assert_ne!(BulletOwner::Player.bullet_scale(), BulletOwner::Enemy.bullet_scale());
+    }
+}
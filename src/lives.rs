@@ -0,0 +1,413 @@
+use bevy::prelude::*;
+use crate::{
+    coop::CoopLives,
+    difficulty::DifficultyConfig,
+    explosion::{Lifetime, Particle, Spin},
+    game_assets::GameAssets,
+    game_state::GameState,
+    players::{CurrentPlayer, PlayerSlots},
+    powerup::{break_shield, Shield},
+    practice::{game_speed, infinite_lives, PracticeRun},
+    run_intro::RunIntro,
+    sound::{Sfx, SfxEvent},
+    spawn::{spawn_game_entity, SpawnSpec},
+    ColorsPalette, EntityIdCounter, EntityType, Player, PlayerHit,
+};
+
+/// Where the player ship (re)appears
+pub const PLAYER_START: Vec3 = Vec3::new(0.0, -250.0, 0.0);
+
+/// Seconds between losing a ship and the replacement appearing
+const RESPAWN_DELAY: f32 = 1.5;
+
+/// Seconds from the ship breaking up to the respawn, turn change or game over
+const DEATH_SEQUENCE_DURATION: f32 = 1.0;
+
+/// Seconds of slow motion at the start of the death sequence
+const SLOW_MOTION_DURATION: f32 = 0.5;
+
+/// How fast game time runs during the slow motion
+const SLOW_MOTION_SPEED: f32 = 0.3;
+
+/// Units per second the pieces of a destroyed ship fly apart at
+const FRAGMENT_SPEED: f32 = 90.0;
+
+/// Radians per second the pieces of a destroyed ship turn, alternating direction
+const FRAGMENT_SPIN: f32 = 6.0;
+
+/// Seconds a freshly respawned ship ignores hits
+const INVULNERABILITY_DURATION: f32 = 2.0;
+
+/// Seconds an invulnerable ship stays visible or hidden before blinking again
+const BLINK_INTERVAL: f32 = 0.1;
+
+/// Ships the player has left, including the one currently in play
+#[derive(Resource)]
+pub struct Lives(pub u32);
+
+impl Default for Lives {
+    fn default() -> Self {
+        Lives(DifficultyConfig::normal().starting_lives)
+    }
+}
+
+/// Present while waiting to bring back a destroyed ship
+#[derive(Resource)]
+pub struct RespawnTimer(pub Timer);
+
+/// Present from the player ship being destroyed until the life is taken. Game
+/// time runs slow for the first part, enemies hold their fire and input is
+/// ignored. Timed on real time, so the slow motion doesn't stretch it.
+#[derive(Resource)]
+pub struct DeathSequence(pub Timer);
+
+impl Default for DeathSequence {
+    fn default() -> Self {
+        DeathSequence(Timer::from_seconds(DEATH_SEQUENCE_DURATION, TimerMode::Once))
+    }
+}
+
+/// Player ships carrying this ignore every hit until the timer runs out
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+impl Default for Invulnerable {
+    fn default() -> Self {
+        Invulnerable(Timer::from_seconds(INVULNERABILITY_DURATION, TimerMode::Once))
+    }
+}
+
+/// Breaks the ship up when the player is hit and starts the death sequence,
+/// after which `advance_death_sequence` takes the life. A shielded ship loses
+/// its shield instead. Hits on the co-op ship are left to `coop`.
+#[allow(clippy::type_complexity)]
+pub fn handle_player_hit(
+    mut commands: Commands,
+    mut player_hit_events: EventReader<PlayerHit>,
+    player_query: Query<(Entity, &Transform, &Sprite, Option<&Shield>), (With<Player>, Without<Invulnerable>)>,
+    images: Option<Res<Assets<Image>>>,
+    mut sfx_events: EventWriter<SfxEvent>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    practice: Option<Res<PracticeRun>>,
+) {
+    let Ok((player_entity, transform, sprite, shield)) = player_query.get_single() else {
+        player_hit_events.clear();
+        return;
+    };
+
+    // Several hits in the same frame only cost a single ship
+    let Some(hit) = player_hit_events.read().filter(|hit| hit.ship == player_entity).last() else {
+        return;
+    };
+
+    if let Some(shield) = shield {
+        break_shield(&mut commands, player_entity, shield);
+        sfx_events.send(SfxEvent(Sfx::ShieldBreak));
+        info!(cause = ?hit.cause, "Shield absorbed a hit");
+        return;
+    }
+
+    // Recursive so a docked second ship goes down with it
+    commands.entity(player_entity).despawn_recursive();
+    let image_size = images.as_deref().and_then(|images| images.get(&sprite.image)).map(Image::size_f32);
+    spawn_ship_fragments(&mut commands, transform, sprite, image_size);
+
+    commands.insert_resource(DeathSequence::default());
+    virtual_time.set_relative_speed(SLOW_MOTION_SPEED * game_speed(practice.as_deref()));
+    info!(cause = ?hit.cause, "Player destroyed");
+}
+
+/// Splits the ship into its four quarters, each flying away from the middle
+/// and spinning. `image_size` is the size of the ship texture in pixels, when
+/// it is loaded, so every piece shows its own quarter of it.
+fn spawn_ship_fragments(
+    commands: &mut Commands,
+    transform: &Transform,
+    sprite: &Sprite,
+    image_size: Option<Vec2>,
+) {
+    let half_scale = transform.scale / 2.0;
+
+    for (index, (x, y)) in [(-1.0, 1.0), (1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)].into_iter().enumerate() {
+        let direction = Vec2::new(x, y).normalize();
+        // Texture rows go down while world y goes up
+        let rect = image_size.map(|size| {
+            let left = if x < 0.0 { 0.0 } else { size.x / 2.0 };
+            let top = if y > 0.0 { 0.0 } else { size.y / 2.0 };
+            Rect::from_corners(Vec2::new(left, top), Vec2::new(left, top) + size / 2.0)
+        });
+        let spin = if index % 2 == 0 { FRAGMENT_SPIN } else { -FRAGMENT_SPIN };
+
+        commands.spawn((
+            Particle {
+                velocity: (direction * FRAGMENT_SPEED).extend(0.0),
+            },
+            Spin(spin),
+            Lifetime(Timer::from_seconds(DEATH_SEQUENCE_DURATION, TimerMode::Once)),
+            StateScoped(GameState::Playing),
+            Sprite {
+                rect,
+                ..sprite.clone()
+            },
+            Transform {
+                translation: transform.translation + (Vec2::new(x, y) * half_scale.truncate() / 2.0).extend(0.0),
+                rotation: transform.rotation,
+                scale: half_scale,
+            },
+        ));
+    }
+}
+
+/// Runs the death sequence on real time: game time goes back to the speed of
+/// the run once the slow motion is over, and the life is taken at the end. The
+/// speed is set outright rather than scaled back, so it always ends up exact.
+#[allow(clippy::too_many_arguments)]
+pub fn advance_death_sequence(
+    mut commands: Commands,
+    death_sequence: Option<ResMut<DeathSequence>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+    mut lives: ResMut<Lives>,
+    mut next_state: ResMut<NextState<GameState>>,
+    slots: Res<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+    coop_lives: Option<Res<CoopLives>>,
+    practice: Option<Res<PracticeRun>>,
+) {
+    let Some(mut death_sequence) = death_sequence else {
+        return;
+    };
+
+    let finished = death_sequence.0.tick(real_time.delta()).finished();
+    let speed = game_speed(practice.as_deref());
+    if death_sequence.0.elapsed_secs() >= SLOW_MOTION_DURATION && virtual_time.relative_speed() != speed {
+        virtual_time.set_relative_speed(speed);
+    }
+    if !finished {
+        return;
+    }
+
+    commands.remove_resource::<DeathSequence>();
+    let partner_lives = coop_lives.as_deref().map_or(0, |coop_lives| coop_lives.0);
+    let infinite = infinite_lives(practice.as_deref());
+    lose_life(&mut commands, &mut lives, &slots, &current, partner_lives, infinite, &mut next_state);
+    info!(lives = lives.0, "Life lost");
+}
+
+/// Drops a death sequence cut short by leaving the run, with game time back at full speed
+pub fn end_death_sequence(mut commands: Commands, mut virtual_time: ResMut<Time<Virtual>>) {
+    commands.remove_resource::<DeathSequence>();
+    virtual_time.set_relative_speed(1.0);
+}
+
+/// Takes a life after the ship in play was lost. In a two-player run the turn
+/// passes on while the other player has ships left. Otherwise the respawn
+/// delay starts, or once none are left the run ends, unless the co-op partner
+/// still has `partner_lives` ships and plays on alone. With `infinite` lives,
+/// as a practice run may have, no life is taken and the ship always comes back.
+pub fn lose_life(
+    commands: &mut Commands,
+    lives: &mut Lives,
+    slots: &PlayerSlots,
+    current: &CurrentPlayer,
+    partner_lives: u32,
+    infinite: bool,
+    next_state: &mut NextState<GameState>,
+) {
+    if !infinite {
+        lives.0 = lives.0.saturating_sub(1);
+    }
+
+    if slots.others_have_lives(current.0) {
+        next_state.set(GameState::TurnChange);
+    } else if lives.0 > 0 {
+        commands.insert_resource(RespawnTimer(Timer::from_seconds(RESPAWN_DELAY, TimerMode::Once)));
+    } else if partner_lives == 0 {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Brings the player back at the starting position once the respawn delay is
+/// over, held still for the short "READY" intro
+pub fn respawn_player(
+    mut commands: Commands,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    respawn_timer: Option<ResMut<RespawnTimer>>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    time: Res<Time>,
+) {
+    let Some(mut respawn_timer) = respawn_timer else {
+        return;
+    };
+
+    if !respawn_timer.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    commands.remove_resource::<RespawnTimer>();
+
+    spawn_game_entity(
+        &mut commands,
+        &mut entity_ids,
+        SpawnSpec::new(EntityType::Player, PLAYER_START, &color_palette, &game_assets).with(Invulnerable::default()),
+    );
+    commands.insert_resource(RunIntro::ready());
+}
+
+/// Counts down invulnerability and removes it when it expires, making sure the
+/// ship doesn't stay hidden mid-blink
+pub fn tick_invulnerability(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invulnerable, Option<&mut Visibility>)>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable, visibility) in query.iter_mut() {
+        if invulnerable.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            if let Some(mut visibility) = visibility {
+                *visibility = Visibility::Inherited;
+            }
+        }
+    }
+}
+
+/// Blinks invulnerable ships on and off every `BLINK_INTERVAL`. The phase comes
+/// from the invulnerability timer, so it pauses along with it.
+pub fn blink_invulnerable(mut query: Query<(&Invulnerable, &mut Visibility)>) {
+    for (invulnerable, mut visibility) in query.iter_mut() {
+        let blinks = (invulnerable.0.elapsed_secs() / BLINK_INTERVAL) as u32;
+        let target = if blinks % 2 == 0 { Visibility::Inherited } else { Visibility::Hidden };
+
+        if *visibility != target {
+            *visibility = target;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn ship_blinks_while_invulnerable_and_is_visible_after() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_systems(
+            Update,
+            (tick_invulnerability, blink_invulnerable.after(tick_invulnerability)),
+        );
+
+        let mut invulnerable = Invulnerable::default();
+        invulnerable.0.tick(Duration::from_secs_f32(BLINK_INTERVAL * 1.5));
+        let player = app.world_mut().spawn((Player, invulnerable, Visibility::Inherited)).id();
+
+        app.update();
+        assert_eq!(app.world().get::<Visibility>(player), Some(&Visibility::Hidden));
+
+        app.world_mut()
+            .get_mut::<Invulnerable>(player)
+            .unwrap()
+            .0
+            .tick(Duration::from_secs_f32(INVULNERABILITY_DURATION));
+        app.update();
+
+        assert!(app.world().get::<Invulnerable>(player).is_none());
+        assert_eq!(app.world().get::<Visibility>(player), Some(&Visibility::Inherited));
+    }
+
+    #[test]
+    fn death_sequence_slows_time_then_takes_a_single_life() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .insert_resource(Lives(3))
+            .init_resource::<PlayerSlots>()
+            .init_resource::<CurrentPlayer>()
+            .init_resource::<NextState<GameState>>()
+            .add_event::<PlayerHit>()
+            .add_event::<SfxEvent>()
+            .add_systems(Update, (handle_player_hit, advance_death_sequence.after(handle_player_hit)));
+
+        let player = app
+            .world_mut()
+            .spawn((
+                Player,
+                Sprite::default(),
+                Transform::from_translation(PLAYER_START).with_scale(Vec3::splat(50.0)),
+            ))
+            .id();
+        let cause = app.world_mut().spawn_empty().id();
+        let hit = |app: &mut App| {
+            app.world_mut().send_event(PlayerHit { cause, ship: player });
+        };
+        let speed = |app: &App| app.world().resource::<Time<Virtual>>().relative_speed();
+
+        // The first update has no elapsed time
+        hit(&mut app);
+        app.update();
+        assert!(app.world().get_entity(player).is_err());
+        assert_eq!(app.world_mut().query::<&Spin>().iter(app.world()).count(), 4);
+        assert_eq!(speed(&app), SLOW_MOTION_SPEED);
+
+        // A second hit in the middle of the slow motion changes nothing
+        for _ in 0..3 {
+            app.update();
+        }
+        hit(&mut app);
+        app.update();
+        assert_eq!(speed(&app), SLOW_MOTION_SPEED);
+        app.update();
+        assert_eq!(speed(&app), 1.0);
+        assert_eq!(app.world().resource::<Lives>().0, 3);
+
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(!app.world().contains_resource::<DeathSequence>());
+        assert!(app.world().contains_resource::<RespawnTimer>());
+        assert_eq!(app.world().resource::<Lives>().0, 2);
+        assert_eq!(speed(&app), 1.0);
+    }
+
+    #[test]
+    fn practice_keeps_its_speed_and_lives_through_a_death() {
+        use crate::practice::PracticeSettings;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .insert_resource(PracticeRun(PracticeSettings {
+                speed: 0.5,
+                infinite_lives: true,
+                ..Default::default()
+            }))
+            .insert_resource(Lives(1))
+            .init_resource::<PlayerSlots>()
+            .init_resource::<CurrentPlayer>()
+            .init_resource::<NextState<GameState>>()
+            .add_event::<PlayerHit>()
+            .add_event::<SfxEvent>()
+            .add_systems(Update, (handle_player_hit, advance_death_sequence.after(handle_player_hit)));
+        app.world_mut().resource_mut::<Time<Virtual>>().set_relative_speed(0.5);
+
+        let player = app
+            .world_mut()
+            .spawn((Player, Sprite::default(), Transform::from_translation(PLAYER_START)))
+            .id();
+        let cause = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(PlayerHit { cause, ship: player });
+        app.update();
+        let speed = |app: &App| app.world().resource::<Time<Virtual>>().relative_speed();
+        assert_eq!(speed(&app), SLOW_MOTION_SPEED * 0.5);
+
+        for _ in 0..12 {
+            app.update();
+        }
+        assert!(app.world().contains_resource::<RespawnTimer>());
+        assert_eq!(app.world().resource::<Lives>().0, 1);
+        assert!(matches!(app.world().resource::<NextState<GameState>>(), NextState::Unchanged));
+        assert_eq!(speed(&app), 0.5);
+    }
+}
@@ -0,0 +1,673 @@
+use bevy::{app::AppExit, prelude::*, window::PrimaryWindow};
+use crate::{
+    audio_settings::{AudioSettings, VOLUME_STEP},
+    coop::CoopRun,
+    difficulty::Difficulty,
+    display::DisplaySettings,
+    game_assets::GameAssets,
+    game_state::{GameState, MenuScreen},
+    highscore::HighScores,
+    palette_preset::PalettePreset,
+    players::PlayerCount,
+    practice::{PracticeRun, PracticeSettings},
+    ColorsPalette,
+};
+
+/// Title screen: a one-player, two-player or co-op start, Practice, High Scores,
+/// Options and Quit, plus the practice page for the starting wave, game speed,
+/// lives and enemy fire of a practice run, the high score table and the options
+/// page for volume, fullscreen, difficulty and colors. Driven by the arrow keys
+/// or a gamepad d-pad.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuSelection>()
+            .add_systems(OnEnter(MenuScreen::Main), spawn_main_menu)
+            .add_systems(OnEnter(MenuScreen::Options), spawn_options_menu)
+            .add_systems(OnEnter(MenuScreen::HighScores), spawn_high_scores_menu)
+            .add_systems(OnEnter(MenuScreen::Practice), spawn_practice_menu)
+            .add_systems(
+                Update,
+                (
+                    navigate_menu,
+                    confirm_menu_entry.after(navigate_menu),
+                    adjust_option.after(navigate_menu),
+                    leave_page.run_if(not(in_state(MenuScreen::Main))),
+                    highlight_selection.after(navigate_menu),
+                    refresh_option_labels.after(confirm_menu_entry).after(adjust_option),
+                )
+                    .run_if(in_state(GameState::Menu)),
+            );
+    }
+}
+
+/// Everything a menu entry can do when picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Start,           // One player
+    StartTwoPlayers, // Two players taking turns
+    StartCoop,       // Two ships on screen at once
+    Practice,        // Opens the practice page
+    StartPractice,
+    PracticeWave,
+    PracticeSpeed,
+    PracticeLives,
+    PracticeEnemyFire,
+    HighScores,
+    Options,
+    Quit,
+    Volume,
+    Fullscreen,
+    Difficulty,
+    Colors,
+    Back,
+}
+
+impl MenuScreen {
+    /// Entries of the page, top to bottom
+    fn entries(&self) -> &'static [MenuAction] {
+        match self {
+            MenuScreen::Main => &[
+                MenuAction::Start,
+                MenuAction::StartTwoPlayers,
+                MenuAction::StartCoop,
+                MenuAction::Practice,
+                MenuAction::HighScores,
+                MenuAction::Options,
+                MenuAction::Quit,
+            ],
+            MenuScreen::Options => &[
+                MenuAction::Volume,
+                MenuAction::Fullscreen,
+                MenuAction::Difficulty,
+                MenuAction::Colors,
+                MenuAction::Back,
+            ],
+            MenuScreen::HighScores => &[MenuAction::Back],
+            MenuScreen::Practice => &[
+                MenuAction::StartPractice,
+                MenuAction::PracticeWave,
+                MenuAction::PracticeSpeed,
+                MenuAction::PracticeLives,
+                MenuAction::PracticeEnemyFire,
+                MenuAction::Back,
+            ],
+        }
+    }
+}
+
+/// A selectable line of text on the current menu page
+#[derive(Component)]
+pub struct MenuEntry {
+    pub index: usize, // Position on the page, matching `MenuSelection`
+    pub action: MenuAction,
+}
+
+/// Index of the highlighted entry on the current page
+#[derive(Resource, Default)]
+pub struct MenuSelection(pub usize);
+
+/// Moves the selection `step` entries along a page of `len`, wrapping around
+/// at both ends
+pub fn step_selection(selection: usize, len: usize, step: isize) -> usize {
+    (selection as isize + step).rem_euclid(len as isize) as usize
+}
+
+/// Text shown for an entry. Options show their current value.
+pub fn entry_label(
+    action: MenuAction,
+    audio_settings: &AudioSettings,
+    display_settings: &DisplaySettings,
+    difficulty: Difficulty,
+    palette_preset: PalettePreset,
+    practice_settings: &PracticeSettings,
+) -> String {
+    match action {
+        MenuAction::Start => "1 Player".to_string(),
+        MenuAction::StartTwoPlayers => "2 Players".to_string(),
+        MenuAction::StartCoop => "Co-op".to_string(),
+        MenuAction::Practice => "Practice".to_string(),
+        MenuAction::StartPractice => "Start".to_string(),
+        MenuAction::PracticeWave => format!("< Wave: {} >", practice_settings.starting_wave),
+        MenuAction::PracticeSpeed => format!("< Speed: {}x >", practice_settings.speed),
+        MenuAction::PracticeLives => {
+            let lives = if practice_settings.infinite_lives { "Infinite" } else { "Normal" };
+            format!("< Lives: {} >", lives)
+        }
+        MenuAction::PracticeEnemyFire => {
+            let fire = if practice_settings.enemy_fire { "On" } else { "Off" };
+            format!("< Enemy Fire: {} >", fire)
+        }
+        MenuAction::HighScores => "High Scores".to_string(),
+        MenuAction::Options => "Options".to_string(),
+        MenuAction::Quit => "Quit".to_string(),
+        MenuAction::Volume if audio_settings.muted => "< Volume: Muted >".to_string(),
+        MenuAction::Volume => format!("< Volume: {:.0}% >", audio_settings.master * 100.0),
+        MenuAction::Fullscreen => {
+            let mode = if display_settings.fullscreen { "On" } else { "Off" };
+            format!("< Fullscreen: {} >", mode)
+        }
+        MenuAction::Difficulty => format!("< Difficulty: {} >", difficulty),
+        MenuAction::Colors => format!("< Colors: {} >", palette_preset),
+        MenuAction::Back => "Back".to_string(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_main_menu(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    audio_settings: Res<AudioSettings>,
+    display_settings: Res<DisplaySettings>,
+    difficulty: Res<Difficulty>,
+    palette_preset: Res<PalettePreset>,
+    practice_settings: Res<PracticeSettings>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    spawn_menu_page(
+        &mut commands,
+        &game_assets,
+        &color_palette,
+        &audio_settings,
+        &display_settings,
+        *difficulty,
+        *palette_preset,
+        &practice_settings,
+        &mut selection,
+        MenuScreen::Main,
+        "GALAGA",
+        None,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_options_menu(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    audio_settings: Res<AudioSettings>,
+    display_settings: Res<DisplaySettings>,
+    difficulty: Res<Difficulty>,
+    palette_preset: Res<PalettePreset>,
+    practice_settings: Res<PracticeSettings>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    spawn_menu_page(
+        &mut commands,
+        &game_assets,
+        &color_palette,
+        &audio_settings,
+        &display_settings,
+        *difficulty,
+        *palette_preset,
+        &practice_settings,
+        &mut selection,
+        MenuScreen::Options,
+        "OPTIONS",
+        None,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_practice_menu(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    audio_settings: Res<AudioSettings>,
+    display_settings: Res<DisplaySettings>,
+    difficulty: Res<Difficulty>,
+    palette_preset: Res<PalettePreset>,
+    practice_settings: Res<PracticeSettings>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    spawn_menu_page(
+        &mut commands,
+        &game_assets,
+        &color_palette,
+        &audio_settings,
+        &display_settings,
+        *difficulty,
+        *palette_preset,
+        &practice_settings,
+        &mut selection,
+        MenuScreen::Practice,
+        "PRACTICE",
+        Some("Practice runs never count for high scores or stats"),
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_high_scores_menu(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    audio_settings: Res<AudioSettings>,
+    display_settings: Res<DisplaySettings>,
+    difficulty: Res<Difficulty>,
+    palette_preset: Res<PalettePreset>,
+    practice_settings: Res<PracticeSettings>,
+    high_scores: Res<HighScores>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    spawn_menu_page(
+        &mut commands,
+        &game_assets,
+        &color_palette,
+        &audio_settings,
+        &display_settings,
+        *difficulty,
+        *palette_preset,
+        &practice_settings,
+        &mut selection,
+        MenuScreen::HighScores,
+        "HIGH SCORES",
+        Some(&high_score_table(&high_scores)),
+    );
+}
+
+/// One line per entry of the table: place, initials, score, wave reached and
+/// date. Entries from older saves leave out what they don't know.
+pub fn high_score_table(high_scores: &HighScores) -> String {
+    if high_scores.entries.is_empty() {
+        return "No scores yet".to_string();
+    }
+
+    high_scores
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let wave = if entry.wave > 0 { format!("Wave {}", entry.wave) } else { "-".to_string() };
+            let date = if entry.date.is_empty() { "-" } else { &entry.date };
+            format!("{:>2}. {:<3} {:>8}  {:<8} {}", index + 1, entry.name, entry.score, wave, date)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Spawns a title with `body` text, if any, and the entries of `screen` below
+/// it, starting with the first entry selected. The page is removed again when
+/// leaving `screen`.
+#[allow(clippy::too_many_arguments)]
+fn spawn_menu_page(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    color_palette: &ColorsPalette,
+    audio_settings: &AudioSettings,
+    display_settings: &DisplaySettings,
+    difficulty: Difficulty,
+    palette_preset: PalettePreset,
+    practice_settings: &PracticeSettings,
+    selection: &mut MenuSelection,
+    screen: MenuScreen,
+    title: &str,
+    body: Option<&str>,
+) {
+    selection.0 = 0;
+    let font = game_assets.font.clone();
+
+    commands
+        .spawn((
+            StateScoped(screen),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(16.0),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 64.0,
+                    ..Default::default()
+                },
+                Node {
+                    margin: UiRect::bottom(Val::Px(32.0)),
+                    ..Default::default()
+                },
+            ));
+
+            if let Some(body) = body {
+                parent.spawn((
+                    Text::new(body),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 24.0,
+                        ..Default::default()
+                    },
+                    Node {
+                        margin: UiRect::bottom(Val::Px(16.0)),
+                        ..Default::default()
+                    },
+                ));
+            }
+
+            for (index, action) in screen.entries().iter().copied().enumerate() {
+                let color = if index == selection.0 { color_palette.player_color } else { Color::WHITE };
+                parent.spawn((
+                    MenuEntry { index, action },
+                    Text::new(entry_label(
+                        action,
+                        audio_settings,
+                        display_settings,
+                        difficulty,
+                        palette_preset,
+                        practice_settings,
+                    )),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 32.0,
+                        ..Default::default()
+                    },
+                    TextColor(color),
+                ));
+            }
+        });
+}
+
+/// Moves the selection up or down with the arrow keys or d-pad
+fn navigate_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    menu_screen: Res<State<MenuScreen>>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keyboard_input.just_pressed(key) || gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+    };
+
+    let len = menu_screen.get().entries().len();
+    if pressed(KeyCode::ArrowUp, GamepadButton::DPadUp) {
+        selection.0 = step_selection(selection.0, len, -1);
+    }
+    if pressed(KeyCode::ArrowDown, GamepadButton::DPadDown) {
+        selection.0 = step_selection(selection.0, len, 1);
+    }
+}
+
+/// Runs the selected entry when Enter or the gamepad's south button is
+/// pressed. Alt+Enter is left to the fullscreen toggle.
+#[allow(clippy::too_many_arguments)]
+fn confirm_menu_entry(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    menu_screen: Res<State<MenuScreen>>,
+    selection: Res<MenuSelection>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut display_settings: ResMut<DisplaySettings>,
+    mut difficulty: ResMut<Difficulty>,
+    mut palette_preset: ResMut<PalettePreset>,
+    mut practice_settings: ResMut<PracticeSettings>,
+    mut player_count: ResMut<PlayerCount>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let alt_held = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let confirmed = (keyboard_input.just_pressed(KeyCode::Enter) && !alt_held)
+        || gamepads.iter().any(|gamepad| gamepad.just_pressed(GamepadButton::South));
+    if !confirmed {
+        return;
+    }
+
+    let Some(action) = menu_screen.get().entries().get(selection.0) else {
+        return;
+    };
+
+    match action {
+        MenuAction::Start => {
+            *player_count = PlayerCount(1);
+            next_game_state.set(GameState::Playing);
+        }
+        MenuAction::StartTwoPlayers => {
+            *player_count = PlayerCount(2);
+            next_game_state.set(GameState::Playing);
+        }
+        MenuAction::StartCoop => {
+            *player_count = PlayerCount(1);
+            commands.insert_resource(CoopRun);
+            next_game_state.set(GameState::Playing);
+        }
+        MenuAction::Practice => next_menu_screen.set(MenuScreen::Practice),
+        MenuAction::StartPractice => {
+            *player_count = PlayerCount(1);
+            commands.insert_resource(PracticeRun(*practice_settings));
+            next_game_state.set(GameState::Playing);
+        }
+        MenuAction::PracticeWave => practice_settings.step_wave(1),
+        MenuAction::PracticeSpeed => practice_settings.step_speed(1),
+        MenuAction::PracticeLives => practice_settings.infinite_lives = !practice_settings.infinite_lives,
+        MenuAction::PracticeEnemyFire => practice_settings.enemy_fire = !practice_settings.enemy_fire,
+        MenuAction::HighScores => next_menu_screen.set(MenuScreen::HighScores),
+        MenuAction::Options => next_menu_screen.set(MenuScreen::Options),
+        MenuAction::Quit => {
+            app_exit_events.send(AppExit::Success);
+        }
+        MenuAction::Volume => audio_settings.muted = !audio_settings.muted,
+        MenuAction::Fullscreen => {
+            if let Ok(mut window) = window_query.get_single_mut() {
+                display_settings.toggle_fullscreen(&mut window);
+            }
+        }
+        MenuAction::Difficulty => *difficulty = difficulty.step(1),
+        MenuAction::Colors => *palette_preset = palette_preset.step(1),
+        MenuAction::Back => next_menu_screen.set(MenuScreen::Main),
+    }
+}
+
+/// Changes the selected option with left and right: the volume, the difficulty,
+/// the colors and the practice wave and speed step up or down, the on/off
+/// settings flip either way
+#[allow(clippy::too_many_arguments)]
+fn adjust_option(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    menu_screen: Res<State<MenuScreen>>,
+    selection: Res<MenuSelection>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut display_settings: ResMut<DisplaySettings>,
+    mut difficulty: ResMut<Difficulty>,
+    mut palette_preset: ResMut<PalettePreset>,
+    mut practice_settings: ResMut<PracticeSettings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keyboard_input.just_pressed(key) || gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+    };
+
+    let step = match (
+        pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft),
+        pressed(KeyCode::ArrowRight, GamepadButton::DPadRight),
+    ) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => return,
+    };
+
+    match menu_screen.get().entries().get(selection.0) {
+        Some(MenuAction::Volume) => audio_settings.adjust_master(step * VOLUME_STEP),
+        Some(MenuAction::Fullscreen) => {
+            if let Ok(mut window) = window_query.get_single_mut() {
+                display_settings.toggle_fullscreen(&mut window);
+            }
+        }
+        Some(MenuAction::Difficulty) => *difficulty = difficulty.step(step as isize),
+        Some(MenuAction::Colors) => *palette_preset = palette_preset.step(step as isize),
+        Some(MenuAction::PracticeWave) => practice_settings.step_wave(step as isize),
+        Some(MenuAction::PracticeSpeed) => practice_settings.step_speed(step as isize),
+        Some(MenuAction::PracticeLives) => practice_settings.infinite_lives = !practice_settings.infinite_lives,
+        Some(MenuAction::PracticeEnemyFire) => practice_settings.enemy_fire = !practice_settings.enemy_fire,
+        _ => {}
+    }
+}
+
+/// Goes back to the main page from the options or the high scores with
+/// Escape or the gamepad's east button
+fn leave_page(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads.iter().any(|gamepad| gamepad.just_pressed(GamepadButton::East))
+    {
+        next_menu_screen.set(MenuScreen::Main);
+    }
+}
+
+/// Draws the selected entry in the player color and the others in white
+fn highlight_selection(
+    selection: Res<MenuSelection>,
+    color_palette: Res<ColorsPalette>,
+    mut entry_query: Query<(&MenuEntry, &mut TextColor)>,
+) {
+    for (entry, mut text_color) in entry_query.iter_mut() {
+        let color = if entry.index == selection.0 { color_palette.player_color } else { Color::WHITE };
+        if text_color.0 != color {
+            text_color.0 = color;
+        }
+    }
+}
+
+/// Keeps the option labels in sync with the settings they show
+fn refresh_option_labels(
+    audio_settings: Res<AudioSettings>,
+    display_settings: Res<DisplaySettings>,
+    difficulty: Res<Difficulty>,
+    palette_preset: Res<PalettePreset>,
+    practice_settings: Res<PracticeSettings>,
+    mut entry_query: Query<(&MenuEntry, &mut Text)>,
+) {
+    if !audio_settings.is_changed()
+        && !display_settings.is_changed()
+        && !difficulty.is_changed()
+        && !palette_preset.is_changed()
+        && !practice_settings.is_changed()
+    {
+        return;
+    }
+
+    for (entry, mut text) in entry_query.iter_mut() {
+        let label = entry_label(
+            entry.action,
+            &audio_settings,
+            &display_settings,
+            *difficulty,
+            *palette_preset,
+            &practice_settings,
+        );
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_wraps_at_both_ends() {
+        assert_eq!(step_selection(0, 3, -1), 2);
+        assert_eq!(step_selection(2, 3, 1), 0);
+        assert_eq!(step_selection(1, 3, 1), 2);
+    }
+
+    #[test]
+    fn high_score_table_lists_every_entry() {
+        use crate::highscore::HighScoreEntry;
+
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_score_table(&high_scores), "No scores yet");
+
+        for (name, score, wave, date) in [("ABC", 12_000, 7, "2026-10-16"), ("---", 900, 0, "")] {
+            high_scores.insert(HighScoreEntry {
+                name: name.to_string(),
+                score,
+                wave,
+                date: date.to_string(),
+                difficulty: Difficulty::Normal,
+            });
+        }
+        assert_eq!(
+            high_score_table(&high_scores),
+            " 1. ABC    12000  Wave 7   2026-10-16\n 2. ---      900  -        -"
+        );
+    }
+
+    #[test]
+    fn option_labels_show_the_current_settings() {
+        let mut audio_settings = AudioSettings {
+            master: 0.8,
+            ..Default::default()
+        };
+        let display_settings = DisplaySettings {
+            fullscreen: true,
+            ..Default::default()
+        };
+
+        let difficulty = Difficulty::Hard;
+        let preset = PalettePreset::HighContrast;
+        let mut practice = PracticeSettings::default();
+
+        assert_eq!(
+            entry_label(MenuAction::Volume, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Volume: 80% >"
+        );
+        assert_eq!(
+            entry_label(MenuAction::Fullscreen, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Fullscreen: On >"
+        );
+        assert_eq!(
+            entry_label(MenuAction::Difficulty, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Difficulty: Hard >"
+        );
+        assert_eq!(
+            entry_label(MenuAction::Colors, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Colors: High Contrast >"
+        );
+
+        audio_settings.muted = true;
+        assert_eq!(
+            entry_label(MenuAction::Volume, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Volume: Muted >"
+        );
+
+        practice.step_wave(4);
+        practice.step_speed(-1);
+        practice.enemy_fire = false;
+        assert_eq!(
+            entry_label(MenuAction::PracticeWave, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Wave: 5 >"
+        );
+        assert_eq!(
+            entry_label(MenuAction::PracticeSpeed, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Speed: 0.75x >"
+        );
+        assert_eq!(
+            entry_label(MenuAction::PracticeLives, &audio_settings, &display_settings, difficulty, preset, &practice),
+            "< Lives: Normal >"
+        );
+        assert_eq!(
+            entry_label(
+                MenuAction::PracticeEnemyFire,
+                &audio_settings,
+                &display_settings,
+                difficulty,
+                preset,
+                &practice
+            ),
+            "< Enemy Fire: Off >"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use bevy::prelude::*;
+use crate::{
+    bullet::apply_velocity,
+    explosion::Lifetime,
+    game_assets::GameAssets,
+    game_state::{GameState, PauseState},
+    layer,
+    lives::DeathSequence,
+    run_intro::RunIntro,
+    player_input::ShipKeys,
+    sound::{Sfx, SfxEvent},
+    wave::Wave,
+    Bullet, BulletOwner, ColorsPalette, Damage, DespawnOutOfBounds, Fly, InputSet, MovementSet, Player,
+    Velocity,
+};
+
+/// First wave on which the ship carries missiles
+pub const MISSILE_UNLOCK_WAVE: u32 = 4;
+
+/// Seconds between two missiles
+const MISSILE_COOLDOWN: f32 = 3.0;
+
+/// Missile speed in units per second, well below the bullet speed
+const MISSILE_SPEED: f32 = 180.0;
+
+/// Radians per second a missile can turn toward its target
+const MISSILE_TURN_RATE: f32 = 2.5;
+
+/// Seconds a missile flies before blowing up on its own
+const MISSILE_LIFETIME: f32 = 4.0;
+
+/// Damage of a missile hit, twice a bullet's
+const MISSILE_DAMAGE: u32 = 2;
+
+/// Size of a missile, long and thin along its heading
+const MISSILE_SCALE: Vec3 = Vec3::new(6.0, 20.0, 1.0);
+
+/// Slow homing missiles, an alternate fire unlocked on later waves
+pub struct MissilePlugin;
+
+impl Plugin for MissilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MissileCooldown>()
+            .add_systems(OnEnter(GameState::Playing), reset_missile_cooldown)
+            .add_systems(
+                Update,
+                fire_missile
+                    .in_set(InputSet)
+                    .run_if(in_state(PauseState::Running).and(not(resource_exists::<DeathSequence>)))
+                    .run_if(not(resource_exists::<RunIntro>)),
+            )
+            .add_systems(FixedUpdate, steer_missiles.before(apply_velocity).in_set(MovementSet));
+    }
+}
+
+/// Steers a projectile toward the nearest fly. Its velocity keeps `speed` and
+/// turns by at most `turn_rate` radians per second.
+#[derive(Component)]
+pub struct Homing {
+    pub turn_rate: f32,
+    pub speed: f32,
+}
+
+/// Fly a homing missile is chasing, picked again whenever it is gone
+#[derive(Component, Default)]
+pub struct MissileTarget(pub Option<Entity>);
+
+/// Minimum time between two missiles
+#[derive(Resource)]
+pub struct MissileCooldown(pub Timer);
+
+impl Default for MissileCooldown {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(MISSILE_COOLDOWN, TimerMode::Once);
+        timer.tick(timer.duration()); // Ready to fire right away
+        MissileCooldown(timer)
+    }
+}
+
+/// Makes the first missile of a run available immediately
+fn reset_missile_cooldown(mut commands: Commands) {
+    commands.insert_resource(MissileCooldown::default());
+}
+
+/// Launches a missile on a fresh press of a missile key, once the wave has
+/// unlocked them and the cooldown is over. Missiles don't count toward the
+/// player bullet cap.
+#[allow(clippy::too_many_arguments)]
+fn fire_missile(
+    mut commands: Commands,
+    ship_keys: ShipKeys,
+    mut cooldown: ResMut<MissileCooldown>,
+    wave: Res<Wave>,
+    player_query: Query<&Transform, With<Player>>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    mut sfx_events: EventWriter<SfxEvent>,
+    time: Res<Time>,
+) {
+    cooldown.0.tick(time.delta());
+
+    if wave.0 < MISSILE_UNLOCK_WAVE
+        || !cooldown.0.finished()
+        || !ship_keys.missile()
+    {
+        return;
+    }
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return; // No ship while waiting to respawn
+    };
+
+    cooldown.0.reset();
+    debug!(position = ?player_transform.translation, "Player fires a missile");
+    spawn_missile(
+        &mut commands,
+        player_transform.translation + Vec3::new(0.0, 50.0, 0.0),
+        &color_palette,
+        &game_assets,
+    );
+    sfx_events.send(SfxEvent(Sfx::Shoot));
+}
+
+/// Spawns a missile at `position` heading straight up. It is a player bullet,
+/// so hit checks and the out-of-bounds despawner treat it like any other shot.
+fn spawn_missile(
+    commands: &mut Commands,
+    position: Vec3,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+) {
+    commands.spawn((
+        Bullet { owner: BulletOwner::Player },
+        Homing {
+            turn_rate: MISSILE_TURN_RATE,
+            speed: MISSILE_SPEED,
+        },
+        MissileTarget::default(),
+        Damage(MISSILE_DAMAGE),
+        Lifetime(Timer::from_seconds(MISSILE_LIFETIME, TimerMode::Once)),
+        Velocity(Vec2::new(0.0, MISSILE_SPEED)),
+        DespawnOutOfBounds,
+        StateScoped(GameState::Playing),
+        GameAssets::sprite(&game_assets.bullet, color_palette.bullet_color(BulletOwner::Player)),
+        Transform {
+            translation: position.with_z(layer::BULLETS),
+            scale: MISSILE_SCALE,
+            ..Default::default()
+        },
+    ));
+}
+
+/// Turns every homing projectile toward its target, choosing the nearest fly
+/// whenever it has none or its target was destroyed mid-flight. Without any fly
+/// left it keeps flying straight. The sprite is rotated to face its heading.
+pub fn steer_missiles(
+    mut missile_query: Query<(&mut Transform, &mut Velocity, &mut MissileTarget, &Homing), Without<Fly>>,
+    fly_query: Query<(Entity, &Transform), With<Fly>>,
+    time: Res<Time>,
+) {
+    for (mut transform, mut velocity, mut target, homing) in missile_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let distance = |fly_transform: &Transform| {
+            fly_transform.translation.truncate().distance_squared(position)
+        };
+
+        let target_transform = match target.0.and_then(|fly| fly_query.get(fly).ok()) {
+            Some((_, fly_transform)) => Some(fly_transform),
+            None => {
+                let nearest = fly_query.iter().min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)));
+                target.0 = nearest.map(|(fly, _)| fly);
+                nearest.map(|(_, fly_transform)| fly_transform)
+            }
+        };
+        let target_position = target_transform.map(|fly_transform| fly_transform.translation.truncate());
+
+        let heading = velocity.0.to_angle();
+        let new_heading = match target_position {
+            Some(target_position) if target_position != position => {
+                let wanted = (target_position - position).to_angle();
+                let max_turn = homing.turn_rate * time.delta_secs();
+                heading + turn_toward(heading, wanted).clamp(-max_turn, max_turn)
+            }
+            _ => heading,
+        };
+
+        velocity.0 = Vec2::from_angle(new_heading) * homing.speed;
+        // The sprite is long along its y axis, which points up at angle zero
+        transform.rotation = Quat::from_rotation_z(new_heading - FRAC_PI_2);
+    }
+}
+
+/// Signed angle to turn from `heading` to `wanted` the short way round, in `[-PI, PI]`
+fn turn_toward(heading: f32, wanted: f32) -> f32 {
+    let turn = (wanted - heading).rem_euclid(TAU);
+    if turn > PI {
+        turn - TAU
+    } else {
+        turn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    fn steering_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .add_systems(Update, steer_missiles);
+        app
+    }
+
+    fn spawn_test_missile(app: &mut App) -> Entity {
+        app.world_mut()
+            .spawn((
+                Homing {
+                    turn_rate: MISSILE_TURN_RATE,
+                    speed: MISSILE_SPEED,
+                },
+                MissileTarget::default(),
+                Velocity(Vec2::new(0.0, MISSILE_SPEED)),
+                Transform::default(),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn missile_turns_toward_the_nearest_fly_at_a_limited_rate() {
+        let mut app = steering_app();
+        let near = app.world_mut().spawn((Fly, Transform::from_xyz(200.0, 0.0, 0.0))).id();
+        app.world_mut().spawn((Fly, Transform::from_xyz(-400.0, 0.0, 0.0)));
+        let missile = spawn_test_missile(&mut app);
+
+        app.update();
+        app.update();
+
+        let velocity = app.world().get::<Velocity>(missile).unwrap().0;
+        assert_eq!(app.world().get::<MissileTarget>(missile).unwrap().0, Some(near));
+        assert!(velocity.x > 0.0, "turned right toward the nearer fly");
+        assert!((velocity.length() - MISSILE_SPEED).abs() < 1e-3);
+        // One 100 ms step at 2.5 rad/s turns at most 0.25 rad off straight up
+        assert!(velocity.to_angle() >= FRAC_PI_2 - MISSILE_TURN_RATE * 0.1 - 1e-4);
+    }
+
+    #[test]
+    fn missile_picks_a_new_target_when_its_fly_dies() {
+        let mut app = steering_app();
+        let first = app.world_mut().spawn((Fly, Transform::from_xyz(100.0, 100.0, 0.0))).id();
+        let second = app.world_mut().spawn((Fly, Transform::from_xyz(-300.0, 300.0, 0.0))).id();
+        let missile = spawn_test_missile(&mut app);
+
+        app.update();
+        assert_eq!(app.world().get::<MissileTarget>(missile).unwrap().0, Some(first));
+
+        app.world_mut().despawn(first);
+        app.update();
+        assert_eq!(app.world().get::<MissileTarget>(missile).unwrap().0, Some(second));
+    }
+
+    #[test]
+    fn turns_go_the_short_way_round() {
+        assert!((turn_toward(0.1, -0.1) + 0.2).abs() < 1e-6);
+        assert!(turn_toward(3.0, -3.0) > 0.0);
+    }
+}
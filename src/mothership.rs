@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+use rand::Rng;
+use crate::{
+    bullet::BulletPool,
+    config::GameConfig,
+    difficulty::DifficultyConfig,
+    fly_logic::{spawn_enemy_bullet, spawn_fly, EnemyKind, Health},
+    game_assets::GameAssets,
+    game_rng::GameRng,
+    layer,
+    lives::DeathSequence,
+    practice::enemy_fire_on,
+    run_intro::RunIntro,
+    ColorsPalette, EntityIdCounter, MovementSet, PlayField, PlayerPosition,
+};
+
+/// Size of the mothership sprite in units
+pub const MOTHERSHIP_SCALE: f32 = 150.0;
+
+/// Distance from the top of the play field down to the mothership's patrol line
+const PATROL_DROP: f32 = 120.0;
+
+/// Speed of the horizontal patrol, in units per second
+const PATROL_SPEED: f32 = 80.0;
+
+/// Seconds between two lunges, picked at random in this range
+const LUNGE_INTERVAL: std::ops::Range<f32> = 3.0..6.0;
+
+/// Seconds a lunge takes, down and back up
+const LUNGE_DURATION: f32 = 1.5;
+
+/// How far below its patrol line a lunge takes the mothership
+const LUNGE_DEPTH: f32 = 250.0;
+
+/// Seconds between two spreads at full strength, halved below half health
+const SPREAD_INTERVAL: f32 = 1.6;
+
+/// Angle in radians between the bullets of a spread
+const SPREAD_ANGLE: f32 = 0.3;
+
+/// Size of the health bar at full health, in units
+const HEALTH_BAR_SIZE: Vec2 = Vec2::new(120.0, 8.0);
+
+/// Height of the health bar above the mothership's center
+const HEALTH_BAR_OFFSET: f32 = 95.0;
+
+/// The giant enemy of every 5th wave: patrols the top of the screen, lunges
+/// down now and then and fires spreads of 3 bullets, twice as often once it
+/// is down to half health
+pub struct MothershipPlugin;
+
+impl Plugin for MothershipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                move_mothership.in_set(MovementSet),
+                // Holds its fire while the player ship is in pieces or the run intro plays, and
+                // in a practice run without enemy fire
+                fire_spreads
+                    .run_if(enemy_fire_on)
+                    .run_if(not(resource_exists::<DeathSequence>))
+                    .run_if(not(resource_exists::<RunIntro>)),
+            ),
+        )
+        .add_systems(Update, update_health_bar);
+    }
+}
+
+/// Marks the mothership and drives its patrol
+#[derive(Component)]
+pub struct Mothership {
+    pub direction: f32,       // 1 while patrolling right, -1 left
+    pub patrol_y: f32,        // Height of the patrol line
+    pub lunge_timer: Timer,   // Counts down to the next lunge
+    pub lunge: Option<Timer>, // Runs while lunging
+}
+
+/// Counts down to the mothership's next spread
+#[derive(Component)]
+pub struct SpreadGun(pub Timer);
+
+/// The colored part of the mothership's health bar, a child of the mothership
+#[derive(Component)]
+struct HealthBar;
+
+/// Spawns the mothership at the top center of the play field, with its
+/// health bar above it
+pub fn spawn_mothership(
+    commands: &mut Commands,
+    entity_ids: &mut EntityIdCounter,
+    rng: &mut GameRng,
+    play_field: &PlayField,
+    color_palette: &ColorsPalette,
+    game_assets: &GameAssets,
+    config: &GameConfig,
+) -> Entity {
+    let patrol_y = play_field.half_size().y - PATROL_DROP;
+    let position = Vec3::new(0.0, patrol_y, 0.0);
+    let kind = EnemyKind::Mothership;
+
+    let mothership = spawn_fly(commands, entity_ids, position, kind, color_palette, game_assets, config);
+    commands
+        .entity(mothership)
+        .insert((
+            Mothership {
+                direction: if rng.gen_bool(0.5) { 1.0 } else { -1.0 },
+                patrol_y,
+                lunge_timer: Timer::from_seconds(rng.gen_range(LUNGE_INTERVAL), TimerMode::Once),
+                lunge: None,
+            },
+            SpreadGun(Timer::from_seconds(SPREAD_INTERVAL, TimerMode::Repeating)),
+            Transform::from_translation(position.with_z(layer::ENEMIES)).with_scale(Vec3::splat(MOTHERSHIP_SCALE)),
+        ))
+        .with_children(|parent| {
+            // Children are scaled with the mothership, so sizes are relative to it
+            let bar_scale = (HEALTH_BAR_SIZE / MOTHERSHIP_SCALE).extend(1.0);
+            let bar_position = Vec3::new(0.0, HEALTH_BAR_OFFSET / MOTHERSHIP_SCALE, 0.1);
+            parent.spawn((
+                Sprite::from_color(kind.color(color_palette).with_alpha(0.3), Vec2::ONE),
+                Transform::from_translation(bar_position).with_scale(bar_scale),
+            ));
+            parent.spawn((
+                HealthBar,
+                Sprite::from_color(kind.color(color_palette), Vec2::ONE),
+                Transform::from_translation(bar_position + Vec3::Z * 0.1).with_scale(bar_scale),
+            ));
+        });
+
+    info!("Mothership incoming");
+    mothership
+}
+
+/// Patrols the mothership from wall to wall and sends it lunging down every
+/// few seconds. It holds its column while lunging.
+fn move_mothership(
+    mut mothership_query: Query<(&mut Transform, &mut Mothership)>,
+    play_field: Res<PlayField>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    let edge = play_field.half_size().x - MOTHERSHIP_SCALE / 2.0;
+
+    for (mut transform, mut mothership) in mothership_query.iter_mut() {
+        if let Some(lunge) = mothership.lunge.as_mut() {
+            lunge.tick(time.delta());
+            let depth = LUNGE_DEPTH * (std::f32::consts::PI * lunge.fraction()).sin();
+            transform.translation.y = mothership.patrol_y - depth;
+
+            if lunge.finished() {
+                transform.translation.y = mothership.patrol_y;
+                mothership.lunge = None;
+                let interval = game_rng.gen_range(LUNGE_INTERVAL);
+                mothership.lunge_timer = Timer::from_seconds(interval, TimerMode::Once);
+            }
+            continue;
+        }
+
+        transform.translation.x += mothership.direction * PATROL_SPEED * time.delta_secs();
+        if transform.translation.x.abs() >= edge {
+            transform.translation.x = transform.translation.x.clamp(-edge, edge);
+            mothership.direction = -transform.translation.x.signum();
+        }
+
+        if mothership.lunge_timer.tick(time.delta()).finished() {
+            mothership.lunge = Some(Timer::from_seconds(LUNGE_DURATION, TimerMode::Once));
+        }
+    }
+}
+
+/// Whether the mothership is down to half health, firing twice as often
+pub fn enraged(health: &Health) -> bool {
+    health.0 * 2 <= EnemyKind::Mothership.max_health()
+}
+
+/// Fires a spread of 3 bullets at the ship, the middle one aimed straight at it
+#[allow(clippy::too_many_arguments)]
+fn fire_spreads(
+    mut commands: Commands,
+    mut gun_query: Query<(&Transform, &Health, &mut SpreadGun)>,
+    player_position: Res<PlayerPosition>,
+    difficulty: Res<DifficultyConfig>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    mut bullet_pool: ResMut<BulletPool>,
+    time: Res<Time>,
+) {
+    for (transform, health, mut gun) in gun_query.iter_mut() {
+        let interval = if enraged(health) { SPREAD_INTERVAL / 2.0 } else { SPREAD_INTERVAL };
+        if gun.0.duration().as_secs_f32() != interval {
+            gun.0.set_duration(std::time::Duration::from_secs_f32(interval));
+        }
+
+        if !gun.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        // From the bottom edge instead of deep inside the sprite
+        let origin = transform.translation - Vec3::new(0.0, transform.scale.y / 2.0 - 30.0, 0.0);
+        let aim = (player_position.0 - origin).truncate().normalize_or(Vec2::NEG_Y);
+        for angle in [-SPREAD_ANGLE, 0.0, SPREAD_ANGLE] {
+            let target = origin + (Vec2::from_angle(angle).rotate(aim) * 1000.0).extend(0.0);
+            spawn_enemy_bullet(
+                &mut commands,
+                &mut bullet_pool,
+                origin,
+                target,
+                difficulty.enemy_bullet_speed,
+                &color_palette,
+                &game_assets,
+            );
+        }
+    }
+}
+
+/// Length and offset of the health bar's colored part for `fraction` of the
+/// health left, relative to the full bar. It shrinks toward its left end.
+fn health_bar_fill(fraction: f32) -> (f32, f32) {
+    let fraction = fraction.clamp(0.0, 1.0);
+    (fraction, -(1.0 - fraction) / 2.0)
+}
+
+/// Shrinks the health bar as the mothership takes hits, in the color of the
+/// stage it is in
+#[allow(clippy::type_complexity)]
+fn update_health_bar(
+    mothership_query: Query<(&Health, &Children), (With<Mothership>, Changed<Health>)>,
+    mut bar_query: Query<(&mut Transform, &mut Sprite), With<HealthBar>>,
+    color_palette: Res<ColorsPalette>,
+) {
+    let kind = EnemyKind::Mothership;
+    for (health, children) in mothership_query.iter() {
+        let (length, offset) = health_bar_fill(health.0 as f32 / kind.max_health() as f32);
+        let full_length = HEALTH_BAR_SIZE.x / MOTHERSHIP_SCALE;
+
+        let mut bars = bar_query.iter_many_mut(children);
+        while let Some((mut transform, mut sprite)) = bars.fetch_next() {
+            transform.scale.x = full_length * length;
+            transform.translation.x = full_length * offset;
+            sprite.color = kind.color_at(health.0, &color_palette);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bullet, BulletOwner};
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn health_bar_shrinks_toward_its_left_end() {
+        assert_eq!(health_bar_fill(1.0), (1.0, 0.0));
+        assert_eq!(health_bar_fill(0.5), (0.5, -0.25));
+        assert_eq!(health_bar_fill(0.0), (0.0, -0.5));
+    }
+
+    #[test]
+    fn tint_shifts_at_half_health() {
+        let palette = ColorsPalette::default();
+        let kind = EnemyKind::Mothership;
+        assert_eq!(kind.color_at(29, &palette), palette.mothership_color);
+        assert_eq!(kind.color_at(16, &palette), palette.mothership_color);
+        assert_eq!(kind.color_at(15, &palette), palette.mothership_enraged_color);
+        assert!(!enraged(&Health(16)));
+        assert!(enraged(&Health(15)));
+    }
+
+    #[test]
+    fn spreads_come_twice_as_often_at_half_health() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(400)))
+            .insert_resource(PlayerPosition(Vec3::new(0.0, -250.0, 0.0)))
+            .insert_resource(DifficultyConfig::normal())
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<BulletPool>()
+            .add_systems(Update, fire_spreads);
+
+        let spread_gun = || SpreadGun(Timer::from_seconds(SPREAD_INTERVAL, TimerMode::Repeating));
+        let transform = Transform::from_xyz(0.0, 280.0, 0.0).with_scale(Vec3::splat(MOTHERSHIP_SCALE));
+        app.world_mut().spawn((transform, Health(30), spread_gun()));
+        app.world_mut().spawn((transform, Health(10), spread_gun()));
+
+        for _ in 0..5 {
+            app.update(); // 1.6 seconds, the first update has no elapsed time
+        }
+
+        // 2 spreads from the enraged one, 1 from the other
+        let bullets: Vec<Vec2> = app
+            .world_mut()
+            .query::<(&Bullet, &crate::Velocity)>()
+            .iter(app.world())
+            .filter(|(bullet, _)| bullet.owner == BulletOwner::Enemy)
+            .map(|(_, velocity)| velocity.0)
+            .collect();
+        assert_eq!(bullets.len(), 9);
+        assert!(bullets.iter().all(|velocity| velocity.y < 0.0));
+        assert!(bullets.iter().any(|velocity| velocity.x < 0.0));
+        assert!(bullets.iter().any(|velocity| velocity.x > 0.0));
+    }
+}
@@ -0,0 +1,53 @@
+use bevy::{audio::Volume, prelude::*};
+use crate::{audio_settings::AudioSettings, game_assets::GameAssets, game_state::GameState, run_intro::RunIntro};
+
+/// Looping background track: stops on game over and restarts with the next
+/// run, once the intro jingle of the run is over
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameState::Loading), start_music)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    start_music.run_if(not(resource_exists::<RunIntro>)),
+                    stop_music.run_if(resource_exists::<RunIntro>),
+                ),
+            )
+            .add_systems(
+                Update,
+                start_music.run_if(in_state(GameState::Playing).and(resource_removed::<RunIntro>)),
+            )
+            .add_systems(OnEnter(GameState::GameOver), stop_music);
+    }
+}
+
+/// Marks the entity playing the background track
+#[derive(Component)]
+pub struct BackgroundMusic;
+
+/// Starts the looping track unless it is already playing
+fn start_music(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    music_query: Query<(), With<BackgroundMusic>>,
+    audio_settings: Res<AudioSettings>,
+) {
+    if !music_query.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        BackgroundMusic,
+        AudioPlayer::new(game_assets.music.clone()),
+        PlaybackSettings::LOOP.with_volume(Volume::new(audio_settings.music_volume())),
+    ));
+}
+
+/// Silences the game over screen by removing the track
+fn stop_music(mut commands: Commands, music_query: Query<Entity, With<BackgroundMusic>>) {
+    for entity in music_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,312 @@
+use bevy::prelude::*;
+use crate::{
+    difficulty::Difficulty,
+    game_assets::GameAssets,
+    game_state::{start_on_enter, GameState},
+    highscore::{record_high_score, today, HighScoreEntry, HighScoreFile, HighScores},
+    keybindings::KeyBindings,
+    ColorsPalette,
+};
+
+/// Letters of the initials
+const INITIALS: usize = 3;
+
+/// Arcade style initials for every score that makes the high score table,
+/// asked for on the game over screen before the scores are shown. Left and
+/// right change the letter, Enter or fire confirms it, and letter keys type
+/// it straight away.
+pub struct NameEntryPlugin;
+
+impl Plugin for NameEntryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            spawn_name_entry.after(record_high_score).run_if(resource_exists::<NameEntry>),
+        )
+        .add_systems(
+            Update,
+            // After Enter was checked for a new game, so the Enter that finishes doesn't start one
+            (enter_initials.after(start_on_enter), show_initials.after(enter_initials))
+                .run_if(in_state(GameState::GameOver).and(resource_exists::<NameEntry>)),
+        );
+    }
+}
+
+/// A score of the run that makes the table, waiting for its initials
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub player: usize, // Index of the player, 1 for the co-op ship
+    pub score: u32,
+    pub wave: u32,
+    pub difficulty: Difficulty, // Of the run the score was made on
+}
+
+/// Present on the game over screen while initials are being entered
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct NameEntry {
+    pending: Vec<Candidate>, // Best first, the first one is being entered
+    letters: [u8; INITIALS],
+    cursor: usize, // Letter being picked
+}
+
+impl NameEntry {
+    pub fn new(pending: Vec<Candidate>) -> Self {
+        NameEntry {
+            pending,
+            letters: [b'A'; INITIALS],
+            cursor: 0,
+        }
+    }
+
+    /// Initials as entered so far
+    fn name(&self) -> String {
+        String::from_utf8_lossy(&self.letters).into_owned()
+    }
+
+    /// Moves the letter under the cursor `step` places along the alphabet,
+    /// wrapping from Z to A and back
+    fn step_letter(&mut self, step: i8) {
+        let letter = &mut self.letters[self.cursor];
+        *letter = b'A' + ((*letter - b'A') as i8 + step).rem_euclid(26) as u8;
+    }
+
+    /// Sets the letter under the cursor and moves on to the next. Returns the
+    /// finished candidate with its initials once the last letter is in.
+    fn confirm(&mut self, letter: u8) -> Option<(Candidate, String)> {
+        self.letters[self.cursor] = letter;
+        self.cursor += 1;
+        if self.cursor < INITIALS {
+            return None;
+        }
+
+        let name = self.name();
+        let candidate = self.pending.remove(0);
+        self.letters = [b'A'; INITIALS];
+        self.cursor = 0;
+        Some((candidate, name))
+    }
+}
+
+/// Marks the name entry screen
+#[derive(Component)]
+struct NameEntryScreen;
+
+/// Marks the text showing whose initials are entered and the letters so far
+#[derive(Component)]
+struct InitialsText;
+
+/// The letter typed with `key`, if it is a letter key
+fn typed_letter(key: KeyCode) -> Option<u8> {
+    const LETTER_KEYS: [KeyCode; 26] = [
+        KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+        KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+        KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+        KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+        KeyCode::KeyY, KeyCode::KeyZ,
+    ];
+    LETTER_KEYS
+        .iter()
+        .position(|letter_key| *letter_key == key)
+        .map(|index| b'A' + index as u8)
+}
+
+/// What the screen says while `name_entry` is being filled in. The letter
+/// being picked is in brackets.
+fn initials_text(name_entry: &NameEntry) -> String {
+    let Some(candidate) = name_entry.pending.first() else {
+        return String::new();
+    };
+
+    let letters = name_entry
+        .letters
+        .iter()
+        .enumerate()
+        .map(|(index, letter)| {
+            let letter = *letter as char;
+            if index == name_entry.cursor { format!("[{}]", letter) } else { format!(" {} ", letter) }
+        })
+        .collect::<String>();
+    format!(
+        "NEW HIGH SCORE\n\nPLAYER {}  {}\n\nENTER YOUR INITIALS\n\n{}",
+        candidate.player + 1,
+        candidate.score,
+        letters
+    )
+}
+
+fn spawn_name_entry(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    name_entry: Res<NameEntry>,
+) {
+    commands
+        .spawn((
+            NameEntryScreen,
+            StateScoped(GameState::GameOver),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                InitialsText,
+                Text::new(initials_text(&name_entry)),
+                TextFont {
+                    font: game_assets.font.clone(),
+                    font_size: 40.0,
+                    ..Default::default()
+                },
+                TextColor(color_palette.player_color),
+                TextLayout::new_with_justify(JustifyText::Center),
+            ));
+        });
+}
+
+/// Picks and confirms the letters. Once a score has all of its initials it
+/// goes into the table. After the last one the table is saved and the name
+/// entry screen makes way for the game over screen.
+#[allow(clippy::too_many_arguments)]
+fn enter_initials(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    key_bindings: Res<KeyBindings>,
+    mut name_entry: ResMut<NameEntry>,
+    mut high_scores: ResMut<HighScores>,
+    high_score_file: Res<HighScoreFile>,
+    screen_query: Query<Entity, With<NameEntryScreen>>,
+) {
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keyboard_input.just_pressed(key) || gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+    };
+
+    if pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft) {
+        name_entry.step_letter(-1);
+    }
+    if pressed(KeyCode::ArrowRight, GamepadButton::DPadRight) {
+        name_entry.step_letter(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) && name_entry.cursor > 0 {
+        name_entry.cursor -= 1;
+    }
+
+    // A letter key types that letter, even if it is also bound to fire
+    let typed = keyboard_input.get_just_pressed().find_map(|key| typed_letter(*key));
+    let alt_held = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let fired = key_bindings
+        .fire
+        .iter()
+        .any(|key| typed_letter(*key).is_none() && keyboard_input.just_pressed(*key));
+    let confirmed = (keyboard_input.just_pressed(KeyCode::Enter) && !alt_held)
+        || fired
+        || gamepads.iter().any(|gamepad| gamepad.just_pressed(GamepadButton::South));
+
+    let letter = match typed {
+        Some(letter) => letter,
+        None if confirmed => name_entry.letters[name_entry.cursor],
+        None => return,
+    };
+    let Some((candidate, name)) = name_entry.confirm(letter) else {
+        return;
+    };
+
+    let place = high_scores.insert(HighScoreEntry {
+        name,
+        score: candidate.score,
+        wave: candidate.wave,
+        date: today(),
+        difficulty: candidate.difficulty,
+    });
+    info!(score = candidate.score, place = ?place.map(|place| place + 1), "High score entered");
+
+    if !name_entry.pending.is_empty() {
+        return;
+    }
+
+    high_scores.save_to(&high_score_file);
+    commands.remove_resource::<NameEntry>();
+    for screen in screen_query.iter() {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
+/// Keeps the screen in step with the letters picked
+fn show_initials(name_entry: Res<NameEntry>, mut text_query: Query<&mut Text, With<InitialsText>>) {
+    if !name_entry.is_changed() {
+        return;
+    }
+
+    let content = initials_text(&name_entry);
+    for mut text in text_query.iter_mut() {
+        if text.0 != content {
+            text.0.clone_from(&content);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(player: usize, score: u32) -> Candidate {
+        Candidate {
+            player,
+            score,
+            wave: 5,
+            difficulty: Difficulty::Hard,
+        }
+    }
+
+    #[test]
+    fn letters_wrap_and_the_cursor_moves_on() {
+        let mut name_entry = NameEntry::new(vec![candidate(0, 900)]);
+        name_entry.step_letter(-1);
+        assert_eq!(name_entry.name(), "ZAA");
+        assert!(initials_text(&name_entry).ends_with("[Z] A  A "));
+
+        assert_eq!(name_entry.confirm(b'Z'), None);
+        name_entry.step_letter(27);
+        assert_eq!(name_entry.confirm(b'B'), None);
+        assert_eq!(name_entry.confirm(b'Q'), Some((candidate(0, 900), "ZBQ".to_string())));
+        assert!(name_entry.pending.is_empty());
+    }
+
+    #[test]
+    fn typed_initials_go_into_the_table() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<HighScores>()
+            .insert_resource(HighScoreFile(None))
+            .insert_resource(NameEntry::new(vec![candidate(0, 900), candidate(1, 400)]))
+            .add_systems(Update, enter_initials.run_if(resource_exists::<NameEntry>));
+
+        let press = |app: &mut App, key: KeyCode| {
+            let mut keyboard_input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keyboard_input.reset_all();
+            keyboard_input.press(key);
+            app.update();
+        };
+
+        for key in [KeyCode::KeyJ, KeyCode::KeyO, KeyCode::KeyE] {
+            press(&mut app, key);
+        }
+        // The second player keeps the A and confirms the rest with the arrows and Enter
+        for key in [KeyCode::Enter, KeyCode::ArrowRight, KeyCode::Enter, KeyCode::Space] {
+            press(&mut app, key);
+        }
+
+        assert!(!app.world().contains_resource::<NameEntry>());
+        let entries = &app.world().resource::<HighScores>().entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].name.as_str(), entries[0].score, entries[0].wave), ("JOE", 900, 5));
+        assert_eq!(entries[1].name, "ABA");
+        assert!(entries.iter().all(|entry| entry.difficulty == Difficulty::Hard));
+    }
+}
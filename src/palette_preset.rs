@@ -0,0 +1,308 @@
+use std::{fmt, str::FromStr};
+use bevy::prelude::*;
+use crate::{
+    config::{retinted, GameConfig},
+    game_state::GameState,
+    Bullet, ColorsPalette, Fly,
+};
+
+/// Units the high-contrast outline sticks out on every side of what it outlines
+const OUTLINE_WIDTH: f32 = 2.0;
+
+/// Color of the high-contrast outlines
+const OUTLINE_COLOR: Color = Color::WHITE;
+
+/// Color schemes for players who can't tell the classic colors apart, picked
+/// on the options page or in the game config. High contrast also outlines
+/// every enemy and bullet so they stand out from the starfield.
+pub struct PalettePresetPlugin;
+
+impl Plugin for PalettePresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                apply_palette_preset.run_if(resource_changed::<PalettePreset>),
+                (spawn_outlines, follow_outlines)
+                    .chain()
+                    .after(apply_palette_preset)
+                    .run_if(resource_equals(PalettePreset::HighContrast)),
+            ),
+        );
+    }
+}
+
+/// Which colors the game is drawn in. `Classic` is the palette of the game
+/// config, the others replace it with built-in ones.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PalettePreset {
+    #[default]
+    Classic,
+    Deuteranopia,
+    Protanopia,
+    HighContrast,
+}
+
+impl PalettePreset {
+    pub const ALL: [PalettePreset; 4] = [
+        PalettePreset::Classic,
+        PalettePreset::Deuteranopia,
+        PalettePreset::Protanopia,
+        PalettePreset::HighContrast,
+    ];
+
+    /// Colors of this preset, `configured` being the palette of the game config.
+    /// Flies and butterflies share a color in every preset, as they do in the
+    /// classic one, and no other colors are shared, so sprites can always be
+    /// re-tinted by their color alone.
+    pub fn palette(self, configured: &ColorsPalette) -> ColorsPalette {
+        match self {
+            PalettePreset::Classic => configured.clone(),
+            // Blues, oranges and yellows, which stay apart without telling red from green
+            PalettePreset::Deuteranopia => ColorsPalette {
+                player_color: Color::srgb(0.35, 0.7, 0.9),
+                fly_color: Color::srgb(0.9, 0.6, 0.0),
+                bee_color: Color::srgb(0.95, 0.9, 0.25),
+                butterfly_color: Color::srgb(0.9, 0.6, 0.0),
+                boss_color: Color::srgb(0.0, 0.45, 0.7),
+                boss_damaged_color: Color::srgb(0.8, 0.6, 0.7),
+                mothership_color: Color::srgb(0.8, 0.4, 0.0),
+                mothership_enraged_color: Color::srgb(1.0, 0.85, 0.7),
+                player_bullet_color: Color::srgb(0.0, 0.6, 0.5),
+                enemy_bullet_color: Color::srgb(1.0, 0.5, 0.2),
+                powerup_color: Color::srgb(0.6, 0.9, 1.0),
+                bomb_color: Color::srgb(1.0, 0.75, 0.3),
+                shield_color: Color::srgb(0.5, 0.8, 1.0),
+                coop_player_color: Color::srgb(0.85, 0.85, 0.95),
+                hit_flash_color: Color::WHITE,
+                background_color: Color::srgb(0.0, 0.0, 0.15),
+            },
+            // Like deuteranopia, but without the deep reds that look almost black
+            PalettePreset::Protanopia => ColorsPalette {
+                player_color: Color::srgb(0.2, 0.75, 1.0),
+                fly_color: Color::srgb(1.0, 0.85, 0.3),
+                bee_color: Color::srgb(1.0, 1.0, 0.5),
+                butterfly_color: Color::srgb(1.0, 0.85, 0.3),
+                boss_color: Color::srgb(0.3, 0.4, 1.0),
+                boss_damaged_color: Color::srgb(0.85, 0.85, 0.85),
+                mothership_color: Color::srgb(0.95, 0.7, 0.2),
+                mothership_enraged_color: Color::srgb(1.0, 0.95, 0.8),
+                player_bullet_color: Color::srgb(0.4, 1.0, 1.0),
+                enemy_bullet_color: Color::srgb(1.0, 0.9, 0.1),
+                powerup_color: Color::srgb(0.6, 0.6, 1.0),
+                bomb_color: Color::srgb(1.0, 0.8, 0.5),
+                shield_color: Color::srgb(0.6, 0.85, 1.0),
+                coop_player_color: Color::srgb(0.9, 0.9, 1.0),
+                hit_flash_color: Color::WHITE,
+                background_color: Color::srgb(0.0, 0.0, 0.12),
+            },
+            // Fully saturated colors on black
+            PalettePreset::HighContrast => ColorsPalette {
+                player_color: Color::srgb(0.0, 1.0, 1.0),
+                fly_color: Color::srgb(1.0, 0.0, 1.0),
+                bee_color: Color::srgb(1.0, 1.0, 0.0),
+                butterfly_color: Color::srgb(1.0, 0.0, 1.0),
+                boss_color: Color::srgb(0.0, 1.0, 0.0),
+                boss_damaged_color: Color::srgb(1.0, 0.5, 0.0),
+                mothership_color: Color::srgb(1.0, 0.0, 0.5),
+                mothership_enraged_color: Color::srgb(1.0, 0.25, 0.0),
+                player_bullet_color: Color::srgb(0.5, 1.0, 0.5),
+                enemy_bullet_color: Color::srgb(1.0, 0.3, 0.3),
+                powerup_color: Color::srgb(0.3, 0.6, 1.0),
+                bomb_color: Color::srgb(1.0, 0.6, 0.0),
+                shield_color: Color::srgb(0.5, 0.8, 1.0),
+                coop_player_color: Color::srgb(1.0, 0.6, 1.0),
+                hit_flash_color: Color::WHITE,
+                background_color: Color::BLACK,
+            },
+        }
+    }
+
+    /// The preset `step` places further along `ALL`, wrapping around at both ends
+    pub fn step(self, step: isize) -> PalettePreset {
+        let index = PalettePreset::ALL.iter().position(|preset| *preset == self).unwrap_or(0);
+        let len = PalettePreset::ALL.len() as isize;
+        PalettePreset::ALL[(index as isize + step).rem_euclid(len) as usize]
+    }
+}
+
+impl fmt::Display for PalettePreset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PalettePreset::Classic => "Classic",
+            PalettePreset::Deuteranopia => "Deuteranopia",
+            PalettePreset::Protanopia => "Protanopia",
+            PalettePreset::HighContrast => "High Contrast",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for PalettePreset {
+    type Err = ();
+
+    /// Reads a preset by name, ignoring case and spaces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.replace(' ', "");
+        PalettePreset::ALL
+            .into_iter()
+            .find(|preset| preset.to_string().replace(' ', "").eq_ignore_ascii_case(&name))
+            .ok_or(())
+    }
+}
+
+/// Outline drawn behind `target` while high contrast is on
+#[derive(Component)]
+struct Outline {
+    target: Entity,
+}
+
+/// Switches to the colors of the selected preset, re-tinting every sprite
+/// already drawn in a palette color. Outlines go away with high contrast.
+fn apply_palette_preset(
+    mut commands: Commands,
+    preset: Res<PalettePreset>,
+    config: Res<GameConfig>,
+    mut color_palette: ResMut<ColorsPalette>,
+    mut sprite_query: Query<&mut Sprite>,
+    outline_query: Query<Entity, With<Outline>>,
+) {
+    let palette = preset.palette(&config.palette);
+
+    for mut sprite in sprite_query.iter_mut() {
+        if let Some(color) = retinted(sprite.color, &color_palette, &palette) {
+            sprite.color = color;
+        }
+    }
+
+    if *preset != PalettePreset::HighContrast {
+        for outline in outline_query.iter() {
+            commands.entity(outline).despawn();
+        }
+    }
+
+    *color_palette = palette;
+    info!(preset = %*preset, "Palette set");
+}
+
+/// Puts an outline behind every enemy and bullet that doesn't have one yet
+#[allow(clippy::type_complexity)]
+fn spawn_outlines(
+    mut commands: Commands,
+    target_query: Query<(Entity, &Transform), Or<(With<Fly>, With<Bullet>)>>,
+    outline_query: Query<&Outline>,
+) {
+    let outlined: Vec<Entity> = outline_query.iter().map(|outline| outline.target).collect();
+
+    for (target, transform) in target_query.iter() {
+        if outlined.contains(&target) {
+            continue;
+        }
+
+        commands.spawn((
+            Outline { target },
+            StateScoped(GameState::Playing),
+            Sprite::from_color(OUTLINE_COLOR, Vec2::ONE),
+            outline_transform(transform),
+        ));
+    }
+}
+
+/// Keeps every outline right behind its target, and removes the ones whose
+/// target is gone, or parked back in the bullet pool
+#[allow(clippy::type_complexity)]
+fn follow_outlines(
+    mut commands: Commands,
+    mut outline_query: Query<(Entity, &Outline, &mut Transform)>,
+    target_query: Query<&Transform, (Or<(With<Fly>, With<Bullet>)>, Without<Outline>)>,
+) {
+    for (entity, outline, mut transform) in outline_query.iter_mut() {
+        match target_query.get(outline.target) {
+            Ok(target) => *transform = outline_transform(target),
+            Err(_) => commands.entity(entity).despawn(),
+        }
+    }
+}
+
+/// Where the outline of something at `target` goes: just behind it, sticking
+/// out `OUTLINE_WIDTH` on every side
+fn outline_transform(target: &Transform) -> Transform {
+    Transform {
+        translation: target.translation - Vec3::Z * 0.1,
+        rotation: target.rotation,
+        scale: target.scale + Vec3::new(OUTLINE_WIDTH * 2.0, OUTLINE_WIDTH * 2.0, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulletOwner;
+
+    #[test]
+    fn presets_never_share_colors_between_roles() {
+        for preset in PalettePreset::ALL {
+            let palette = preset.palette(&ColorsPalette::default());
+            assert_eq!(palette.fly_color, palette.butterfly_color, "{}", preset);
+
+            let mut colors = crate::config::palette_colors(&palette).to_vec();
+            colors.retain(|color| *color != palette.fly_color);
+            for (index, color) in colors.iter().enumerate() {
+                assert!(!colors[index + 1..].contains(color), "{} repeats {:?}", preset, color);
+            }
+        }
+    }
+
+    #[test]
+    fn presets_are_read_by_name_and_wrap_around() {
+        assert_eq!("highcontrast".parse(), Ok(PalettePreset::HighContrast));
+        assert_eq!("High Contrast".parse(), Ok(PalettePreset::HighContrast));
+        assert_eq!("Deuteranopia".parse(), Ok(PalettePreset::Deuteranopia));
+        assert_eq!("Tritanopia".parse::<PalettePreset>(), Err(()));
+        assert_eq!(PalettePreset::HighContrast.step(1), PalettePreset::Classic);
+        assert_eq!(PalettePreset::Classic.step(-1), PalettePreset::HighContrast);
+    }
+
+    #[test]
+    fn switching_presets_retints_sprites_and_outlines_come_and_go() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameConfig>()
+            .init_resource::<ColorsPalette>()
+            .init_resource::<PalettePreset>()
+            .add_plugins(PalettePresetPlugin);
+
+        let classic = ColorsPalette::default();
+        let fly = app
+            .world_mut()
+            .spawn((Fly, Sprite::from_color(classic.bee_color, Vec2::ONE), Transform::from_scale(Vec3::splat(50.0))))
+            .id();
+        let bullet_color = classic.bullet_color(BulletOwner::Player);
+        app.world_mut().spawn((
+            Bullet { owner: BulletOwner::Player },
+            Sprite::from_color(bullet_color, Vec2::ONE),
+            Transform::from_scale(Vec3::splat(10.0)),
+        ));
+        app.update();
+
+        let outlines = |app: &mut App| app.world_mut().query::<&Outline>().iter(app.world()).count();
+        assert_eq!(outlines(&mut app), 0);
+
+        *app.world_mut().resource_mut::<PalettePreset>() = PalettePreset::HighContrast;
+        app.update();
+
+        let high_contrast = PalettePreset::HighContrast.palette(&classic);
+        assert_eq!(app.world().get::<Sprite>(fly).unwrap().color, high_contrast.bee_color);
+        assert_eq!(app.world().resource::<ColorsPalette>().bee_color, high_contrast.bee_color);
+        assert_eq!(outlines(&mut app), 2);
+
+        app.world_mut().despawn(fly);
+        app.update();
+        app.update();
+        assert_eq!(outlines(&mut app), 1);
+
+        *app.world_mut().resource_mut::<PalettePreset>() = PalettePreset::Classic;
+        app.update();
+        assert_eq!(outlines(&mut app), 0);
+    }
+}
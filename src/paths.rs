@@ -0,0 +1,312 @@
+use std::{fs, path::Path};
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+use crate::{
+    formation::{follow_entry_path, EnteringFormation},
+    MovementSet, PlayField,
+};
+
+/// Flight paths read at startup, so entrances can be choreographed without recompiling
+const PATHS_PATH: &str = "assets/config/paths.ron";
+
+/// The paths shipped with the game, used when the file is missing or broken
+const BUILT_IN_PATHS: &str = include_str!("../assets/config/paths.ron");
+
+/// Entrance paths defined in data: flies spawn at the start of a path, fly
+/// along a smooth curve through its points, then either hop up to their
+/// formation slot or, without one, leave the field and are removed
+pub struct PathsPlugin;
+
+impl Plugin for PathsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PathLibrary::load(Path::new(PATHS_PATH))).add_systems(
+            FixedUpdate,
+            follow_paths.in_set(MovementSet).before(follow_entry_path),
+        );
+    }
+}
+
+/// Every flight path by id, plus which of them challenge stages fly. Normal
+/// waves name their entrances in the `WavePlan`.
+#[derive(Resource, Debug, Clone)]
+pub struct PathLibrary {
+    paths: HashMap<String, FlightPath>,
+    challenge_paths: Vec<String>,
+}
+
+/// A curve through `points`, in units of half the play field, flown in `duration` seconds
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FlightPath {
+    pub id: String,
+    pub points: Vec<(f32, f32)>,
+    pub duration: f32,
+}
+
+/// Layout of the paths file
+#[derive(Deserialize)]
+struct PathsFile {
+    paths: Vec<FlightPath>,
+    challenge_paths: Vec<String>,
+}
+
+impl Default for PathLibrary {
+    fn default() -> Self {
+        PathLibrary::from_ron(BUILT_IN_PATHS).expect("built-in paths are valid")
+    }
+}
+
+impl PathLibrary {
+    /// Reads the paths at `path`, keeping the built-in ones if the file is
+    /// missing or can't be used
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return PathLibrary::default();
+        };
+
+        match PathLibrary::from_ron(&contents) {
+            Ok(library) => library,
+            Err(error) => {
+                warn!("Ignoring flight paths {}: {}", path.display(), error);
+                PathLibrary::default()
+            }
+        }
+    }
+
+    /// Parses a paths file, checking every path can be flown and every id
+    /// the challenge stages use exists
+    pub fn from_ron(contents: &str) -> Result<Self, String> {
+        let file: PathsFile = ron::from_str(contents).map_err(|error| error.to_string())?;
+
+        let mut paths = HashMap::default();
+        for path in file.paths {
+            if path.points.len() < 2 {
+                return Err(format!("path `{}` needs at least 2 points", path.id));
+            }
+            if path.duration <= 0.0 {
+                return Err(format!("path `{}` needs a positive duration", path.id));
+            }
+            paths.insert(path.id.clone(), path);
+        }
+
+        if file.challenge_paths.is_empty() {
+            return Err("`challenge_paths` lists no paths".to_string());
+        }
+        if let Some(id) = file.challenge_paths.iter().find(|id| !paths.contains_key(*id)) {
+            return Err(format!("`challenge_paths` uses unknown path `{}`", id));
+        }
+
+        Ok(PathLibrary {
+            paths,
+            challenge_paths: file.challenge_paths,
+        })
+    }
+
+    /// The path called `id`, if there is one
+    pub fn get(&self, id: &str) -> Option<&FlightPath> {
+        self.paths.get(id)
+    }
+
+    /// Paths of a challenge stage, in the order they are flown
+    pub fn challenge_paths(&self) -> impl Iterator<Item = &FlightPath> {
+        self.challenge_paths.iter().map(|id| &self.paths[id])
+    }
+}
+
+impl FlightPath {
+    /// Where the path is `t` of the way along, 0.0 being its start and 1.0 its end
+    pub fn position(&self, t: f32, play_field: &PlayField) -> Vec2 {
+        let points: Vec<Vec2> = self.points.iter().map(|&(x, y)| Vec2::new(x, y)).collect();
+        catmull_rom(&points, t) * play_field.half_size()
+    }
+
+    /// Where a fly starts flying this path
+    pub fn start(&self, play_field: &PlayField) -> Vec3 {
+        self.position(0.0, play_field).extend(0.0)
+    }
+}
+
+/// Point `t` of the way along a Catmull-Rom spline passing through every one
+/// of `points`, each segment between two points taking the same share of `t`.
+/// The end points are repeated so the curve starts and ends on them.
+pub fn catmull_rom(points: &[Vec2], t: f32) -> Vec2 {
+    match points.len() {
+        0 => return Vec2::ZERO,
+        1 => return points[0],
+        _ => {}
+    }
+
+    let segments = points.len() - 1;
+    let scaled = t.clamp(0.0, 1.0) * segments as f32;
+    let index = (scaled.floor() as usize).min(segments - 1);
+    let local = scaled - index as f32;
+
+    let p0 = points[index.saturating_sub(1)];
+    let p1 = points[index];
+    let p2 = points[index + 1];
+    let p3 = points[(index + 2).min(segments)];
+
+    let local2 = local * local;
+    let local3 = local2 * local;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * local
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * local2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * local3)
+}
+
+/// A fly flying along the path `path_id` of the `PathLibrary`, `t` of the way
+/// (0.0 to 1.0) along
+#[derive(Component, Debug, Clone)]
+pub struct FollowPath {
+    pub path_id: String,
+    pub t: f32,
+    pub speed: f32, // 1.0 flies the path in its duration, 2.0 in half of it
+}
+
+impl FollowPath {
+    /// Starts `path` from its beginning
+    pub fn new(path: &FlightPath) -> Self {
+        FollowPath {
+            path_id: path.id.clone(),
+            t: 0.0,
+            speed: 1.0,
+        }
+    }
+
+    /// Flies the path `speed` times as fast
+    pub fn with_speed(self, speed: f32) -> Self {
+        FollowPath { speed, ..self }
+    }
+}
+
+/// Moves flies along their path. At its end a fly heading for the formation
+/// hops up to its slot, one without a slot has left the field and is removed.
+pub fn follow_paths(
+    mut commands: Commands,
+    mut fly_query: Query<(Entity, &mut Transform, &mut FollowPath, Option<&mut EnteringFormation>)>,
+    library: Res<PathLibrary>,
+    play_field: Res<PlayField>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut follow, entering) in fly_query.iter_mut() {
+        match library.get(&follow.path_id) {
+            Some(path) => {
+                follow.t = (follow.t + time.delta_secs() * follow.speed / path.duration).min(1.0);
+                transform.translation = path.position(follow.t, &play_field).extend(transform.translation.z);
+            }
+            None => {
+                warn!(path = %follow.path_id, "Unknown flight path, skipping it");
+                follow.t = 1.0;
+            }
+        }
+
+        if follow.t < 1.0 {
+            continue;
+        }
+
+        commands.entity(entity).remove::<FollowPath>();
+        match entering {
+            Some(mut entering) => *entering = EnteringFormation::from_path_end(transform.translation),
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn spline_passes_through_every_point() {
+        let points = [Vec2::new(0.0, 0.0), Vec2::new(10.0, 5.0), Vec2::new(20.0, 0.0), Vec2::new(30.0, 10.0)];
+
+        for (index, point) in points.iter().enumerate() {
+            let t = index as f32 / 3.0;
+            assert!(catmull_rom(&points, t).distance(*point) < 1e-4, "{} at {}", point, t);
+        }
+        assert!(catmull_rom(&points, 2.0).distance(points[3]) < 1e-4);
+
+        // Straight through evenly spaced points, no overshoot
+        let line = [Vec2::ZERO, Vec2::X, Vec2::X * 2.0];
+        assert!(catmull_rom(&line, 0.25).distance(Vec2::X * 0.5) < 1e-4);
+    }
+
+    #[test]
+    fn built_in_paths_load_and_challenge_paths_start_and_end_off_screen() {
+        let library = PathLibrary::default();
+        assert!(library.get("swoop_left").is_some());
+
+        for path in library.challenge_paths() {
+            for (x, y) in [path.points[0], path.points[path.points.len() - 1]] {
+                assert!(x.abs() > 1.0 || y.abs() > 1.0, "{}", path.id);
+            }
+        }
+    }
+
+    #[test]
+    fn challenge_stages_may_only_use_known_paths() {
+        let error = PathLibrary::from_ron(
+            r#"(
+                paths: [(id: "a", points: [(0.0, 0.0), (1.0, 1.0)], duration: 1.0)],
+                challenge_paths: ["b"],
+            )"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("`b`"), "{}", error);
+
+        let error = PathLibrary::from_ron(
+            r#"(
+                paths: [(id: "a", points: [(0.0, 0.0)], duration: 1.0)],
+                challenge_paths: ["a"],
+            )"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("2 points"), "{}", error);
+    }
+
+    #[test]
+    fn flies_hop_to_their_slot_or_leave_at_the_end_of_the_path() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(250)))
+            .insert_resource(PlayField {
+                width: 1200.0,
+                height: 800.0,
+            })
+            .init_resource::<PathLibrary>()
+            .add_systems(Update, follow_paths);
+
+        let library = PathLibrary::default();
+        let entrance = library.get("swoop_left").unwrap().clone();
+        let challenge = library.get("loop_left").unwrap().clone();
+        let play_field = *app.world().resource::<PlayField>();
+
+        let entering = app
+            .world_mut()
+            .spawn((
+                FollowPath::new(&entrance),
+                EnteringFormation::from_path_end(entrance.start(&play_field)),
+                Transform::from_translation(entrance.start(&play_field)),
+            ))
+            .id();
+        let streaming = app
+            .world_mut()
+            .spawn((FollowPath::new(&challenge), Transform::from_translation(challenge.start(&play_field))))
+            .id();
+
+        // Long enough for the entrance, not for the challenge path
+        for _ in 0..9 {
+            app.update(); // 2 seconds, the first update has no elapsed time
+        }
+        assert!(app.world().get::<FollowPath>(entering).is_none());
+        let end = entrance.position(1.0, &play_field).extend(0.0);
+        assert_eq!(app.world().get::<EnteringFormation>(entering).unwrap().start, end);
+        assert!(app.world().get::<FollowPath>(streaming).is_some());
+
+        for _ in 0..28 {
+            app.update(); // 7 seconds more
+        }
+        assert!(app.world().get_entity(streaming).is_err());
+    }
+}
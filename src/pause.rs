@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+use crate::{
+    game_assets::GameAssets,
+    game_state::{spawn_centered_text, GameState, PauseState},
+    keybindings::KeyBindings,
+    layer,
+    replay::ReplayPlayback,
+    ColorsPalette, PlayField,
+};
+
+/// Pausing a run: freezes gameplay and virtual time behind a dimmed overlay.
+/// A replay pauses where the recorded run did, never on a key press.
+///
+/// Stopping `Time<Virtual>` is the one way gameplay is paused: `FixedUpdate`
+/// gets no steps while it stands still, and every timer ticked from `Time` in
+/// `Update` stops counting. Only `Update` systems acting on input or on state
+/// changes still check for `PauseState::Running`. Menus and other things that
+/// should keep going read `Time<Real>` instead.
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(PauseState::Paused), (pause_time, spawn_pause_overlay))
+            .add_systems(OnExit(PauseState::Paused), resume_time)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause.run_if(in_state(GameState::Playing).and(not(resource_exists::<ReplayPlayback>))),
+                    fit_pause_overlay,
+                ),
+            );
+    }
+}
+
+/// The dimmed quad covering the play field while paused
+#[derive(Component)]
+struct PauseOverlay;
+
+/// Switches between running and paused when a pause key is pressed
+fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    pause_state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if !keyboard_input.any_just_pressed(key_bindings.pause.iter().copied()) {
+        return;
+    }
+
+    next_state.set(match pause_state.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+    });
+}
+
+/// Stops virtual time so nothing driven by `Time` keeps counting while paused
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+/// Lets virtual time run again, picking up where it stopped without catching
+/// up on the paused time
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+/// Dims the play field and shows the PAUSED prompt until the game resumes
+fn spawn_pause_overlay(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    play_field: Res<PlayField>,
+) {
+    commands.spawn((
+        PauseOverlay,
+        StateScoped(PauseState::Paused),
+        Sprite {
+            color: color_palette.background_color.with_alpha(0.7),
+            ..Default::default()
+        },
+        Transform {
+            // Drawn above every gameplay sprite
+            translation: Vec3::new(0.0, 0.0, layer::OVERLAY),
+            scale: Vec3::new(play_field.width, play_field.height, 1.0),
+            ..Default::default()
+        },
+    ));
+
+    spawn_centered_text(&mut commands, &game_assets, PauseState::Paused, "PAUSED");
+}
+
+/// Keeps the overlay covering the play field when the field is fitted to a
+/// window that was resized while paused
+fn fit_pause_overlay(
+    play_field: Res<PlayField>,
+    mut overlay_query: Query<&mut Transform, With<PauseOverlay>>,
+) {
+    if !play_field.is_changed() {
+        return;
+    }
+
+    for mut transform in overlay_query.iter_mut() {
+        transform.scale = Vec3::new(play_field.width, play_field.height, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+    use std::time::Duration;
+
+    /// A repeating 1 second spawn timer, counting how often it fired
+    #[derive(Resource)]
+    struct SpawnTicks {
+        timer: Timer,
+        ticks: u32,
+    }
+
+    /// Steps of the fixed timestep run so far
+    #[derive(Resource, Default)]
+    struct FixedSteps(u32);
+
+    fn tick_spawns(mut spawns: ResMut<SpawnTicks>, time: Res<Time>) {
+        spawns.ticks += spawns.timer.tick(time.delta()).times_finished_this_tick();
+    }
+
+    fn count_fixed_steps(mut steps: ResMut<FixedSteps>) {
+        steps.0 += 1;
+    }
+
+    #[test]
+    fn timers_stand_still_while_paused_and_resume_cleanly() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(250)))
+            .init_state::<GameState>()
+            .add_sub_state::<PauseState>()
+            .insert_resource(SpawnTicks {
+                timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+                ticks: 0,
+            })
+            .init_resource::<FixedSteps>()
+            .add_systems(OnEnter(PauseState::Paused), pause_time)
+            .add_systems(OnExit(PauseState::Paused), resume_time)
+            .add_systems(Update, tick_spawns)
+            .add_systems(FixedUpdate, count_fixed_steps);
+
+        app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        for _ in 0..9 {
+            app.update(); // 2 seconds, the first update has no elapsed time
+        }
+        assert_eq!(app.world().resource::<SpawnTicks>().ticks, 2);
+
+        // Time still moves on in the update that pauses, the pause starts after it
+        app.world_mut().resource_mut::<NextState<PauseState>>().set(PauseState::Paused);
+        app.update();
+        let fixed_steps = app.world().resource::<FixedSteps>().0;
+
+        for _ in 0..20 {
+            app.update(); // 5 seconds
+        }
+        assert!(app.world().resource::<Time<Virtual>>().is_paused());
+        assert_eq!(app.world().resource::<SpawnTicks>().ticks, 2);
+        assert_eq!(app.world().resource::<FixedSteps>().0, fixed_steps);
+
+        // Likewise the update that resumes still stands still
+        app.world_mut().resource_mut::<NextState<PauseState>>().set(PauseState::Running);
+        app.update();
+        app.update();
+        app.update();
+        assert_eq!(app.world().resource::<SpawnTicks>().ticks, 2); // 2.75 seconds in
+        app.update();
+        assert_eq!(app.world().resource::<SpawnTicks>().ticks, 3);
+        assert!(app.world().resource::<FixedSteps>().0 > fixed_steps);
+    }
+
+    #[test]
+    fn overlay_follows_a_play_field_refitted_while_paused() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<PlayField>()
+            .add_systems(Update, fit_pause_overlay);
+        let overlay = app
+            .world_mut()
+            .spawn((PauseOverlay, Transform::from_scale(Vec3::new(1200.0, 800.0, 1.0))))
+            .id();
+        app.update();
+
+        *app.world_mut().resource_mut::<PlayField>() = PlayField {
+            width: 600.0,
+            height: 800.0,
+        };
+        app.update();
+
+        assert_eq!(app.world().get::<Transform>(overlay).unwrap().scale, Vec3::new(600.0, 800.0, 1.0));
+    }
+}
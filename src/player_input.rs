@@ -1,79 +1,733 @@
-use bevy::{
-    input::keyboard::KeyboardInput,
-    prelude::*,
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+use crate::{
+    bomb::BOMB_BUTTON,
+    bullet::{checkout_bullet, BulletPool},
+    capture::{DualFighter, DOCK_OFFSET},
+    config::GameConfig,
+    coop::{CoopPlayer, CoopRun},
+    difficulty::DifficultyConfig,
+    game_assets::GameAssets,
+    game_state::{GameState, PauseState},
+    keybindings::KeyBindings,
+    lives::{
+        advance_death_sequence, blink_invulnerable, end_death_sequence, handle_player_hit, respawn_player,
+        tick_invulnerability, DeathSequence, Lives, RespawnTimer, PLAYER_START,
+    },
+    missile::Homing,
+    players::{CurrentPlayer, PlayerSlots},
+    powerup::WeaponUpgrade,
+    replay::ReplayPlayback,
+    run_intro::RunIntro,
+    sound::{Sfx, SfxEvent},
+    Bullet, BulletOwner, BulletSpeed, ColorsPalette, DebugSettings, FiredBy, InputSet, OutlineContainer,
+    Player, PlayerPosition, PlayerVelocity,
 };
-use crate::{ColorsPalette, PlayerPosition, EntityType, GameEntity};
 
-/// Handles player input (keyboard events)
+/// Player ship: input, shooting, lives and respawning
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerPosition(PLAYER_START))
+            .init_resource::<PlayerVelocity>()
+            .insert_resource(ShootCooldown::default())
+            .insert_resource(MaxPlayerBullets::default())
+            .insert_resource(Lives::default())
+            .init_resource::<TouchControls>()
+            .init_resource::<HorizontalInput>()
+            .init_resource::<PlayerSpeed>()
+            .add_systems(OnEnter(GameState::Playing), reset_player)
+            .add_systems(OnExit(GameState::Playing), end_death_sequence)
+            .add_systems(
+                Update,
+                sync_player_speed.run_if(resource_changed::<GameConfig>.or(resource_changed::<DifficultyConfig>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    // Nothing is steered or fired while the ship is in pieces or the run intro
+                    // plays. Touches are still read during the intro, a tap skips it.
+                    (
+                        handle_touch_input,
+                        handle_player_input
+                            .after(handle_touch_input)
+                            .run_if(not(resource_exists::<RunIntro>)),
+                    )
+                        .in_set(InputSet)
+                        .run_if(not(resource_exists::<DeathSequence>)),
+                    bank_player.after(handle_player_input),
+                    clamp_player_to_bounds.after(handle_player_input),
+                    track_player_velocity.after(clamp_player_to_bounds),
+                    sync_player_position.after(track_player_velocity),
+                    handle_player_hit,
+                    advance_death_sequence.after(handle_player_hit),
+                    respawn_player,
+                    tick_invulnerability,
+                    blink_invulnerable.after(tick_invulnerability),
+                )
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// Default player movement speed in units per second
+pub(crate) const PLAYER_SPEED: f32 = 400.0;
+
+/// Seconds it takes `PlayerVelocity` to mostly catch up with a change of speed
+const VELOCITY_SMOOTHING: f32 = 0.1;
+
+/// Default delay between two player shots, in seconds
+pub(crate) const SHOOT_COOLDOWN: f32 = 0.25;
+
+/// Horizontal distance of each twin-shot bullet from the ship's center
+const TWIN_SHOT_OFFSET: f32 = 15.0;
+
+/// Furthest the ship tilts while moving, in radians (15 degrees)
+const MAX_BANK: f32 = 0.26;
+
+/// Radians per second the ship tilts toward its target, reaching full tilt in about a tenth of a second
+const BANK_SPEED: f32 = 2.5;
+
+/// Default number of player bullets allowed on screen at once, as in the arcade
+pub(crate) const MAX_PLAYER_BULLETS: usize = 2;
+
+/// Player bullets allowed on screen at once. Shots are refused while this many
+/// are still flying; power-ups can raise it.
+#[derive(Resource)]
+pub struct MaxPlayerBullets(pub usize);
+
+impl Default for MaxPlayerBullets {
+    fn default() -> Self {
+        MaxPlayerBullets(MAX_PLAYER_BULLETS)
+    }
+}
+
+/// Movement and fire requested by touches this frame, applied together with the keyboard
+#[derive(Resource, Default)]
+pub struct TouchControls {
+    pub move_x: f32, // World units to move the ship sideways
+    pub fire: bool,  // A new tap wants to shoot
+}
+
+/// Units per second the ship moves while a direction is held: the config's
+/// player speed scaled by the difficulty. Dodging dives depends on it.
+#[derive(Resource, Debug, PartialEq)]
+pub struct PlayerSpeed(pub f32);
+
+impl Default for PlayerSpeed {
+    fn default() -> Self {
+        PlayerSpeed(PLAYER_SPEED)
+    }
+}
+
+/// The first ship's keys as bound in `KeyBindings`, plus the gamepad's bomb
+/// button. In a co-op run the co-op ship's keys are left out, so A and D only
+/// steer the second ship. While a replay plays back the recorded input stands
+/// in for all of them.
+#[derive(SystemParam)]
+pub struct ShipKeys<'w, 's> {
+    keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+    key_bindings: Res<'w, KeyBindings>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+    coop_run: Option<Res<'w, CoopRun>>,
+    playback: Option<Res<'w, ReplayPlayback>>,
+}
+
+impl ShipKeys<'_, '_> {
+    /// `keys` without the ones the co-op ship owns this run
+    fn usable<'a>(&'a self, keys: &'a [KeyCode]) -> impl Iterator<Item = KeyCode> + 'a {
+        keys.iter()
+            .copied()
+            .filter(|key| self.coop_run.is_none() || !self.key_bindings.is_coop_key(*key))
+    }
+
+    /// Movement and fire of a replay come in through `TouchControls`, the keys are ignored
+    fn live(&self) -> bool {
+        self.playback.is_none()
+    }
+
+    pub fn left(&self) -> bool {
+        self.live() && self.keyboard_input.any_pressed(self.usable(&self.key_bindings.left))
+    }
+
+    pub fn right(&self) -> bool {
+        self.live() && self.keyboard_input.any_pressed(self.usable(&self.key_bindings.right))
+    }
+
+    /// Only a fresh press fires, so holding the key doesn't spawn a bullet every frame
+    pub fn fire(&self) -> bool {
+        self.live() && self.keyboard_input.any_just_pressed(self.usable(&self.key_bindings.fire))
+    }
+
+    /// A fresh press of a bomb key or the gamepad's bomb button
+    pub fn bomb(&self) -> bool {
+        match &self.playback {
+            Some(playback) => playback.current().is_some_and(|frame| frame.bomb),
+            None => {
+                self.keyboard_input.any_just_pressed(self.usable(&self.key_bindings.bomb))
+                    || self.gamepads.iter().any(|gamepad| gamepad.just_pressed(BOMB_BUTTON))
+            }
+        }
+    }
+
+    /// A fresh press of a missile key
+    pub fn missile(&self) -> bool {
+        match &self.playback {
+            Some(playback) => playback.current().is_some_and(|frame| frame.missile),
+            None => self.keyboard_input.any_just_pressed(self.usable(&self.key_bindings.missile)),
+        }
+    }
+}
+
+/// Which way the player is steering this frame, from -1 (left) to 1 (right).
+/// Written by `handle_player_input` from the keys and touches it already reads.
+#[derive(Resource, Default)]
+pub struct HorizontalInput(pub f32);
+
+/// Tilt of the ship toward the direction it moves in. Only the rotation is
+/// changed: bullets leave from the translation and the collision box ignores
+/// rotation, so banking is purely visual.
+#[derive(Component)]
+pub struct Banking {
+    pub current: f32, // Radians, positive tilts counterclockwise
+    pub target: f32,
+    pub speed: f32, // Radians per second `current` moves toward `target`
+}
+
+impl Default for Banking {
+    fn default() -> Self {
+        Banking {
+            current: 0.0,
+            target: 0.0,
+            speed: BANK_SPEED,
+        }
+    }
+}
+
+/// Minimum time between two player shots
+#[derive(Resource)]
+pub struct ShootCooldown(pub Timer);
+
+impl ShootCooldown {
+    /// Creates a cooldown that is ready to fire immediately
+    pub fn new(seconds: f32) -> Self {
+        let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+        timer.tick(timer.duration());
+        ShootCooldown(timer)
+    }
+
+    /// Current delay between shots
+    pub fn duration(&self) -> std::time::Duration {
+        self.0.duration()
+    }
+
+    /// Changes the delay between shots, e.g. for a rapid-fire power-up
+    pub fn set_duration(&mut self, seconds: f32) {
+        self.0.set_duration(std::time::Duration::from_secs_f32(seconds));
+    }
+}
+
+impl Default for ShootCooldown {
+    fn default() -> Self {
+        ShootCooldown::new(SHOOT_COOLDOWN)
+    }
+}
+
+/// Restores the player resources at the start of a turn, with the lives left
+/// to the player in play and the configured delay between shots
+fn reset_player(
+    mut commands: Commands,
+    slots: Res<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+    config: Res<GameConfig>,
+) {
+    commands.insert_resource(Lives(slots.current(&current).lives));
+    commands.insert_resource(PlayerPosition(PLAYER_START));
+    commands.insert_resource(PlayerVelocity::default());
+    commands.insert_resource(MaxPlayerBullets::default());
+    commands.insert_resource(ShootCooldown::new(config.shoot_cooldown));
+    commands.remove_resource::<RespawnTimer>();
+}
+
+/// Recomputes the ship speed when the config or the difficulty changes
+fn sync_player_speed(
+    config: Res<GameConfig>,
+    difficulty: Res<DifficultyConfig>,
+    mut player_speed: ResMut<PlayerSpeed>,
+) {
+    player_speed.0 = config.player_speed * difficulty.player_speed;
+    debug!(speed = player_speed.0, "Player speed set");
+}
+
+/// Turns touches into ship movement and shots. A drag that starts in the lower
+/// third of the screen moves the ship by the same distance, a tap above it fires.
+/// Every finger is handled on its own, so moving and shooting work together.
+/// Touches are converted with the camera's actual transform, shake included,
+/// so they land where the player sees them even while the screen shakes.
+pub fn handle_touch_input(
+    touches: Res<Touches>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut touch_controls: ResMut<TouchControls>,
+) {
+    *touch_controls = TouchControls::default();
+
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+
+    // Screen coordinates grow downward, so the lower third starts at two thirds of the height
+    let drag_zone_top = window.height() * 2.0 / 3.0;
+
+    for touch in touches.iter() {
+        if touch.start_position().y < drag_zone_top {
+            continue;
+        }
+
+        let (Ok(previous), Ok(current)) = (
+            camera.viewport_to_world_2d(camera_transform, touch.previous_position()),
+            camera.viewport_to_world_2d(camera_transform, touch.position()),
+        ) else {
+            continue;
+        };
+        touch_controls.move_x += current.x - previous.x;
+    }
+
+    touch_controls.fire = touches
+        .iter_just_pressed()
+        .any(|touch| touch.start_position().y < drag_zone_top);
+}
+
+/// Handles player input (held movement keys or a touch drag move, a fresh fire
+/// key press or tap shoots as long as fewer than `MaxPlayerBullets` player bullets are on screen).
+/// A dual fighter fires from both of its ships at once.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn handle_player_input(
-    mut keyboard_input_events: EventReader<KeyboardInput>,
-    mut player_position: ResMut<PlayerPosition>,
-    mut query: Query<(&mut Transform, &GameEntity), With<GameEntity>>,
-    asset_server: Res<AssetServer>,
+    ship_keys: ShipKeys,
+    touch_controls: Res<TouchControls>,
+    time: Res<Time>,
+    mut shoot_cooldown: ResMut<ShootCooldown>,
+    mut horizontal_input: ResMut<HorizontalInput>,
+    max_player_bullets: Res<MaxPlayerBullets>,
+    // Missiles have their own cooldown and don't count toward the cap, nor do the co-op ship's shots
+    bullet_query: Query<&Bullet, (Without<Homing>, Without<FiredBy>)>,
+    bullet_speed: Res<BulletSpeed>,
+    mut query: Query<(&mut Transform, Option<&WeaponUpgrade>, Has<DualFighter>), With<Player>>,
+    mut sfx_events: EventWriter<SfxEvent>,
+    debug_settings: Res<DebugSettings>,
     mut commands: Commands,
     color_palette: Res<ColorsPalette>, // Use the palette here
+    game_assets: Res<GameAssets>,
+    mut bullet_pool: ResMut<BulletPool>,
+    player_speed: Res<PlayerSpeed>,
 ) {
-    let move_delta = 10.0; // --------> Player movement speed <---------
-    let mut move_offset = Vec3::ZERO;
-    let mut shoot = false;
-
-    for event in keyboard_input_events.read() {
-        if let key_code = event.key_code {
-            match key_code {
-                KeyCode::ArrowLeft => {
-                    move_offset += Vec3::new(-move_delta, 0.0, 0.0); // Move left
-                }
-                KeyCode::ArrowRight => {
-                    move_offset += Vec3::new(move_delta, 0.0, 0.0); // Move right
-                }
-                KeyCode::Space => {
-                    shoot = true;
-                }
-                _ => {}
-            }
-        }
+    // Scaled by the frame time, so the ship moves as fast at any frame rate
+    let move_delta = player_speed.0 * time.delta_secs();
+    let mut axis: f32 = 0.0;
+
+    // Holding both directions cancels out and the ship stays put
+    if ship_keys.left() {
+        axis -= 1.0; // Move left
+    }
+    if ship_keys.right() {
+        axis += 1.0; // Move right
+    }
+    let move_offset = Vec3::new(axis * move_delta + touch_controls.move_x, 0.0, 0.0);
+
+    // A drag steers the same way a held key does, however far it moved
+    if touch_controls.move_x != 0.0 {
+        axis += touch_controls.move_x.signum();
     }
+    horizontal_input.0 = axis.clamp(-1.0, 1.0);
+
+    shoot_cooldown.0.tick(time.delta());
+
+    let fire_pressed = ship_keys.fire() || touch_controls.fire;
+    let shoot = fire_pressed && shoot_cooldown.0.finished();
+    let player_bullets = bullet_query
+        .iter()
+        .filter(|bullet| bullet.owner == BulletOwner::Player)
+        .count();
+    let shoot = shoot && player_bullets < max_player_bullets.0;
+
+    let Ok((mut transform, weapon_upgrade, dual)) = query.get_single_mut() else {
+        return; // No ship while waiting to respawn
+    };
 
     if move_offset != Vec3::ZERO {
-        player_position.0 += move_offset;
+        transform.translation += move_offset;
+        if debug_settings.verbose {
+            debug!(position = ?transform.translation, "Player moved");
+        }
+    }
 
-        for (mut transform, game_entity) in query.iter_mut() {
-            if game_entity.entity_type == EntityType::Player {
-                transform.translation += move_offset;
-                println!("Player moved to position: {:?}", transform.translation);
+    if shoot {
+        shoot_cooldown.0.reset();
+        if debug_settings.verbose {
+            debug!(position = ?transform.translation, "Player shoots");
+        }
+        let ship_offsets: &[f32] = if dual { &[0.0, DOCK_OFFSET] } else { &[0.0] };
+        let shot_offsets: &[f32] = if weapon_upgrade.is_some() {
+            &[-TWIN_SHOT_OFFSET, TWIN_SHOT_OFFSET]
+        } else {
+            &[0.0]
+        };
+        for ship_offset in ship_offsets {
+            for shot_offset in shot_offsets {
+                let origin = transform.translation + Vec3::new(ship_offset + shot_offset, 0.0, 0.0);
+                shoot_bullet(
+                    &mut commands,
+                    &mut bullet_pool,
+                    origin,
+                    bullet_speed.0,
+                    &color_palette,
+                    &game_assets,
+                );
             }
         }
+        sfx_events.send(SfxEvent(Sfx::Shoot));
     }
+}
 
-    if shoot {
-        println!("Player shoots!");
-        shoot_bullet(&mut commands, &player_position, &color_palette);
-        let shoot_sound = asset_server.load("sounds/shooting.ogg");
-        commands.spawn(AudioPlayer::new(shoot_sound));
+/// Tilts the ship toward the way it is steering and eases it back upright once
+/// the input stops
+pub fn bank_player(
+    horizontal_input: Res<HorizontalInput>,
+    mut query: Query<(&mut Transform, &mut Banking), With<Player>>,
+    time: Res<Time>,
+) {
+    for (mut transform, mut banking) in query.iter_mut() {
+        // Moving right leans the ship clockwise, i.e. a negative angle
+        banking.target = -horizontal_input.0 * MAX_BANK;
+
+        let step = banking.speed * time.delta_secs();
+        let remaining = banking.target - banking.current;
+        let current = if remaining.abs() <= step {
+            banking.target
+        } else {
+            banking.current + step * remaining.signum()
+        };
+        if current != banking.current {
+            banking.current = current;
+            transform.rotation = Quat::from_rotation_z(current);
+        }
+    }
+}
+
+/// Keeps the player ships inside the container so they stop at the walls. A
+/// docked second ship stops at the right wall too.
+#[allow(clippy::type_complexity)]
+pub fn clamp_player_to_bounds(
+    mut player_query: Query<
+        (&mut Transform, Has<DualFighter>),
+        (Or<(With<Player>, With<CoopPlayer>)>, Without<OutlineContainer>),
+    >,
+    container_query: Query<(&Transform, &OutlineContainer)>,
+) {
+    let Ok((container_transform, container)) = container_query.get_single() else {
+        return;
+    };
+
+    for (mut transform, dual) in player_query.iter_mut() {
+        let half_width = container.width / 2.0 - transform.scale.x / 2.0;
+        let min_x = container_transform.translation.x - half_width;
+        let mut max_x = container_transform.translation.x + half_width;
+        if dual {
+            max_x -= DOCK_OFFSET;
+        }
+
+        if transform.translation.x < min_x || transform.translation.x > max_x {
+            transform.translation.x = transform.translation.x.clamp(min_x, max_x);
+        }
+    }
+}
+
+/// Eases `PlayerVelocity` toward how fast the ship moved since `PlayerPosition`
+/// was last synced, so one jerky frame doesn't swing where enemies aim. A ship
+/// that was just spawned has not moved yet, however far it is from the last one.
+/// Like `PlayerPosition` this follows the `Player` ship only, the one all enemy
+/// fire aims at, so fire is never led at the co-op ship.
+pub fn track_player_velocity(
+    query: Query<Ref<Transform>, With<Player>>,
+    player_position: Res<PlayerPosition>,
+    mut player_velocity: ResMut<PlayerVelocity>,
+    time: Res<Time>,
+) {
+    let Ok(transform) = query.get_single() else {
+        player_velocity.0 = Vec2::ZERO;
+        return;
+    };
+    if transform.is_added() {
+        player_velocity.0 = Vec2::ZERO;
+        return;
     }
+
+    let delta = time.delta_secs();
+    if delta <= 0.0 {
+        return;
+    }
+    let moved = (transform.translation - player_position.0).truncate() / delta;
+    let blend = 1.0 - (-delta / VELOCITY_SMOOTHING).exp();
+    player_velocity.0 = player_velocity.0.lerp(moved, blend);
 }
 
-/// Shoots a bullet from the player's position
-fn shoot_bullet(
+/// Copies the player's transform into `PlayerPosition`. This is the only place
+/// the resource is written while playing, so the two can never drift apart.
+pub fn sync_player_position(
+    query: Query<&Transform, (With<Player>, Changed<Transform>)>,
+    mut player_position: ResMut<PlayerPosition>,
+) {
+    if let Ok(transform) = query.get_single() {
+        player_position.0 = transform.translation;
+    }
+}
+
+/// Shoots a bullet straight up from the player's position at `speed`, returning it
+pub(crate) fn shoot_bullet(
     commands: &mut Commands,
-    player_position: &ResMut<PlayerPosition>,
+    bullet_pool: &mut BulletPool,
+    player_translation: Vec3,
+    speed: f32,
     color_palette: &ColorsPalette,
-) {
-    let bullet_starting_position = player_position.0 + Vec3::new(0.0, 50.0, 0.0);
-
-    commands.spawn((
-        crate::Bullet,
-        SpriteBundle {
-            transform: Transform {
-                translation: bullet_starting_position,
-                scale: Vec3::splat(10.0),
-                ..Default::default()
-            },
-            sprite: Sprite {
-                color: color_palette.bullet_color,
-                ..Default::default()
-            },
-            ..Default::default()
-        },
-    ));
+    game_assets: &GameAssets,
+) -> Entity {
+    let bullet_starting_position = player_translation + Vec3::new(0.0, 50.0, 0.0);
+
+    checkout_bullet(
+        commands,
+        bullet_pool,
+        BulletOwner::Player,
+        bullet_starting_position,
+        Vec2::new(0.0, speed),
+        color_palette,
+        game_assets,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Velocity;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    fn shooting_app() -> App {
+        let mut app = App::new();
+        let mut keyboard_input = ButtonInput::<KeyCode>::default();
+        keyboard_input.press(KeyCode::Space);
+
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(keyboard_input)
+            .init_resource::<KeyBindings>()
+            .init_resource::<TouchControls>()
+            .init_resource::<HorizontalInput>()
+            .insert_resource(ShootCooldown::new(0.0))
+            .insert_resource(MaxPlayerBullets::default())
+            .insert_resource(BulletSpeed(300.0))
+            .add_event::<SfxEvent>()
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<BulletPool>()
+            .init_resource::<DebugSettings>()
+            .init_resource::<PlayerSpeed>()
+            .add_systems(Update, handle_player_input);
+        app.world_mut().spawn((Player, Transform::from_translation(PLAYER_START)));
+        app
+    }
+
+    fn player_bullets(app: &mut App) -> usize {
+        app.world_mut()
+            .query::<&Bullet>()
+            .iter(app.world())
+            .filter(|bullet| bullet.owner == BulletOwner::Player)
+            .count()
+    }
+
+    #[test]
+    fn cannot_shoot_past_player_bullet_cap() {
+        let mut app = shooting_app();
+        for _ in 0..MAX_PLAYER_BULLETS {
+            app.world_mut().spawn((
+                Bullet { owner: BulletOwner::Player },
+                Transform::default(),
+            ));
+        }
+
+        app.update();
+
+        assert_eq!(player_bullets(&mut app), MAX_PLAYER_BULLETS);
+    }
+
+    #[test]
+    fn enemy_bullets_do_not_count_toward_cap() {
+        let mut app = shooting_app();
+        for _ in 0..MAX_PLAYER_BULLETS {
+            app.world_mut().spawn((
+                Bullet { owner: BulletOwner::Enemy },
+                Transform::default(),
+            ));
+        }
+
+        app.update();
+
+        assert_eq!(player_bullets(&mut app), 1);
+    }
+
+    #[test]
+    fn reset_player_leaves_nothing_from_the_previous_run() {
+        let mut app = App::new();
+        let mut cooldown = ShootCooldown::default();
+        cooldown.set_duration(SHOOT_COOLDOWN * 4.0);
+        cooldown.0.reset();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Lives(0))
+            .insert_resource(PlayerPosition(Vec3::new(120.0, -250.0, 0.0)))
+            .insert_resource(MaxPlayerBullets(MAX_PLAYER_BULLETS * 2))
+            .insert_resource(cooldown)
+            .insert_resource(RespawnTimer(Timer::from_seconds(1.0, TimerMode::Once)))
+            .init_resource::<PlayerSlots>()
+            .init_resource::<CurrentPlayer>()
+            .init_resource::<GameConfig>()
+            .add_systems(Update, reset_player);
+
+        app.update();
+
+        assert_eq!(app.world().resource::<Lives>().0, Lives::default().0);
+        assert_eq!(app.world().resource::<PlayerPosition>().0, PLAYER_START);
+        assert_eq!(app.world().resource::<MaxPlayerBullets>().0, MAX_PLAYER_BULLETS);
+        let shoot_cooldown = app.world().resource::<ShootCooldown>();
+        assert!(shoot_cooldown.0.finished());
+        assert_eq!(
+            shoot_cooldown.duration(),
+            Duration::from_secs_f32(GameConfig::default().shoot_cooldown)
+        );
+        assert!(!app.world().contains_resource::<RespawnTimer>());
+    }
+
+    #[test]
+    fn velocity_follows_the_ship_and_ignores_a_respawn() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .insert_resource(PlayerPosition(Vec3::new(300.0, -250.0, 0.0)))
+            .init_resource::<PlayerVelocity>()
+            .add_systems(Update, (track_player_velocity, sync_player_position).chain());
+        let ship = app.world_mut().spawn((Player, Transform::from_translation(PLAYER_START))).id();
+        let velocity = |app: &App| app.world().resource::<PlayerVelocity>().0;
+
+        // Spawned far from the last known position, yet not moving
+        app.update();
+        app.update();
+        assert_eq!(velocity(&app), Vec2::ZERO);
+
+        // 10 units every 50 ms
+        for _ in 0..20 {
+            app.world_mut().get_mut::<Transform>(ship).unwrap().translation.x += 10.0;
+            app.update();
+        }
+        assert!((velocity(&app) - Vec2::new(200.0, 0.0)).length() < 1.0);
+
+        app.world_mut().despawn(ship);
+        app.update();
+        assert_eq!(velocity(&app), Vec2::ZERO);
+    }
+
+    #[test]
+    fn ship_banks_into_the_turn_and_levels_out() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .insert_resource(HorizontalInput(1.0))
+            .add_systems(Update, bank_player);
+        let ship = app.world_mut().spawn((Player, Banking::default(), Transform::default())).id();
+        let tilt = |app: &App| app.world().get::<Banking>(ship).unwrap().current;
+
+        app.update();
+        app.update();
+        assert!(tilt(&app) < 0.0 && tilt(&app) > -MAX_BANK);
+
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(tilt(&app), -MAX_BANK);
+
+        app.world_mut().resource_mut::<HorizontalInput>().0 = 0.0;
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(tilt(&app), 0.0);
+        assert_eq!(app.world().get::<Transform>(ship).unwrap().rotation, Quat::IDENTITY);
+    }
+
+    #[test]
+    fn banked_ship_still_fires_straight_up() {
+        let mut app = shooting_app();
+        let mut ship = app.world_mut().query_filtered::<&mut Transform, With<Player>>();
+        ship.single_mut(app.world_mut()).rotation = Quat::from_rotation_z(-MAX_BANK);
+
+        app.update();
+
+        let (transform, velocity) = app
+            .world_mut()
+            .query_filtered::<(&Transform, &Velocity), With<Bullet>>()
+            .single(app.world());
+        assert_eq!(transform.translation.x, PLAYER_START.x);
+        assert_eq!(transform.rotation, Quat::IDENTITY);
+        assert_eq!(velocity.0.x, 0.0);
+    }
+    #[test]
+    fn ship_moves_by_speed_times_frame_time_and_stops_with_both_keys_held() {
+        let mut keyboard_input = ButtonInput::<KeyCode>::default();
+        keyboard_input.press(KeyCode::ArrowRight);
+
+        let mut app = shooting_app();
+        app.insert_resource(keyboard_input)
+            .insert_resource(PlayerSpeed(300.0))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)));
+        let x = |app: &mut App| {
+            let mut ship = app.world_mut().query_filtered::<&Transform, With<Player>>();
+            ship.single(app.world()).translation.x
+        };
+
+        app.update(); // The first frame has no elapsed time
+        app.update();
+        assert!((x(&mut app) - (PLAYER_START.x + 30.0)).abs() < 1e-3);
+
+        app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::ArrowLeft);
+        app.update();
+        assert!((x(&mut app) - (PLAYER_START.x + 30.0)).abs() < 1e-3);
+        assert_eq!(app.world().resource::<HorizontalInput>().0, 0.0);
+    }
+
+    #[test]
+    fn coop_keys_only_steer_the_coop_ship_in_coop() {
+        let mut keyboard_input = ButtonInput::<KeyCode>::default();
+        keyboard_input.press(KeyCode::KeyD);
+
+        let mut app = shooting_app();
+        app.insert_resource(keyboard_input);
+        app.update();
+        assert_eq!(app.world().resource::<HorizontalInput>().0, 1.0);
+
+        app.insert_resource(CoopRun);
+        app.update();
+        assert_eq!(app.world().resource::<HorizontalInput>().0, 0.0);
+    }
+
+    #[test]
+    fn player_speed_follows_the_config_and_the_difficulty() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<PlayerSpeed>()
+            .init_resource::<GameConfig>()
+            .insert_resource(DifficultyConfig::hard())
+            .add_systems(Update, sync_player_speed);
+
+        app.world_mut().resource_mut::<GameConfig>().player_speed = 500.0;
+        app.update();
+
+        let expected = 500.0 * DifficultyConfig::hard().player_speed;
+        assert_eq!(app.world().resource::<PlayerSpeed>().0, expected);
+    }
 }
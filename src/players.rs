@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+use crate::{
+    attract::DemoRun,
+    difficulty::DifficultyConfig,
+    game_assets::GameAssets,
+    game_state::{spawn_centered_text, GameState},
+    highscore::record_high_score,
+    lives::Lives,
+    practice::PracticeRun,
+    wave::{StartingWave, Wave},
+    Score,
+};
+
+/// Seconds the "PLAYER N READY" screen is shown before the next turn starts
+const TURN_CHANGE_DURATION: f32 = 2.5;
+
+/// One or two players. With two they take turns, each with their own score,
+/// lives and wave, switching whenever the player in play loses a ship.
+pub struct PlayersPlugin;
+
+impl Plugin for PlayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerCount>()
+            .init_resource::<PlayerSlots>()
+            .init_resource::<CurrentPlayer>()
+            .add_systems(
+                OnEnter(GameState::TurnChange),
+                (save_current_player, next_player, spawn_ready_screen).chain(),
+            )
+            .add_systems(Update, end_turn_change.run_if(in_state(GameState::TurnChange)))
+            .add_systems(OnEnter(GameState::GameOver), save_current_player.before(record_high_score));
+
+        // Every way into a fresh run. Coming back from a turn change resumes the slots instead.
+        for state in [GameState::Loading, GameState::Menu, GameState::GameOver] {
+            app.add_systems(OnExit(state), start_new_game);
+        }
+    }
+}
+
+/// How many players the next run is for, picked on the title screen
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerCount(pub usize);
+
+impl Default for PlayerCount {
+    fn default() -> Self {
+        PlayerCount(1)
+    }
+}
+
+/// Where a player stands in the run, kept while the other player has their turn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerSlot {
+    pub score: u32,
+    pub lives: u32,
+    pub wave: u32,
+}
+
+/// Every player of the run, in turn order. A turn starts from the slot of the
+/// current player: `Score`, `Lives` and `Wave` are loaded from it and written
+/// back when the turn ends.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PlayerSlots(pub Vec<PlayerSlot>);
+
+impl PlayerSlots {
+    /// `players` fresh slots, all starting with `lives` ships on wave `wave`
+    pub fn new(players: usize, lives: u32, wave: u32) -> Self {
+        PlayerSlots(vec![PlayerSlot { score: 0, lives, wave }; players.max(1)])
+    }
+
+    /// Slot of the player in play
+    pub fn current(&self, current: &CurrentPlayer) -> PlayerSlot {
+        self.0[current.0]
+    }
+
+    /// First player after `player` in turn order with ships left, going round
+    /// the table so `player` itself comes last
+    pub fn next_with_lives(&self, player: usize) -> Option<usize> {
+        let count = self.0.len();
+        (1..=count)
+            .map(|step| (player + step) % count)
+            .find(|&next| self.0[next].lives > 0)
+    }
+
+    /// Whether anyone but `player` still has ships, so losing one passes the turn on
+    pub fn others_have_lives(&self, player: usize) -> bool {
+        self.0
+            .iter()
+            .enumerate()
+            .any(|(index, slot)| index != player && slot.lives > 0)
+    }
+
+    /// Highest score of any player, the one that counts for the high score
+    pub fn best_score(&self) -> u32 {
+        self.0.iter().map(|slot| slot.score).max().unwrap_or(0)
+    }
+}
+
+impl Default for PlayerSlots {
+    fn default() -> Self {
+        PlayerSlots::new(1, DifficultyConfig::normal().starting_lives, 1)
+    }
+}
+
+/// Index into `PlayerSlots` of the player in play
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentPlayer(pub usize);
+
+/// Counts down the "PLAYER N READY" screen
+#[derive(Resource)]
+struct TurnChangeTimer(Timer);
+
+/// Sets up the slots for a new run with the chosen number of players, player 1
+/// first. The demo always plays alone. A practice run starts on the wave
+/// picked for it.
+fn start_new_game(
+    mut commands: Commands,
+    player_count: Res<PlayerCount>,
+    demo: Option<Res<DemoRun>>,
+    practice: Option<Res<PracticeRun>>,
+    difficulty: Res<DifficultyConfig>,
+    starting_wave: Res<StartingWave>,
+) {
+    let players = if demo.is_some() { 1 } else { player_count.0 };
+    let wave = practice.map_or(starting_wave.0, |practice| practice.0.starting_wave);
+    commands.insert_resource(PlayerSlots::new(players, difficulty.starting_lives, wave));
+    commands.insert_resource(CurrentPlayer(0));
+}
+
+/// Writes the score, lives and wave of the player in play back to their slot
+fn save_current_player(
+    mut slots: ResMut<PlayerSlots>,
+    current: Res<CurrentPlayer>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    wave: Res<Wave>,
+) {
+    slots.0[current.0] = PlayerSlot {
+        score: score.0,
+        lives: lives.0,
+        wave: wave.0,
+    };
+}
+
+/// Passes the turn to the next player with ships left
+fn next_player(slots: Res<PlayerSlots>, mut current: ResMut<CurrentPlayer>) {
+    if let Some(next) = slots.next_with_lives(current.0) {
+        current.0 = next;
+    }
+    info!(player = current.0 + 1, "Next player's turn");
+}
+
+/// Shows whose turn is next on an empty screen, the field is cleared in between
+fn spawn_ready_screen(mut commands: Commands, game_assets: Res<GameAssets>, current: Res<CurrentPlayer>) {
+    spawn_centered_text(
+        &mut commands,
+        &game_assets,
+        GameState::TurnChange,
+        &format!("PLAYER {} READY", current.0 + 1),
+    );
+    commands.insert_resource(TurnChangeTimer(Timer::from_seconds(TURN_CHANGE_DURATION, TimerMode::Once)));
+}
+
+/// Starts the next turn once the ready screen was shown long enough
+fn end_turn_change(
+    mut timer: ResMut<TurnChangeTimer>,
+    time: Res<Time>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_skip_players_without_ships() {
+        let mut slots = PlayerSlots::new(2, 3, 1);
+        assert_eq!(slots.next_with_lives(0), Some(1));
+        assert_eq!(slots.next_with_lives(1), Some(0));
+        assert!(slots.others_have_lives(0));
+
+        slots.0[1].lives = 0;
+        assert_eq!(slots.next_with_lives(0), Some(0));
+        assert!(!slots.others_have_lives(0));
+        assert!(slots.others_have_lives(1));
+
+        slots.0[0].lives = 0;
+        assert_eq!(slots.next_with_lives(0), None);
+    }
+
+    #[test]
+    fn ending_a_turn_stores_the_player_and_hands_over() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(PlayerSlots::new(2, 3, 1))
+            .init_resource::<CurrentPlayer>()
+            .insert_resource(Score(1200))
+            .insert_resource(Lives(2))
+            .insert_resource(Wave(4))
+            .add_systems(Update, (save_current_player, next_player).chain());
+
+        app.update();
+
+        let slots = app.world().resource::<PlayerSlots>();
+        assert_eq!(slots.0[0], PlayerSlot { score: 1200, lives: 2, wave: 4 });
+        assert_eq!(slots.0[1], PlayerSlot { score: 0, lives: 3, wave: 1 });
+        assert_eq!(slots.best_score(), 1200);
+        assert_eq!(*app.world().resource::<CurrentPlayer>(), CurrentPlayer(1));
+    }
+}
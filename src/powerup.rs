@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use rand::Rng;
+use crate::{
+    bomb::{Bombs, MAX_BOMBS},
+    capture::DualFighter,
+    collision::is_colliding,
+    difficulty::DifficultyConfig,
+    fly_logic::EnemyKind,
+    game_assets::GameAssets,
+    game_rng::GameRng,
+    game_state::{GameState, PauseState},
+    layer,
+    player_input::MaxPlayerBullets,
+    ColorsPalette, DespawnOutOfBounds, EnemyKilled, Player, Score, Velocity,
+};
+
+/// Speed pickups fall at, in units per second
+const PICKUP_FALL_SPEED: f32 = 150.0;
+
+/// Share of dropped pickups that are bombs
+const BOMB_PICKUP_SHARE: f32 = 0.1;
+
+/// Share of dropped pickups that are shields, the rest is the twin shot
+const SHIELD_PICKUP_SHARE: f32 = 0.15;
+
+/// Points for collecting a shield while one is already up
+pub const SHIELD_BONUS_POINTS: u32 = 500;
+
+/// Size of the shield bubble relative to the ship
+const SHIELD_SCALE: f32 = 1.4;
+
+/// Opacity of the shield bubble, so the ship stays visible through it
+const SHIELD_ALPHA: f32 = 0.35;
+
+/// Seconds the twin shot lasts after collecting a pickup
+pub const WEAPON_UPGRADE_DURATION: f32 = 15.0;
+
+/// Falling pickups dropped by enemies: the twin-shot upgrade or, more rarely,
+/// a shield or a bomb
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DropChance::default()).add_systems(
+            Update,
+            (
+                drop_pickups,
+                collect_pickups,
+                tick_weapon_upgrade.after(collect_pickups),
+                sync_bullet_cap.after(tick_weapon_upgrade),
+            )
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// Chance in `[0, 1]` that a destroyed enemy drops a pickup
+#[derive(Resource)]
+pub struct DropChance(pub f32);
+
+impl Default for DropChance {
+    fn default() -> Self {
+        DropChance(DifficultyConfig::normal().drop_chance)
+    }
+}
+
+/// A pickup falling toward the bottom of the screen, and what it grants
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUp {
+    TwinShot,
+    Bomb, // One more bomb charge, up to `MAX_BOMBS`
+    Shield,
+}
+
+/// Player ships carrying this fire two bullets per shot until the timer runs out
+#[derive(Component)]
+pub struct WeaponUpgrade(pub Timer);
+
+impl Default for WeaponUpgrade {
+    fn default() -> Self {
+        WeaponUpgrade(Timer::from_seconds(WEAPON_UPGRADE_DURATION, TimerMode::Once))
+    }
+}
+
+/// Player ships carrying this lose the shield instead of a life on their next
+/// hit. `bubble` is the child sprite drawing it.
+#[derive(Component)]
+pub struct Shield {
+    pub bubble: Entity,
+}
+
+/// Rolls the drop chance for every enemy destroyed this frame, then which
+/// pickup drops. The mothership always drops one.
+fn drop_pickups(
+    mut commands: Commands,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    drop_chance: Res<DropChance>,
+    mut game_rng: ResMut<GameRng>,
+    color_palette: Res<ColorsPalette>,
+) {
+    for event in enemy_killed_events.read() {
+        if event.kind == EnemyKind::Mothership || game_rng.gen::<f32>() < drop_chance.0 {
+            let roll = game_rng.gen::<f32>();
+            let kind = if roll < BOMB_PICKUP_SHARE {
+                PowerUp::Bomb
+            } else if roll < BOMB_PICKUP_SHARE + SHIELD_PICKUP_SHARE {
+                PowerUp::Shield
+            } else {
+                PowerUp::TwinShot
+            };
+            spawn_pickup(&mut commands, event.position, kind, &color_palette);
+        }
+    }
+}
+
+/// Spawns a pickup at `position`. Missed pickups fall off the bottom and are culled.
+pub fn spawn_pickup(commands: &mut Commands, position: Vec3, kind: PowerUp, color_palette: &ColorsPalette) {
+    let color = match kind {
+        PowerUp::TwinShot => color_palette.powerup_color,
+        PowerUp::Bomb => color_palette.bomb_color,
+        PowerUp::Shield => color_palette.shield_color,
+    };
+
+    commands.spawn((
+        kind,
+        Velocity(Vec2::new(0.0, -PICKUP_FALL_SPEED)),
+        DespawnOutOfBounds,
+        StateScoped(GameState::Playing),
+        Sprite {
+            color,
+            ..Default::default()
+        },
+        Transform {
+            translation: position.with_z(layer::BULLETS),
+            scale: Vec3::splat(20.0),
+            ..Default::default()
+        },
+    ));
+}
+
+/// Grants what a pickup holds when the player touches it. Collecting another
+/// twin shot while upgraded restarts the timer instead of stacking, bombs add a
+/// charge unless the player already holds `MAX_BOMBS`. Shields don't stack
+/// either, one collected while shielded is worth `SHIELD_BONUS_POINTS` instead.
+#[allow(clippy::type_complexity)]
+pub fn collect_pickups(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &Transform, Option<&mut WeaponUpgrade>, Has<Shield>), With<Player>>,
+    pickup_query: Query<(Entity, &PowerUp, &Transform), Without<Player>>,
+    mut bombs: ResMut<Bombs>,
+    mut score: ResMut<Score>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+) {
+    let Ok((player_entity, player_transform, mut upgrade, mut shielded)) = player_query.get_single_mut()
+    else {
+        return;
+    };
+
+    for (pickup_entity, kind, pickup_transform) in pickup_query.iter() {
+        if !is_colliding(
+            player_transform.translation.truncate(),
+            player_transform.scale.truncate(),
+            pickup_transform.translation.truncate(),
+            pickup_transform.scale.truncate(),
+        ) {
+            continue;
+        }
+
+        commands.entity(pickup_entity).despawn();
+
+        match (kind, upgrade.as_mut()) {
+            (PowerUp::Bomb, _) => bombs.0 = (bombs.0 + 1).min(MAX_BOMBS),
+            (PowerUp::TwinShot, Some(upgrade)) => upgrade.0.reset(),
+            (PowerUp::TwinShot, None) => {
+                commands.entity(player_entity).insert(WeaponUpgrade::default());
+            }
+            (PowerUp::Shield, _) if shielded => score.0 += SHIELD_BONUS_POINTS,
+            (PowerUp::Shield, _) => {
+                spawn_shield(&mut commands, player_entity, &color_palette, &game_assets);
+                shielded = true; // A second shield in the same frame is a bonus too
+            }
+        }
+    }
+}
+
+/// Puts a shield on the player ship, drawn as a translucent bubble around it.
+/// The bubble has no collider, so the ship's own bullets fly straight through.
+fn spawn_shield(commands: &mut Commands, player: Entity, color_palette: &ColorsPalette, game_assets: &GameAssets) {
+    let bubble = commands
+        .spawn((
+            GameAssets::sprite(&game_assets.shield, color_palette.shield_color.with_alpha(SHIELD_ALPHA)),
+            Transform {
+                // Scaled along with the ship, and drawn just in front of it
+                translation: Vec3::new(0.0, 0.0, 0.1),
+                scale: Vec3::splat(SHIELD_SCALE),
+                ..Default::default()
+            },
+        ))
+        .id();
+
+    commands.entity(player).add_child(bubble).insert(Shield { bubble });
+    debug!("Shield up");
+}
+
+/// Takes the shield off `player` and removes its bubble, after it absorbed a hit
+pub fn break_shield(commands: &mut Commands, player: Entity, shield: &Shield) {
+    commands.entity(shield.bubble).despawn_recursive();
+    commands.entity(player).remove::<Shield>();
+}
+
+/// Counts down the twin shot and removes it when it expires
+fn tick_weapon_upgrade(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut WeaponUpgrade)>,
+    time: Res<Time>,
+) {
+    for (entity, mut upgrade) in query.iter_mut() {
+        if upgrade.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<WeaponUpgrade>();
+        }
+    }
+}
+
+/// Doubles the player bullet cap while the twin shot is active, and again for a
+/// dual fighter, so each trigger still allows as many volleys as a single shot does
+fn sync_bullet_cap(
+    player_query: Query<(Has<WeaponUpgrade>, Has<DualFighter>), With<Player>>,
+    mut max_player_bullets: ResMut<MaxPlayerBullets>,
+) {
+    let default_cap = MaxPlayerBullets::default().0;
+    let (upgraded, dual) = player_query.get_single().unwrap_or_default();
+    let cap = default_cap * (1 + upgraded as usize) * (1 + dual as usize);
+
+    if max_player_bullets.0 != cap {
+        max_player_bullets.0 = cap;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn pickup_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Bombs>()
+            .init_resource::<Score>()
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .add_systems(Update, collect_pickups);
+        app
+    }
+
+    #[test]
+    fn second_pickup_refreshes_the_upgrade() {
+        let mut app = pickup_app();
+
+        let mut upgrade = WeaponUpgrade::default();
+        upgrade.0.tick(Duration::from_secs(10));
+        let player = app
+            .world_mut()
+            .spawn((Player, upgrade, Transform::from_scale(Vec3::splat(50.0))))
+            .id();
+        let pickup = app
+            .world_mut()
+            .spawn((PowerUp::TwinShot, Transform::from_scale(Vec3::splat(20.0))))
+            .id();
+
+        app.update();
+
+        assert!(app.world().get_entity(pickup).is_err());
+        let upgrade = app.world().get::<WeaponUpgrade>(player).unwrap();
+        assert_eq!(upgrade.0.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn bomb_pickups_add_charges_up_to_the_cap() {
+        let mut app = pickup_app();
+        app.insert_resource(Bombs(MAX_BOMBS - 1));
+        app.world_mut().spawn((Player, Transform::from_scale(Vec3::splat(50.0))));
+
+        for _ in 0..2 {
+            app.world_mut()
+                .spawn((PowerUp::Bomb, Transform::from_scale(Vec3::splat(20.0))));
+            app.update();
+        }
+
+        assert_eq!(app.world().resource::<Bombs>().0, MAX_BOMBS);
+    }
+    #[test]
+    fn second_shield_is_worth_points_instead_of_stacking() {
+        let mut app = pickup_app();
+        let player = app.world_mut().spawn((Player, Transform::from_scale(Vec3::splat(50.0)))).id();
+
+        for _ in 0..2 {
+            app.world_mut()
+                .spawn((PowerUp::Shield, Transform::from_scale(Vec3::splat(20.0))));
+            app.update();
+        }
+
+        let bubble = app.world().get::<Shield>(player).unwrap().bubble;
+        assert_eq!(app.world().get::<Parent>(bubble).map(|parent| parent.get()), Some(player));
+        assert_eq!(app.world().get::<Children>(player).unwrap().len(), 1);
+        assert_eq!(app.world().resource::<Score>().0, SHIELD_BONUS_POINTS);
+    }
+}
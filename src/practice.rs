@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use crate::{
+    game_assets::GameAssets,
+    game_state::{GameState, PauseState},
+    resize::FieldAnchor,
+    ColorsPalette,
+};
+
+/// Highest wave a practice run can start on, the highest stage the banner shows
+pub const MAX_PRACTICE_WAVE: u32 = 99;
+
+/// Game speeds a practice run can be played at, slowest first
+pub const PRACTICE_SPEEDS: [f32; 5] = [0.5, 0.75, 1.0, 1.25, 1.5];
+
+/// Key that ends a paused practice run, back to the title screen
+const QUIT_KEY: KeyCode = KeyCode::KeyQ;
+
+/// Practice runs, set up on their own menu page: any starting wave, slower or
+/// faster game time, infinite lives and enemies that never shoot. They are
+/// labeled as such while playing and never make the high score table or the
+/// lifetime stats. As one may never run out of lives, it can be left from the
+/// pause screen.
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PracticeSettings>()
+            .add_systems(OnEnter(GameState::Menu), end_practice)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (apply_game_speed, spawn_practice_label.run_if(resource_exists::<PracticeRun>)),
+            )
+            .add_systems(OnEnter(PauseState::Paused), spawn_quit_hint.run_if(resource_exists::<PracticeRun>))
+            .add_systems(
+                Update,
+                quit_practice.run_if(in_state(PauseState::Paused).and(resource_exists::<PracticeRun>)),
+            );
+    }
+}
+
+/// What the next practice run is like, picked on the practice page
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PracticeSettings {
+    pub starting_wave: u32,
+    pub speed: f32, // Relative speed of game time, one of `PRACTICE_SPEEDS`
+    pub infinite_lives: bool,
+    pub enemy_fire: bool,
+}
+
+impl Default for PracticeSettings {
+    fn default() -> Self {
+        PracticeSettings {
+            starting_wave: 1,
+            speed: 1.0,
+            infinite_lives: false,
+            enemy_fire: true,
+        }
+    }
+}
+
+impl PracticeSettings {
+    /// Moves the starting wave `step` waves along, wrapping around at both ends
+    pub fn step_wave(&mut self, step: isize) {
+        let index = self.starting_wave.clamp(1, MAX_PRACTICE_WAVE) as isize - 1;
+        self.starting_wave = (index + step).rem_euclid(MAX_PRACTICE_WAVE as isize) as u32 + 1;
+    }
+
+    /// Moves the speed `step` places along `PRACTICE_SPEEDS`, wrapping around at both ends
+    pub fn step_speed(&mut self, step: isize) {
+        let index = PRACTICE_SPEEDS.iter().position(|speed| *speed == self.speed).unwrap_or(2);
+        let len = PRACTICE_SPEEDS.len() as isize;
+        self.speed = PRACTICE_SPEEDS[(index as isize + step).rem_euclid(len) as usize];
+    }
+}
+
+/// Present while the run in progress is a practice run, with the settings it
+/// was started with
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PracticeRun(pub PracticeSettings);
+
+/// Shows the run is a practice one, and how it differs from a real one
+#[derive(Component)]
+struct PracticeText;
+
+/// Relative speed of game time in the run, `practice` being the practice run, if any
+pub fn game_speed(practice: Option<&PracticeRun>) -> f32 {
+    practice.map_or(1.0, |practice| practice.0.speed)
+}
+
+/// Whether losing a ship costs a life in the run
+pub fn infinite_lives(practice: Option<&PracticeRun>) -> bool {
+    practice.is_some_and(|practice| practice.0.infinite_lives)
+}
+
+/// Run condition for everything that fires enemy bullets. Only a practice run
+/// ever turns enemy fire off.
+pub fn enemy_fire_on(practice: Option<Res<PracticeRun>>) -> bool {
+    practice.is_none_or(|practice| practice.0.enemy_fire)
+}
+
+/// Back on the menu the next run is a real one again
+fn end_practice(mut commands: Commands) {
+    commands.remove_resource::<PracticeRun>();
+}
+
+/// Runs game time at the practice speed, or at full speed in a real run
+fn apply_game_speed(mut virtual_time: ResMut<Time<Virtual>>, practice: Option<Res<PracticeRun>>) {
+    virtual_time.set_relative_speed(game_speed(practice.as_deref()));
+}
+
+/// Text of the practice label, naming every setting that differs from a real run
+fn practice_label(settings: &PracticeSettings) -> String {
+    let mut label = "PRACTICE".to_string();
+    if settings.speed != 1.0 {
+        label.push_str(&format!("  {}x", settings.speed));
+    }
+    if settings.infinite_lives {
+        label.push_str("  INFINITE LIVES");
+    }
+    if !settings.enemy_fire {
+        label.push_str("  NO ENEMY FIRE");
+    }
+    label
+}
+
+/// Spawns the practice label in the bottom left corner of the play field. Like
+/// the HUD, it is gone as soon as the game leaves Playing.
+fn spawn_practice_label(
+    mut commands: Commands,
+    practice: Res<PracticeRun>,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+) {
+    commands
+        .spawn((
+            StateScoped(GameState::Playing),
+            FieldAnchor { margin: 10.0 },
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                PracticeText,
+                Text::new(practice_label(&practice.0)),
+                TextFont {
+                    font: game_assets.font.clone(),
+                    font_size: 24.0,
+                    ..Default::default()
+                },
+                TextColor(color_palette.bee_color),
+            ));
+        });
+}
+
+/// Tells how to leave the practice run, below the PAUSED prompt
+fn spawn_quit_hint(mut commands: Commands, game_assets: Res<GameAssets>) {
+    commands
+        .spawn((
+            StateScoped(PauseState::Paused),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Percent(35.0),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Q - Back to the title screen"),
+                TextFont {
+                    font: game_assets.font.clone(),
+                    font_size: 24.0,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+/// Ends the paused practice run when the quit key is pressed
+fn quit_practice(keyboard_input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keyboard_input.just_pressed(QUIT_KEY) {
+        info!("Practice run left");
+        next_state.set(GameState::Menu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_wrap_around_at_both_ends() {
+        let mut settings = PracticeSettings::default();
+        settings.step_wave(-1);
+        assert_eq!(settings.starting_wave, MAX_PRACTICE_WAVE);
+        settings.step_wave(1);
+        assert_eq!(settings.starting_wave, 1);
+        settings.step_wave(4);
+        assert_eq!(settings.starting_wave, 5);
+
+        settings.step_speed(2);
+        assert_eq!(settings.speed, 1.5);
+        settings.step_speed(1);
+        assert_eq!(settings.speed, 0.5);
+        settings.step_speed(-1);
+        assert_eq!(settings.speed, 1.5);
+    }
+
+    #[test]
+    fn label_names_what_differs_from_a_real_run() {
+        let mut settings = PracticeSettings::default();
+        assert_eq!(practice_label(&settings), "PRACTICE");
+
+        settings.speed = 0.75;
+        settings.infinite_lives = true;
+        settings.enemy_fire = false;
+        assert_eq!(practice_label(&settings), "PRACTICE  0.75x  INFINITE LIVES  NO ENEMY FIRE");
+    }
+
+    #[test]
+    fn only_a_practice_run_changes_how_the_run_plays() {
+        assert_eq!(game_speed(None), 1.0);
+        assert!(!infinite_lives(None));
+
+        let practice = PracticeRun(PracticeSettings {
+            speed: 0.5,
+            infinite_lives: true,
+            ..Default::default()
+        });
+        assert_eq!(game_speed(Some(&practice)), 0.5);
+        assert!(infinite_lives(Some(&practice)));
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use crate::{
+    attract::DemoRun,
+    coop::CoopRun,
+    difficulty::Difficulty,
+    game_assets::{AfterLoading, GameAssets},
+    game_rng::GameRng,
+    game_state::{GameState, PauseState},
+    lives::Lives,
+    name_entry::NameEntry,
+    player_input::{handle_player_input, handle_touch_input, PlayerSpeed, ShipKeys, TouchControls},
+    players::PlayerCount,
+    practice::PracticeRun,
+    wave::{StartingWave, Wave},
+    ColorsPalette, InputSet, PlayerPosition, Score,
+};
+
+/// Folder saved replays go to, next to the game
+const REPLAY_DIR: &str = "replays";
+
+/// Frames between two checksums of the run
+const CHECKSUM_INTERVAL: usize = 60;
+
+/// FNV-1a constants for `checksum`
+const FNV_OFFSET: u32 = 2_166_136_261;
+const FNV_PRIME: u32 = 16_777_619;
+
+/// Recording runs and playing them back. Every frame of a run records the
+/// ship's input along with how long the frame lasted, so playing the frames
+/// back with the same lengths steps the fixed timestep exactly as the
+/// recorded run did. Co-op runs, practice runs and the demo aren't recorded.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        // Every way into a fresh run, the same ones `PlayersPlugin` starts new slots on
+        for state in [GameState::Loading, GameState::Menu, GameState::GameOver] {
+            app.add_systems(
+                OnTransition {
+                    exited: state,
+                    entered: GameState::Playing,
+                },
+                start_run,
+            );
+        }
+
+        app.add_systems(OnEnter(GameState::GameOver), (finish_recording, finish_playback))
+            .add_systems(OnExit(GameState::GameOver), forget_finished_replay)
+            .add_systems(
+                Update,
+                (
+                    record_input.run_if(resource_exists::<ReplayBuffer>),
+                    play_input.run_if(resource_exists::<ReplayPlayback>),
+                )
+                    .after(handle_touch_input)
+                    .before(handle_player_input)
+                    .in_set(InputSet),
+            )
+            .add_systems(
+                Update,
+                // R is a letter while initials are typed in
+                save_replay_on_key.run_if(
+                    in_state(GameState::GameOver)
+                        .and(resource_exists::<FinishedReplay>)
+                        .and(not(resource_exists::<NameEntry>)),
+                ),
+            )
+            .add_systems(
+                Last,
+                (
+                    record_checksum.run_if(resource_exists::<ReplayBuffer>),
+                    advance_playback.run_if(resource_exists::<ReplayPlayback>),
+                ),
+            );
+    }
+}
+
+/// Everything needed to play a run again: how it was set up and the input of
+/// every frame. Saved as RON in a `.replay` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64, // `GameRng` is reseeded with it when the run starts
+    pub difficulty: Difficulty,
+    pub starting_wave: u32,
+    pub players: usize,
+    pub frames: Vec<InputFrame>,
+    pub checksums: Vec<Checksum>,
+}
+
+/// The ship's input during one frame of a run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub delta: u64,  // Nanoseconds of real time the frame lasted, 0 while paused
+    pub move_x: f32, // World units the ship was steered sideways, by keys and touches together
+    pub fire: bool,
+    pub bomb: bool,
+    pub missile: bool,
+    pub paused: bool,
+}
+
+/// Checksum of the run's state at the end of a frame, see `checksum`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Checksum {
+    pub frame: usize,
+    pub value: u32,
+}
+
+impl Replay {
+    /// Reads a replay saved by `save`
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = ron::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    /// Checksum recorded at the end of `frame`, if one was taken there
+    fn checksum_at(&self, frame: usize) -> Option<u32> {
+        self.checksums
+            .binary_search_by_key(&frame, |checksum| checksum.frame)
+            .ok()
+            .map(|index| self.checksums[index].value)
+    }
+}
+
+/// The run being recorded
+#[derive(Resource)]
+pub struct ReplayBuffer(pub Replay);
+
+/// Present while a replay is played back instead of taking the player's input
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    frame: usize,   // Index of the frame being played
+    started: bool,  // Whether the recorded run has started yet
+    diverged: bool, // Whether a checksum already failed to match
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayback {
+            replay,
+            frame: 0,
+            started: false,
+            diverged: false,
+        }
+    }
+
+    /// Input of the frame being played, `None` before the run starts
+    pub fn current(&self) -> Option<&InputFrame> {
+        if self.started {
+            self.replay.frames.get(self.frame)
+        } else {
+            None
+        }
+    }
+}
+
+/// The replay of the run that just ended, until the game over screen is left
+#[derive(Resource)]
+struct FinishedReplay(Replay);
+
+/// Tells the player how to save the replay, and where it went once saved
+#[derive(Component)]
+struct ReplayPrompt;
+
+/// Sets `app` up to play back `replay` instead of a run of the player's own:
+/// the run starts right after loading, with the recorded settings. Called
+/// after `GalagaPlugin` was added, so nothing overrides them.
+pub fn play_back(app: &mut App, replay: Replay) {
+    let first_delta = replay.frames.first().map_or(0, |frame| frame.delta);
+
+    app.insert_resource(replay.difficulty)
+        .insert_resource(StartingWave(replay.starting_wave))
+        .insert_resource(PlayerCount(replay.players))
+        .insert_resource(AfterLoading(GameState::Playing))
+        // Loading takes as long as the first recorded frame, every frame after gets its own length
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_nanos(first_delta)))
+        .insert_resource(ReplayPlayback::new(replay));
+}
+
+/// FNV-1a hash of the score, lives, wave and where the ship is. Taken every
+/// `CHECKSUM_INTERVAL` frames while recording and compared while playing back.
+fn checksum(score: u32, lives: u32, wave: u32, player_x: f32) -> u32 {
+    [score, lives, wave, player_x.to_bits()]
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+        .fold(FNV_OFFSET, |hash, byte| (hash ^ byte as u32).wrapping_mul(FNV_PRIME))
+}
+
+/// Reseeds `GameRng` as a run starts, so the run only depends on the seed and
+/// not on how long the menu was up, and starts recording it. A replay reseeds
+/// with its recorded seed and starts playing instead.
+#[allow(clippy::too_many_arguments)]
+fn start_run(
+    mut commands: Commands,
+    mut game_rng: ResMut<GameRng>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    demo: Option<Res<DemoRun>>,
+    coop_run: Option<Res<CoopRun>>,
+    practice: Option<Res<PracticeRun>>,
+    difficulty: Res<Difficulty>,
+    starting_wave: Res<StartingWave>,
+    player_count: Res<PlayerCount>,
+) {
+    // Time the menu left over in the fixed timestep is dropped, so the first
+    // step of the run comes at the same point in a replay
+    let overstep = fixed_time.overstep();
+    fixed_time.discard_overstep(overstep);
+
+    if let Some(mut playback) = playback {
+        *game_rng = GameRng::from_seed(playback.replay.seed);
+        playback.started = true;
+        info!(seed = playback.replay.seed, frames = playback.replay.frames.len(), "Playing back replay");
+        return;
+    }
+
+    let seed = game_rng.next_u64();
+    *game_rng = GameRng::from_seed(seed);
+
+    if demo.is_some() || coop_run.is_some() || practice.is_some() {
+        commands.remove_resource::<ReplayBuffer>();
+        return;
+    }
+
+    commands.insert_resource(ReplayBuffer(Replay {
+        seed,
+        difficulty: *difficulty,
+        starting_wave: starting_wave.0,
+        players: player_count.0,
+        frames: Vec::new(),
+        checksums: Vec::new(),
+    }));
+}
+
+/// Records this frame's input, summed up the way `handle_player_input` applies it
+fn record_input(
+    mut buffer: ResMut<ReplayBuffer>,
+    ship_keys: ShipKeys,
+    touch_controls: Res<TouchControls>,
+    player_speed: Res<PlayerSpeed>,
+    pause_state: Option<Res<State<PauseState>>>,
+    time: Res<Time>,
+    real_time: Res<Time<Real>>,
+) {
+    let mut axis: f32 = 0.0;
+    if ship_keys.left() {
+        axis -= 1.0;
+    }
+    if ship_keys.right() {
+        axis += 1.0;
+    }
+
+    buffer.0.frames.push(InputFrame {
+        // Real time, as playback feeds it to the clock and a death sequence slows game time down again
+        delta: if time.delta().is_zero() { 0 } else { real_time.delta().as_nanos() as u64 },
+        // Same sum as in `handle_player_input`, so playing it back moves the ship to the same spot
+        move_x: axis * (player_speed.0 * time.delta_secs()) + touch_controls.move_x,
+        fire: ship_keys.fire() || touch_controls.fire,
+        bomb: ship_keys.bomb(),
+        missile: ship_keys.missile(),
+        paused: pause_state.is_some_and(|state| *state.get() == PauseState::Paused),
+    });
+}
+
+/// Hands the recorded movement and fire to `handle_player_input` the way a
+/// touch would. `ShipKeys` stands in for the keys.
+fn play_input(playback: Res<ReplayPlayback>, mut touch_controls: ResMut<TouchControls>) {
+    if let Some(frame) = playback.current() {
+        touch_controls.move_x = frame.move_x;
+        touch_controls.fire = frame.fire;
+    }
+}
+
+/// Takes a checksum every `CHECKSUM_INTERVAL` frames of the recording
+fn record_checksum(
+    mut buffer: ResMut<ReplayBuffer>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    wave: Res<Wave>,
+    player_position: Res<PlayerPosition>,
+) {
+    let Some(frame) = buffer.0.frames.len().checked_sub(1) else {
+        return;
+    };
+
+    if frame % CHECKSUM_INTERVAL == 0 {
+        let value = checksum(score.0, lives.0, wave.0, player_position.0.x);
+        buffer.0.checksums.push(Checksum { frame, value });
+    }
+}
+
+/// Compares the frame just played against the recorded checksum, warning the
+/// first time they differ, and lines up the length and pause state of the next frame
+#[allow(clippy::too_many_arguments)]
+fn advance_playback(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    wave: Res<Wave>,
+    player_position: Res<PlayerPosition>,
+    pause_state: Option<Res<State<PauseState>>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+) {
+    if !playback.started {
+        return;
+    }
+
+    let frame = playback.frame;
+    if let Some(expected) = playback.replay.checksum_at(frame) {
+        let value = checksum(score.0, lives.0, wave.0, player_position.0.x);
+        if value != expected && !playback.diverged {
+            warn!(
+                frame,
+                score = score.0,
+                "Replay diverged from the recorded run, the game is no longer deterministic"
+            );
+            playback.diverged = true;
+        }
+    }
+
+    playback.frame += 1;
+    let Some(next) = playback.replay.frames.get(playback.frame).copied() else {
+        warn!(frames = playback.frame, "Replay ran out of input before the run ended");
+        stop_playback(&mut commands, &mut time_update);
+        return;
+    };
+
+    *time_update = TimeUpdateStrategy::ManualDuration(Duration::from_nanos(next.delta));
+
+    // Set a frame ahead, state changes only apply at the start of the next one
+    if let Some(pause_state) = pause_state {
+        let next_state = if next.paused { PauseState::Paused } else { PauseState::Running };
+        if *pause_state.get() != next_state {
+            next_pause_state.set(next_state);
+        }
+    }
+}
+
+/// Hands time back to the clock and the ship back to the player
+fn stop_playback(commands: &mut Commands, time_update: &mut TimeUpdateStrategy) {
+    commands.remove_resource::<ReplayPlayback>();
+    *time_update = TimeUpdateStrategy::Automatic;
+}
+
+/// Keeps the recording of the run that just ended and offers to save it
+fn finish_recording(
+    mut commands: Commands,
+    buffer: Option<Res<ReplayBuffer>>,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+) {
+    let Some(buffer) = buffer else {
+        return;
+    };
+
+    commands.insert_resource(FinishedReplay(buffer.0.clone()));
+    commands.remove_resource::<ReplayBuffer>();
+
+    commands
+        .spawn((
+            StateScoped(GameState::GameOver),
+            Node {
+                width: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ReplayPrompt,
+                Text::new("Press R to save the replay"),
+                TextFont {
+                    font: game_assets.font.clone(),
+                    font_size: 24.0,
+                    ..Default::default()
+                },
+                TextColor(color_palette.player_color),
+            ));
+        });
+}
+
+/// The end of a played back run is the end of the replay
+fn finish_playback(
+    mut commands: Commands,
+    playback: Option<Res<ReplayPlayback>>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+) {
+    let Some(playback) = playback else {
+        return;
+    };
+
+    info!(frames = playback.frame + 1, diverged = playback.diverged, "Replay finished");
+    stop_playback(&mut commands, &mut time_update);
+}
+
+fn forget_finished_replay(mut commands: Commands) {
+    commands.remove_resource::<FinishedReplay>();
+}
+
+/// Where the replay of a run started from `seed` is saved
+fn replay_path(seed: u64) -> PathBuf {
+    Path::new(REPLAY_DIR).join(format!("run-{}.replay", seed))
+}
+
+/// Saves the replay of the run that just ended when R is pressed on the game over screen
+fn save_replay_on_key(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    finished: Res<FinishedReplay>,
+    mut prompt_query: Query<&mut Text, With<ReplayPrompt>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+
+    let path = replay_path(finished.0.seed);
+    let message = match finished.0.save(&path) {
+        Ok(()) => {
+            info!(path = %path.display(), "Replay saved");
+            format!("Replay saved to {} (play it with --replay)", path.display())
+        }
+        Err(error) => {
+            warn!("Could not save replay to {}: {}", path.display(), error);
+            "Could not save the replay".to_string()
+        }
+    };
+
+    for mut text in prompt_query.iter_mut() {
+        text.0 = message.clone();
+    }
+    // Saved once, pressing R again does nothing
+    commands.remove_resource::<FinishedReplay>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybindings::KeyBindings;
+    use bevy::ecs::system::SystemState;
+
+    fn replay(frames: Vec<InputFrame>) -> Replay {
+        Replay {
+            seed: 42,
+            difficulty: Difficulty::Hard,
+            starting_wave: 3,
+            players: 1,
+            frames,
+            checksums: vec![Checksum { frame: 0, value: checksum(100, 3, 3, 0.0) }],
+        }
+    }
+
+    #[test]
+    fn replay_survives_a_round_trip_through_ron() {
+        let replay = replay(vec![
+            InputFrame {
+                delta: 16_666_667,
+                move_x: -6.666_667,
+                fire: true,
+                ..Default::default()
+            },
+            InputFrame {
+                delta: 0,
+                paused: true,
+                ..Default::default()
+            },
+        ]);
+
+        let text = ron::to_string(&replay).unwrap();
+        assert_eq!(ron::from_str::<Replay>(&text).unwrap(), replay);
+        assert_eq!(replay.checksum_at(0), Some(checksum(100, 3, 3, 0.0)));
+        assert_eq!(replay.checksum_at(1), None);
+    }
+
+    #[test]
+    fn checksum_changes_with_the_score() {
+        assert_ne!(checksum(100, 3, 1, 0.0), checksum(110, 3, 1, 0.0));
+        assert_eq!(checksum(100, 3, 1, 0.0), checksum(100, 3, 1, 0.0));
+    }
+
+    fn input_app() -> App {
+        let mut keyboard_input = ButtonInput::<KeyCode>::default();
+        keyboard_input.press(KeyCode::ArrowRight);
+        keyboard_input.press(KeyCode::Space);
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .insert_resource(keyboard_input)
+            .init_resource::<KeyBindings>()
+            .init_resource::<TouchControls>()
+            .insert_resource(PlayerSpeed(300.0));
+        app
+    }
+
+    #[test]
+    fn recorded_input_comes_back_as_touches_with_the_keys_ignored() {
+        let mut app = input_app();
+        app.insert_resource(ReplayBuffer(replay(Vec::new())))
+            .add_systems(Update, record_input);
+        app.update();
+        app.update();
+
+        let frames = app.world().resource::<ReplayBuffer>().0.frames.clone();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].delta, 100_000_000);
+        assert!((frames[1].move_x - 30.0).abs() < 1e-3);
+        assert!(frames[1].fire);
+
+        let mut playback = ReplayPlayback::new(replay(frames.clone()));
+        playback.started = true;
+        playback.frame = 1;
+        let mut app = input_app();
+        app.insert_resource(playback).add_systems(Update, play_input);
+        app.update();
+
+        let touch_controls = app.world().resource::<TouchControls>();
+        assert_eq!(touch_controls.move_x, frames[1].move_x);
+        assert!(touch_controls.fire);
+
+        let mut ship_keys = SystemState::<ShipKeys>::new(app.world_mut());
+        let ship_keys = ship_keys.get(app.world());
+        assert!(!ship_keys.right() && !ship_keys.fire());
+    }
+}
@@ -0,0 +1,246 @@
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+use crate::{config::GameConfig, coop::CoopPlayer, OutlineContainer, PlayField, Player};
+
+/// Seconds the window has to keep its size before the play field is fitted to it
+const RESIZE_DEBOUNCE: f32 = 0.25;
+
+/// Fitting the play field to the window as it is resized. A window narrower
+/// than the configured field narrows the field with it, down to
+/// `MIN_PLAY_FIELD_WIDTH`; a wider one keeps the configured field between
+/// bars. Dragging a window edge sends a resize every frame, so the field is
+/// only refitted once the size has settled for `RESIZE_DEBOUNCE`.
+pub struct ResizePlugin;
+
+impl Plugin for ResizePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FieldInsets>()
+            .add_systems(Startup, fit_initial_window)
+            .add_systems(
+                Update,
+                (
+                    debounce_resize,
+                    apply_resize.run_if(resource_exists::<PendingResize>),
+                    anchor_to_play_field,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Latest window size not yet fitted to, waiting for resizing to settle
+#[derive(Resource, Debug)]
+pub struct PendingResize {
+    size: Vec2, // Logical size of the window
+    timer: Timer,
+}
+
+impl PendingResize {
+    fn new(size: Vec2) -> Self {
+        PendingResize {
+            size,
+            timer: Timer::from_seconds(RESIZE_DEBOUNCE, TimerMode::Once),
+        }
+    }
+}
+
+/// Width of the bars beside and above the play field, in logical pixels, as of
+/// the last fitted window size. Zero while the field fills the window.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct FieldInsets(pub Vec2);
+
+/// Keeps a UI node against the edges of the play field instead of the window.
+/// Each of its `left`, `right`, `top` and `bottom` that is set is moved to
+/// `margin` inside the matching edge of the field.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FieldAnchor {
+    pub margin: f32,
+}
+
+/// Fits the field to the size the window opened with, like to any later size
+fn fit_initial_window(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
+    if let Ok(window) = window_query.get_single() {
+        commands.insert_resource(PendingResize::new(window.size()));
+    }
+}
+
+/// Remembers the latest size the primary window was resized to, restarting
+/// the wait for it to settle. Other windows don't show the play field.
+fn debounce_resize(
+    mut commands: Commands,
+    mut resized_events: EventReader<WindowResized>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    pending: Option<ResMut<PendingResize>>,
+) {
+    let Ok(primary_window) = window_query.get_single() else {
+        resized_events.clear();
+        return;
+    };
+    let primary_resizes = resized_events.read().filter(|resized| resized.window == primary_window);
+    let Some(resized) = primary_resizes.last() else {
+        return;
+    };
+    let size = Vec2::new(resized.width, resized.height);
+
+    match pending {
+        Some(mut pending) => {
+            pending.size = size;
+            pending.timer.reset();
+        }
+        None => commands.insert_resource(PendingResize::new(size)),
+    }
+}
+
+/// Once the window size has settled, fits the play field and its container to
+/// it and moves any ship the narrower field left outside back in. Real time
+/// is used, so resizing a paused game takes effect right away too.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn apply_resize(
+    mut commands: Commands,
+    mut pending: ResMut<PendingResize>,
+    config: Res<GameConfig>,
+    mut play_field: ResMut<PlayField>,
+    mut insets: ResMut<FieldInsets>,
+    mut container_query: Query<(&mut OutlineContainer, &mut Transform)>,
+    mut player_query: Query<
+        &mut Transform,
+        (Or<(With<Player>, With<CoopPlayer>)>, Without<OutlineContainer>),
+    >,
+    time: Res<Time<Real>>,
+) {
+    if !pending.timer.tick(time.delta()).finished() {
+        return;
+    }
+    commands.remove_resource::<PendingResize>();
+
+    let fitted = PlayField::fitting(config.play_field, pending.size);
+    if play_field.set_if_neq(fitted) {
+        info!(width = fitted.width, height = fitted.height, "Play field fitted to the window");
+    }
+    insets.set_if_neq(FieldInsets(fitted.screen_insets(pending.size)));
+
+    for (mut container, mut transform) in container_query.iter_mut() {
+        container.width = fitted.width;
+        container.height = fitted.height;
+        transform.scale = Vec3::new(fitted.width, fitted.height, 1.0);
+    }
+
+    for mut transform in player_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let clamped = fitted.clamp(position, transform.scale.truncate());
+        if clamped != position {
+            transform.translation = clamped.extend(transform.translation.z);
+        }
+    }
+}
+
+/// Moves anchored UI nodes to the edges of the play field when the bars
+/// around it change, and new ones as soon as they are spawned
+fn anchor_to_play_field(insets: Res<FieldInsets>, mut anchor_query: Query<(Ref<FieldAnchor>, &mut Node)>) {
+    for (anchor, mut node) in anchor_query.iter_mut() {
+        if !insets.is_changed() && !anchor.is_added() {
+            continue;
+        }
+
+        let inset = insets.0 + Vec2::splat(anchor.margin);
+        let node = &mut *node;
+        for (edge, inset) in [
+            (&mut node.left, inset.x),
+            (&mut node.right, inset.x),
+            (&mut node.top, inset.y),
+            (&mut node.bottom, inset.y),
+        ] {
+            if *edge != Val::Auto {
+                *edge = Val::Px(inset);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn settled_resize_narrows_the_field_and_pulls_the_ship_back_in() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .init_resource::<GameConfig>()
+            .init_resource::<PlayField>()
+            .add_event::<WindowResized>()
+            .add_plugins(ResizePlugin);
+        let window = app.world_mut().spawn(PrimaryWindow).id();
+        let other_window = app.world_mut().spawn_empty().id();
+        let container = app
+            .world_mut()
+            .spawn((
+                OutlineContainer {
+                    width: 1200.0,
+                    height: 800.0,
+                },
+                Transform::from_scale(Vec3::new(1200.0, 800.0, 1.0)),
+            ))
+            .id();
+        let player = app
+            .world_mut()
+            .spawn((Player, Transform::from_xyz(500.0, -250.0, 0.0).with_scale(Vec3::splat(50.0))))
+            .id();
+        let hud = app
+            .world_mut()
+            .spawn((
+                FieldAnchor { margin: 10.0 },
+                Node {
+                    left: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let resize = |app: &mut App, window: Entity, width: f32, height: f32| {
+            app.world_mut().send_event(WindowResized { window, width, height });
+        };
+
+        // Dragged through a few sizes, none of them fitted while the drag goes on
+        resize(&mut app, window, 900.0, 800.0);
+        app.update();
+        resize(&mut app, window, 600.0, 800.0);
+        app.update();
+        app.update();
+        assert_eq!(*app.world().resource::<PlayField>(), PlayField::default());
+
+        for _ in 0..3 {
+            app.update();
+        }
+        let fitted = PlayField {
+            width: 600.0,
+            height: 800.0,
+        };
+        assert_eq!(*app.world().resource::<PlayField>(), fitted);
+        assert_eq!(app.world().get::<OutlineContainer>(container).unwrap().width, 600.0);
+        assert_eq!(app.world().get::<Transform>(container).unwrap().scale, Vec3::new(600.0, 800.0, 1.0));
+        assert_eq!(app.world().get::<Transform>(player).unwrap().translation, Vec3::new(275.0, -250.0, 0.0));
+        assert_eq!(app.world().get::<Node>(hud).unwrap().left, Val::Px(10.0));
+
+        // Another window being resized leaves the field alone
+        resize(&mut app, other_window, 1600.0, 800.0);
+        for _ in 0..4 {
+            app.update();
+        }
+        assert_eq!(*app.world().resource::<PlayField>(), fitted);
+
+        // Wider than the configured field, which then stays as it is between bars
+        resize(&mut app, window, 1600.0, 800.0);
+        for _ in 0..4 {
+            app.update();
+        }
+        assert_eq!(*app.world().resource::<PlayField>(), PlayField::default());
+        assert_eq!(app.world().resource::<FieldInsets>().0, Vec2::new(200.0, 0.0));
+        let node = app.world().get::<Node>(hud).unwrap();
+        assert_eq!((node.left, node.top, node.right), (Val::Px(210.0), Val::Px(10.0), Val::Auto));
+    }
+}
@@ -0,0 +1,318 @@
+use bevy::{audio::Volume, prelude::*};
+use crate::{
+    attract::DemoRun,
+    audio_settings::AudioSettings,
+    explosion::Lifetime,
+    game_assets::GameAssets,
+    game_state::{GameState, PauseState},
+    player_input::{handle_player_input, ShipKeys, TouchControls},
+    players::CurrentPlayer,
+    wave::{spawn_banner, wave_banner_text, Wave},
+    ColorsPalette, InputSet,
+};
+
+/// Seconds "PLAYER ONE" is shown at the start of a run
+const PLAYER_STEP_DURATION: f32 = 1.5;
+
+/// Seconds "STAGE N" is shown after it
+const STAGE_STEP_DURATION: f32 = 1.5;
+
+/// Seconds "READY" is shown when a new ship comes in
+const READY_STEP_DURATION: f32 = 1.0;
+
+/// The arcade's "PLAYER ONE", "STAGE 1" sequence with the intro jingle at the
+/// start of a run, and a short "READY" whenever a new ship comes in. The ship
+/// is on screen all along but doesn't answer to input, and enemies hold their
+/// attacks. Fire skips it. The background music only starts once the run's
+/// intro is over, see `MusicPlugin`.
+pub struct RunIntroPlugin;
+
+impl Plugin for RunIntroPlugin {
+    fn build(&self, app: &mut App) {
+        // Every way into a fresh run, the same ones `ReplayPlugin` starts recording on
+        for state in [GameState::Loading, GameState::Menu, GameState::GameOver] {
+            app.add_systems(
+                OnTransition {
+                    exited: state,
+                    entered: GameState::Playing,
+                },
+                start_run_intro,
+            );
+        }
+
+        app.add_systems(OnExit(GameState::Playing), end_run_intro).add_systems(
+            Update,
+            (
+                show_first_step.run_if(resource_added::<RunIntro>),
+                // Fire pressed while the intro is up, never a shot the ship ignored
+                skip_run_intro.in_set(InputSet).after(handle_player_input),
+                advance_run_intro.after(show_first_step).after(skip_run_intro),
+            )
+                .run_if(in_state(PauseState::Running).and(resource_exists::<RunIntro>)),
+        );
+    }
+}
+
+/// One text of the intro
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntroStep {
+    Player,
+    Stage,
+    Ready,
+}
+
+impl IntroStep {
+    /// Seconds the step is shown for
+    fn duration(self) -> f32 {
+        match self {
+            IntroStep::Player => PLAYER_STEP_DURATION,
+            IntroStep::Stage => STAGE_STEP_DURATION,
+            IntroStep::Ready => READY_STEP_DURATION,
+        }
+    }
+
+    /// Step shown once this one is over, if any
+    fn next(self) -> Option<IntroStep> {
+        match self {
+            IntroStep::Player => Some(IntroStep::Stage),
+            IntroStep::Stage | IntroStep::Ready => None,
+        }
+    }
+
+    /// What the step says for `player` starting on `wave`
+    fn text(self, player: usize, wave: &Wave) -> String {
+        match self {
+            IntroStep::Player => match player {
+                0 => "PLAYER ONE".to_string(),
+                1 => "PLAYER TWO".to_string(),
+                _ => format!("PLAYER {}", player + 1),
+            },
+            IntroStep::Stage => wave_banner_text(wave),
+            IntroStep::Ready => "READY".to_string(),
+        }
+    }
+}
+
+/// Present while the intro plays. Input systems of the ships and enemy attacks
+/// are gated on it being gone.
+#[derive(Resource, Debug)]
+pub struct RunIntro {
+    step: IntroStep,
+    timer: Timer, // Runs for the step shown
+}
+
+impl RunIntro {
+    /// "PLAYER ONE", then "STAGE N", at the start of a run
+    pub fn run_start() -> Self {
+        RunIntro::at(IntroStep::Player)
+    }
+
+    /// Just "READY", when a new ship comes in during a run
+    pub fn ready() -> Self {
+        RunIntro::at(IntroStep::Ready)
+    }
+
+    fn at(step: IntroStep) -> Self {
+        RunIntro {
+            step,
+            timer: Timer::from_seconds(step.duration(), TimerMode::Once),
+        }
+    }
+
+    /// Whether the intro names the stage, so the wave doesn't show its own banner
+    pub fn announces_stage(&self) -> bool {
+        matches!(self.step, IntroStep::Player | IntroStep::Stage)
+    }
+}
+
+/// Marks the text of the intro step shown
+#[derive(Component)]
+struct IntroText;
+
+/// Marks the player of the intro jingle
+#[derive(Component)]
+struct IntroJingle;
+
+/// Starts the full intro and its jingle on a fresh run. The demo starts
+/// straight away, as in the arcade.
+fn start_run_intro(
+    mut commands: Commands,
+    demo: Option<Res<DemoRun>>,
+    game_assets: Res<GameAssets>,
+    audio_settings: Res<AudioSettings>,
+) {
+    if demo.is_some() {
+        return;
+    }
+
+    commands.insert_resource(RunIntro::run_start());
+    commands.spawn((
+        IntroJingle,
+        StateScoped(GameState::Playing),
+        AudioPlayer::new(game_assets.intro_jingle.clone()),
+        PlaybackSettings::DESPAWN.with_volume(Volume::new(audio_settings.music_volume())),
+    ));
+}
+
+/// Shows `step` across the middle of the screen. The intro takes it down
+/// itself when the step ends, whether on time or skipped.
+fn spawn_step_text(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    color_palette: &ColorsPalette,
+    step: IntroStep,
+    player: usize,
+    wave: &Wave,
+) {
+    let color = match step {
+        IntroStep::Stage => color_palette.bee_color,
+        IntroStep::Player | IntroStep::Ready => color_palette.player_color,
+    };
+    let text = spawn_banner(commands, game_assets, &step.text(player, wave), color, step.duration());
+    commands.entity(text).insert(IntroText).remove::<Lifetime>();
+}
+
+/// Shows the first step once an intro starts, at the start of a run or on a respawn
+fn show_first_step(
+    mut commands: Commands,
+    run_intro: Res<RunIntro>,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    current: Res<CurrentPlayer>,
+    wave: Res<Wave>,
+) {
+    spawn_step_text(&mut commands, &game_assets, &color_palette, run_intro.step, current.0, &wave);
+}
+
+/// Moves on to the next step when the one shown is over, ending the intro after the last
+#[allow(clippy::too_many_arguments)]
+fn advance_run_intro(
+    mut commands: Commands,
+    mut run_intro: ResMut<RunIntro>,
+    text_query: Query<Entity, With<IntroText>>,
+    jingle_query: Query<Entity, With<IntroJingle>>,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    current: Res<CurrentPlayer>,
+    wave: Res<Wave>,
+    time: Res<Time>,
+) {
+    if !run_intro.timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    let Some(next) = run_intro.step.next() else {
+        finish_run_intro(&mut commands, &text_query, &jingle_query);
+        return;
+    };
+
+    *run_intro = RunIntro::at(next);
+    for text in text_query.iter() {
+        commands.entity(text).despawn_recursive();
+    }
+    spawn_step_text(&mut commands, &game_assets, &color_palette, next, current.0, &wave);
+}
+
+/// Cuts the intro short when fire is pressed or tapped
+fn skip_run_intro(
+    mut commands: Commands,
+    ship_keys: ShipKeys,
+    touch_controls: Res<TouchControls>,
+    text_query: Query<Entity, With<IntroText>>,
+    jingle_query: Query<Entity, With<IntroJingle>>,
+) {
+    if ship_keys.fire() || touch_controls.fire {
+        info!("Run intro skipped");
+        finish_run_intro(&mut commands, &text_query, &jingle_query);
+    }
+}
+
+/// Clears the intro away along with its text and jingle, handing over to play
+fn finish_run_intro(
+    commands: &mut Commands,
+    text_query: &Query<Entity, With<IntroText>>,
+    jingle_query: &Query<Entity, With<IntroJingle>>,
+) {
+    commands.remove_resource::<RunIntro>();
+    for entity in text_query.iter().chain(jingle_query.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Drops an intro still running when the run is left
+fn end_run_intro(mut commands: Commands) {
+    commands.remove_resource::<RunIntro>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybindings::KeyBindings;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    fn intro_app(run_intro: RunIntro) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(0.5)))
+            .init_resource::<GameAssets>()
+            .init_resource::<ColorsPalette>()
+            .init_resource::<CurrentPlayer>()
+            .insert_resource(Wave(1))
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<TouchControls>()
+            .insert_resource(run_intro)
+            .add_systems(
+                Update,
+                (
+                    show_first_step.run_if(resource_added::<RunIntro>),
+                    skip_run_intro,
+                    advance_run_intro.after(show_first_step).after(skip_run_intro),
+                )
+                    .run_if(resource_exists::<RunIntro>),
+            );
+        app
+    }
+
+    fn shown(app: &mut App) -> Vec<String> {
+        app.world_mut()
+            .query_filtered::<&Text, With<IntroText>>()
+            .iter(app.world())
+            .map(|text| text.0.clone())
+            .collect()
+    }
+
+    #[test]
+    fn run_start_names_the_player_then_the_stage() {
+        let mut app = intro_app(RunIntro::run_start());
+        app.update();
+        assert_eq!(shown(&mut app), vec!["PLAYER ONE"]);
+
+        // 1.5 seconds in steps of 0.5, after the first update without elapsed time
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(shown(&mut app), vec!["STAGE 1"]);
+        assert!(app.world().resource::<RunIntro>().announces_stage());
+
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(!app.world().contains_resource::<RunIntro>());
+        assert!(shown(&mut app).is_empty());
+    }
+
+    #[test]
+    fn fire_skips_the_intro() {
+        let mut app = intro_app(RunIntro::ready());
+        app.update();
+        assert_eq!(shown(&mut app), vec!["READY"]);
+        assert!(!app.world().resource::<RunIntro>().announces_stage());
+
+        app.world_mut().resource_mut::<TouchControls>().fire = true;
+        app.update();
+        assert!(!app.world().contains_resource::<RunIntro>());
+        assert!(shown(&mut app).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use crate::{
+    explosion::{despawn_expired, Lifetime, Particle},
+    game_assets::GameAssets,
+    game_state::GameState,
+    layer,
+    ColorsPalette, EnemyKilled,
+};
+
+/// Seconds a popup stays on screen while fading out
+const POPUP_LIFETIME: f32 = 0.8;
+
+/// Speed popups float upward at, in units per second
+const POPUP_RISE_SPEED: f32 = 60.0;
+
+/// Popups are drawn with the effects, above the sprites but below the pause overlay
+const POPUP_Z: f32 = layer::EFFECTS;
+
+/// Vertical gap between popups of kills made in the same frame
+const POPUP_STACK_SPACING: f32 = 18.0;
+
+/// Point values floating up from destroyed enemies
+pub struct ScorePopupPlugin;
+
+impl Plugin for ScorePopupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (spawn_score_popups, fade_score_popups.after(despawn_expired)));
+    }
+}
+
+/// Floating text showing the points an enemy was worth
+#[derive(Component)]
+pub struct ScorePopup;
+
+/// Shows the points of every enemy destroyed this frame where it died. Kills
+/// in the same frame are stacked so their texts don't overlap, and every
+/// popup sits on its own depth so none of them flicker over another.
+fn spawn_score_popups(
+    mut commands: Commands,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+    mut popups_spawned: Local<u32>,
+) {
+    for (index, event) in enemy_killed_events.read().enumerate() {
+        let depth = (*popups_spawned % 100) as f32 * 0.01;
+        *popups_spawned = popups_spawned.wrapping_add(1);
+        let offset = Vec3::new(0.0, index as f32 * POPUP_STACK_SPACING, POPUP_Z + depth);
+
+        commands.spawn((
+            ScorePopup,
+            Particle {
+                velocity: Vec3::new(0.0, POPUP_RISE_SPEED, 0.0),
+            },
+            Lifetime(Timer::from_seconds(POPUP_LIFETIME, TimerMode::Once)),
+            StateScoped(GameState::Playing),
+            Text2d::new(event.points.to_string()),
+            TextFont {
+                font: game_assets.font.clone(),
+                font_size: 16.0,
+                ..Default::default()
+            },
+            TextColor(event.kind.color(&color_palette)),
+            Transform::from_translation(event.position.with_z(0.0) + offset),
+        ));
+    }
+}
+
+/// Fades popups out over their lifetime
+fn fade_score_popups(mut popup_query: Query<(&Lifetime, &mut TextColor), With<ScorePopup>>) {
+    for (lifetime, mut text_color) in popup_query.iter_mut() {
+        text_color.0.set_alpha(1.0 - lifetime.0.fraction());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fly_logic::EnemyKind;
+
+    #[test]
+    fn simultaneous_kills_get_separate_popups() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameAssets>()
+            .init_resource::<ColorsPalette>()
+            .add_event::<EnemyKilled>()
+            .add_systems(Update, spawn_score_popups);
+
+        for kind in [EnemyKind::Bee, EnemyKind::Boss] {
+            app.world_mut().send_event(EnemyKilled {
+                entity: Entity::PLACEHOLDER,
+                points: kind.points(),
+                position: Vec3::new(0.0, 100.0, 0.0),
+                kind,
+                player: 0,
+            });
+        }
+
+        app.update();
+
+        let mut popups: Vec<(String, Vec3)> = app
+            .world_mut()
+            .query_filtered::<(&Text2d, &Transform), With<ScorePopup>>()
+            .iter(app.world())
+            .map(|(text, transform)| (text.0.clone(), transform.translation))
+            .collect();
+        popups.sort_by(|a, b| a.1.y.total_cmp(&b.1.y));
+
+        assert_eq!(popups.len(), 2);
+        assert_eq!(popups[0].0, EnemyKind::Bee.points().to_string());
+        assert_eq!(popups[1].0, EnemyKind::Boss.points().to_string());
+        assert_ne!(popups[0].1.y, popups[1].1.y);
+        assert_ne!(popups[0].1.z, popups[1].1.z);
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use crate::{
+    fly_logic::EnemyKind,
+    game_rng::GameRng,
+    game_state::{GameState, PauseState},
+    EnemyKilled, PlayerHit,
+};
+
+/// Shake added when the player ship is hit
+const PLAYER_HIT_SHAKE: f32 = 14.0;
+
+/// Shake added when a boss is destroyed
+const BOSS_KILL_SHAKE: f32 = 5.0;
+
+/// Shake added when the mothership is destroyed
+const MOTHERSHIP_KILL_SHAKE: f32 = 16.0;
+
+/// Shake lost per second, so even the strongest shake settles in well under a second
+const SHAKE_DECAY: f32 = 30.0;
+
+/// Strongest shake allowed, however many hits pile up
+const MAX_SHAKE: f32 = 20.0;
+
+/// Camera shake on heavy impacts: a strong one when the ship is hit or the
+/// mothership goes down, a mild one when a boss does
+pub struct ScreenShakePlugin;
+
+impl Plugin for ScreenShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenShake>()
+            .add_systems(OnExit(GameState::Playing), stop_shake)
+            .add_systems(
+                Update,
+                (add_shake, shake_camera.after(add_shake)).run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// How hard the camera is shaking. The jitter is up to `intensity` units in
+/// each direction and shrinks by `decay` units per second.
+#[derive(Resource)]
+pub struct ScreenShake {
+    pub intensity: f32,
+    pub decay: f32,
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        ScreenShake {
+            intensity: 0.0,
+            decay: SHAKE_DECAY,
+        }
+    }
+}
+
+impl ScreenShake {
+    /// Adds `amount` to the current shake, up to `MAX_SHAKE`
+    pub fn add(&mut self, amount: f32) {
+        self.intensity = (self.intensity + amount).min(MAX_SHAKE);
+    }
+}
+
+/// Starts shakes for this frame's player hits and boss and mothership kills
+fn add_shake(
+    mut player_hit_events: EventReader<PlayerHit>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut shake: ResMut<ScreenShake>,
+) {
+    if player_hit_events.read().count() > 0 {
+        shake.add(PLAYER_HIT_SHAKE);
+    }
+
+    for event in enemy_killed_events.read() {
+        match event.kind {
+            EnemyKind::Boss => shake.add(BOSS_KILL_SHAKE),
+            EnemyKind::Mothership => shake.add(MOTHERSHIP_KILL_SHAKE),
+            _ => {}
+        }
+    }
+}
+
+/// Jitters the camera around the origin while the shake lasts and puts it
+/// back exactly on the origin once the shake has died down. The jitter has its
+/// own generator, so shaking never shifts the gameplay randomness of a seeded run.
+fn shake_camera(
+    mut shake: ResMut<ScreenShake>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+    game_rng: Res<GameRng>,
+    mut jitter_rng: Local<Option<StdRng>>,
+    time: Res<Time>,
+) {
+    let Ok(mut transform) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    if shake.intensity <= 0.0 {
+        if transform.translation.truncate() != Vec2::ZERO {
+            transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+        }
+        return;
+    }
+
+    let rng = jitter_rng.get_or_insert_with(|| StdRng::seed_from_u64(game_rng.seed()));
+    let jitter = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * shake.intensity;
+    transform.translation = jitter.extend(transform.translation.z);
+
+    shake.intensity = (shake.intensity - shake.decay * time.delta_secs()).max(0.0);
+}
+
+/// Ends any shake and recenters the camera for the menu and game over screens
+fn stop_shake(mut shake: ResMut<ScreenShake>, mut camera_query: Query<&mut Transform, With<Camera2d>>) {
+    shake.intensity = 0.0;
+    for mut transform in camera_query.iter_mut() {
+        transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn player_hits_shake_harder_than_boss_kills() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ScreenShake>()
+            .add_event::<PlayerHit>()
+            .add_event::<EnemyKilled>()
+            .add_systems(Update, add_shake);
+
+        let kill = |app: &mut App, kind: EnemyKind| {
+            app.world_mut().send_event(EnemyKilled {
+                entity: Entity::PLACEHOLDER,
+                points: kind.points(),
+                position: Vec3::ZERO,
+                kind,
+                player: 0,
+            });
+            app.update();
+            std::mem::take(&mut app.world_mut().resource_mut::<ScreenShake>().intensity)
+        };
+        assert_eq!(kill(&mut app, EnemyKind::Bee), 0.0);
+        let boss_shake = kill(&mut app, EnemyKind::Boss);
+
+        app.world_mut().send_event(PlayerHit {
+            cause: Entity::PLACEHOLDER,
+            ship: Entity::PLACEHOLDER,
+        });
+        app.update();
+        let hit_shake = app.world().resource::<ScreenShake>().intensity;
+
+        assert!(boss_shake > 0.0);
+        assert!(hit_shake > boss_shake);
+    }
+
+    #[test]
+    fn camera_returns_exactly_to_the_origin() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .insert_resource(GameRng::from_seed(7))
+            .insert_resource(ScreenShake {
+                intensity: PLAYER_HIT_SHAKE,
+                ..Default::default()
+            })
+            .add_systems(Update, shake_camera);
+        let camera = app.world_mut().spawn((Camera2d, Transform::default())).id();
+
+        app.update();
+        app.update();
+        assert_ne!(app.world().get::<Transform>(camera).unwrap().translation, Vec3::ZERO);
+
+        for _ in 0..20 {
+            app.update();
+        }
+
+        assert_eq!(app.world().resource::<ScreenShake>().intensity, 0.0);
+        assert_eq!(app.world().get::<Transform>(camera).unwrap().translation, Vec3::ZERO);
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::{audio::Volume, prelude::*, utils::HashSet};
+use crate::{
+    audio_settings::AudioSettings, game_assets::GameAssets, game_state::GameState, EnemyKilled,
+};
+
+/// Most copies of one sound that play at once. Starting another one cuts off the oldest.
+const MAX_INSTANCES_PER_SOUND: usize = 4;
+
+/// Sound effects played on gameplay events, from the handles in `GameAssets`
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SfxEvent>().add_systems(
+            Update,
+            (
+                send_kill_sounds.run_if(in_state(GameState::Playing)),
+                play_sfx.after(send_kill_sounds),
+            ),
+        );
+    }
+}
+
+/// Every sound effect of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    Shoot,
+    EnemyDeath,
+    ShieldBreak,
+}
+
+impl Sfx {
+    /// Handle loaded at startup for this sound
+    fn handle(self, game_assets: &GameAssets) -> &Handle<AudioSource> {
+        match self {
+            Sfx::Shoot => &game_assets.shoot_sound,
+            Sfx::EnemyDeath => &game_assets.enemy_death_sound,
+            Sfx::ShieldBreak => &game_assets.shield_break_sound,
+        }
+    }
+}
+
+/// Asks for a sound effect to be played, see `play_sfx`
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SfxEvent(pub Sfx);
+
+/// Marks one-shot sound effect players so volume changes reach them. Holds the
+/// sound and the order it was started in, to know which copy is the oldest.
+#[derive(Component)]
+pub struct SoundEffect {
+    sfx: Sfx,
+    started: u64,
+}
+
+/// Plays the requested sound effects at the current effects volume, each in
+/// a player that removes itself once finished. A sound asked for several times
+/// in one frame plays once, the copies would only add up to a louder one.
+/// Past `MAX_INSTANCES_PER_SOUND` copies of a sound, the oldest ones stop.
+fn play_sfx(
+    mut commands: Commands,
+    mut sfx_events: EventReader<SfxEvent>,
+    playing_query: Query<(Entity, &SoundEffect)>,
+    game_assets: Res<GameAssets>,
+    audio_settings: Res<AudioSettings>,
+    mut next_started: Local<u64>,
+) {
+    let requested: HashSet<Sfx> = sfx_events.read().map(|event| event.0).collect();
+
+    for sfx in requested {
+        let mut playing: Vec<(u64, Entity)> = playing_query
+            .iter()
+            .filter(|(_, effect)| effect.sfx == sfx)
+            .map(|(entity, effect)| (effect.started, entity))
+            .collect();
+        playing.sort_unstable();
+
+        let excess = (playing.len() + 1).saturating_sub(MAX_INSTANCES_PER_SOUND);
+        for (_, entity) in playing.into_iter().take(excess) {
+            commands.entity(entity).despawn();
+        }
+
+        commands.spawn((
+            SoundEffect { sfx, started: *next_started },
+            AudioPlayer::new(sfx.handle(&game_assets).clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(audio_settings.sfx_volume())),
+        ));
+        *next_started += 1;
+    }
+}
+
+/// Asks for the death sound of every enemy destroyed this frame
+fn send_kill_sounds(mut enemy_killed_events: EventReader<EnemyKilled>, mut sfx_events: EventWriter<SfxEvent>) {
+    for _ in enemy_killed_events.read() {
+        sfx_events.send(SfxEvent(Sfx::EnemyDeath));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_copies_stop_past_the_cap() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameAssets>()
+            .insert_resource(AudioSettings::default())
+            .add_event::<SfxEvent>()
+            .add_systems(Update, play_sfx);
+
+        let playing = |app: &mut App, sfx: Sfx| -> Vec<u64> {
+            let mut started: Vec<u64> = app
+                .world_mut()
+                .query::<&SoundEffect>()
+                .iter(app.world())
+                .filter(|effect| effect.sfx == sfx)
+                .map(|effect| effect.started)
+                .collect();
+            started.sort_unstable();
+            started
+        };
+
+        for _ in 0..6 {
+            // Asked for twice, played once
+            app.world_mut().send_event(SfxEvent(Sfx::Shoot));
+            app.world_mut().send_event(SfxEvent(Sfx::Shoot));
+            app.update();
+        }
+        app.world_mut().send_event(SfxEvent(Sfx::ShieldBreak));
+        app.update();
+
+        assert_eq!(playing(&mut app, Sfx::Shoot), vec![2, 3, 4, 5]);
+        assert_eq!(playing(&mut app, Sfx::ShieldBreak), vec![6]);
+    }
+}
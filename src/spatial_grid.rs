@@ -0,0 +1,117 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Side of a grid cell in world units, a little more than a fly
+pub const CELL_SIZE: f32 = 64.0;
+
+/// Uniform grid sorting hit boxes by the cells they overlap, so a hit check
+/// only looks at what is nearby instead of at everything on screen. It is
+/// rebuilt from scratch every frame.
+#[derive(Resource)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<usize>>, // Indices into `entries` of every box touching the cell
+    entries: Vec<Entity>,              // Inserted entities, in insertion order
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        SpatialGrid::new(CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::default(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Forgets every inserted entity
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    /// Adds `entity` to every cell its box of `size`, centered on `position`, overlaps
+    pub fn insert(&mut self, entity: Entity, position: Vec2, size: Vec2) {
+        let index = self.entries.len();
+        self.entries.push(entity);
+
+        for cell in cells_overlapping(self.cell_size, position, size) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+
+    /// Entities sharing at least one cell with a box of `size` centered on
+    /// `position`, each once and in the order they were inserted. These are
+    /// only candidates, their boxes still have to be checked for overlap.
+    pub fn query(&self, position: Vec2, size: Vec2) -> Vec<Entity> {
+        let mut indices: Vec<usize> = cells_overlapping(self.cell_size, position, size)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        indices.into_iter().map(|index| self.entries[index]).collect()
+    }
+}
+
+/// Cells a box of `size` centered on `position` overlaps. A box edge lying on a
+/// cell border counts for the cells on both sides, so boxes that only touch
+/// always share a cell, just as `is_colliding` counts touching as a hit.
+fn cells_overlapping(cell_size: f32, position: Vec2, size: Vec2) -> impl Iterator<Item = IVec2> {
+    // Same arithmetic as `Aabb2d::new`, so the edges match the collision boxes exactly
+    let min = ((position - size / 2.0) / cell_size).floor().as_ivec2();
+    let max = ((position + size / 2.0) / cell_size).floor().as_ivec2();
+
+    (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_on_a_cell_border_is_found_from_both_sides() {
+        let mut grid = SpatialGrid::default();
+        let fly = Entity::from_raw(1);
+        grid.insert(fly, Vec2::new(CELL_SIZE, 0.0), Vec2::splat(10.0));
+
+        let left_cell = Vec2::new(CELL_SIZE / 2.0, 0.0);
+        let right_cell = Vec2::new(CELL_SIZE * 1.5, 0.0);
+        assert_eq!(grid.query(left_cell, Vec2::splat(10.0)), vec![fly]);
+        assert_eq!(grid.query(right_cell, Vec2::splat(10.0)), vec![fly]);
+    }
+
+    #[test]
+    fn boxes_touching_on_a_cell_border_share_a_cell() {
+        let mut grid = SpatialGrid::default();
+        let fly = Entity::from_raw(1);
+        // The fly's right edge lies exactly on the border at x = 0
+        grid.insert(fly, Vec2::new(-25.0, 0.0), Vec2::splat(50.0));
+
+        // A bullet whose left edge lies on the same border
+        assert_eq!(grid.query(Vec2::new(5.0, 0.0), Vec2::splat(10.0)), vec![fly]);
+        // Anywhere past the border's cell it is gone
+        assert!(grid.query(Vec2::new(CELL_SIZE + 10.0, 0.0), Vec2::splat(10.0)).is_empty());
+    }
+
+    #[test]
+    fn query_lists_each_entity_once_in_insertion_order() {
+        let mut grid = SpatialGrid::default();
+        let big = Entity::from_raw(1);
+        let small = Entity::from_raw(2);
+        // Spans four cells around the origin
+        grid.insert(big, Vec2::ZERO, Vec2::splat(CELL_SIZE));
+        grid.insert(small, Vec2::new(-10.0, -10.0), Vec2::splat(5.0));
+
+        assert_eq!(grid.query(Vec2::ZERO, Vec2::splat(CELL_SIZE * 2.0)), vec![big, small]);
+
+        grid.clear();
+        assert!(grid.query(Vec2::ZERO, Vec2::splat(CELL_SIZE * 2.0)).is_empty());
+    }
+}
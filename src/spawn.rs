@@ -0,0 +1,212 @@
+//! The one way game entities come into play. Every ship and fly is spawned
+//! from a `SpawnSpec`, so they all get a fresh id, the layer and marker of
+//! their kind and the same state scoping, whatever else they carry.
+
+use bevy::prelude::*;
+use crate::{
+    game_assets::GameAssets, game_state::GameState, layer, player_input::Banking, ColorsPalette, EntityIdCounter,
+    EntityType, Fly, GameEntity, Player,
+};
+
+/// Size of a game entity in units, unless its spec says otherwise
+pub const DEFAULT_SCALE: f32 = 50.0;
+
+/// What `spawn_game_entity` spawns: the kind, where it goes, how big it is, how
+/// it looks and the components only it carries
+pub struct SpawnSpec<B: Bundle = ()> {
+    pub entity_type: EntityType,
+    pub position: Vec3, // Only x and y are kept, z is the layer of the kind
+    pub scale: Vec3,    // Size on screen, also the size it collides at
+    pub sprite: Sprite,
+    pub extra: B,
+}
+
+impl SpawnSpec {
+    /// A plain entity of `entity_type` at `position`, looking like the rest of its kind
+    pub fn new(
+        entity_type: EntityType,
+        position: Vec3,
+        color_palette: &ColorsPalette,
+        game_assets: &GameAssets,
+    ) -> Self {
+        let (color, image) = match entity_type {
+            EntityType::Player => (color_palette.player_color, &game_assets.player),
+            EntityType::Fly => (color_palette.fly_color, &game_assets.fly),
+            EntityType::Bullet => (color_palette.player_bullet_color, &game_assets.bullet),
+        };
+
+        SpawnSpec {
+            entity_type,
+            position,
+            scale: Vec3::splat(DEFAULT_SCALE),
+            sprite: GameAssets::sprite(image, color),
+            extra: (),
+        }
+    }
+}
+
+impl<B: Bundle> SpawnSpec<B> {
+    pub fn with_scale(self, scale: Vec3) -> Self {
+        SpawnSpec { scale, ..self }
+    }
+
+    pub fn with_sprite(self, sprite: Sprite) -> Self {
+        SpawnSpec { sprite, ..self }
+    }
+
+    /// Adds `extra` to the components the entity is spawned with
+    pub fn with<E: Bundle>(self, extra: E) -> SpawnSpec<(B, E)> {
+        SpawnSpec {
+            entity_type: self.entity_type,
+            position: self.position,
+            scale: self.scale,
+            sprite: self.sprite,
+            extra: (self.extra, extra),
+        }
+    }
+}
+
+/// Layer every entity of `entity_type` is drawn on
+fn layer_of(entity_type: EntityType) -> f32 {
+    match entity_type {
+        EntityType::Player => layer::PLAYER,
+        EntityType::Fly => layer::ENEMIES,
+        EntityType::Bullet => layer::BULLETS,
+    }
+}
+
+/// Spawns the game entity `spec` describes with a fresh id, on the layer of
+/// its kind. Every `GameEntity` but the co-op ship, which must never be a
+/// `Player`, is spawned through here so ids stay unique.
+pub fn spawn_game_entity<B: Bundle>(
+    commands: &mut Commands,
+    entity_ids: &mut EntityIdCounter,
+    spec: SpawnSpec<B>,
+) -> Entity {
+    let SpawnSpec {
+        entity_type,
+        position,
+        scale,
+        sprite,
+        extra,
+    } = spec;
+    let id = entity_ids.next_id();
+    debug!(id, entity_type = ?entity_type, "Spawning game entity");
+
+    let mut entity = commands.spawn((
+        GameEntity { id, entity_type },
+        StateScoped(GameState::Playing),
+        sprite,
+        Transform {
+            translation: position.with_z(layer_of(entity_type)),
+            scale,
+            ..Default::default()
+        },
+        extra,
+    ));
+
+    // Marker components let systems filter on the kind without comparing EntityType
+    match entity_type {
+        EntityType::Player => {
+            entity.insert((Player, Banking::default()));
+        }
+        EntityType::Fly => {
+            entity.insert(Fly);
+        }
+        EntityType::Bullet => {}
+    }
+
+    entity.id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::{
+        config::GameConfig,
+        fly_logic::{spawn_fly, AnimationTimer, EnemyKind, Health},
+    };
+
+    /// Names of every component on `entity`
+    fn component_names(app: &App, entity: Entity) -> BTreeSet<String> {
+        let world = app.world();
+        world
+            .entity(entity)
+            .archetype()
+            .components()
+            .filter_map(|component| world.components().get_name(component))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn name_of<T: 'static>() -> String {
+        std::any::type_name::<T>().to_string()
+    }
+
+    #[test]
+    fn ships_and_flies_share_everything_but_their_own_components() {
+        let mut app = App::new();
+        app.init_resource::<EntityIdCounter>()
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<GameConfig>();
+
+        let (player, plain_fly, fly) = app
+            .world_mut()
+            .run_system_once(
+                |mut commands: Commands,
+                 mut entity_ids: ResMut<EntityIdCounter>,
+                 color_palette: Res<ColorsPalette>,
+                 game_assets: Res<GameAssets>,
+                 config: Res<GameConfig>| {
+                    let position = Vec3::new(10.0, 20.0, 0.0);
+                    let spec = |entity_type| SpawnSpec::new(entity_type, position, &color_palette, &game_assets);
+                    (
+                        spawn_game_entity(&mut commands, &mut entity_ids, spec(EntityType::Player)),
+                        spawn_game_entity(&mut commands, &mut entity_ids, spec(EntityType::Fly)),
+                        spawn_fly(
+                            &mut commands,
+                            &mut entity_ids,
+                            position,
+                            EnemyKind::Boss,
+                            &color_palette,
+                            &game_assets,
+                            &config,
+                        ),
+                    )
+                },
+            )
+            .unwrap();
+
+        let player_components = component_names(&app, player);
+        let plain_fly_components = component_names(&app, plain_fly);
+        let fly_components = component_names(&app, fly);
+
+        // Both paths attach the same shared components, each only adds its own
+        let shared: BTreeSet<String> = plain_fly_components.difference(&[name_of::<Fly>()].into()).cloned().collect();
+        let player_own: BTreeSet<String> = [name_of::<Player>(), name_of::<Banking>()].into();
+        assert_eq!(player_components, shared.union(&player_own).cloned().collect());
+        let fly_own: BTreeSet<String> = [
+            name_of::<Fly>(),
+            name_of::<EnemyKind>(),
+            name_of::<Health>(),
+            name_of::<AnimationTimer>(),
+        ]
+        .into();
+        assert_eq!(fly_components, shared.union(&fly_own).cloned().collect());
+
+        let ids: Vec<u32> = [player, plain_fly, fly]
+            .into_iter()
+            .map(|entity| app.world().get::<GameEntity>(entity).unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        let transform = |entity| *app.world().get::<Transform>(entity).unwrap();
+        assert_eq!(transform(player).translation, Vec3::new(10.0, 20.0, layer::PLAYER));
+        assert_eq!(transform(player).scale, Vec3::splat(DEFAULT_SCALE));
+        assert_eq!(transform(fly).translation, Vec3::new(10.0, 20.0, layer::ENEMIES));
+        assert_eq!(transform(fly).scale, Vec3::splat(GameConfig::default().fly_scale));
+    }
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use rand::Rng;
+use crate::{game_rng::GameRng, layer, ColorsPalette, PlayField};
+
+/// Default number of stars, lower it for weak devices
+const STAR_COUNT: usize = 300;
+
+/// Parallax layers as (scroll speed, size, brightness): far stars are slow,
+/// small and dim, near ones fast, bigger and brighter
+const STAR_LAYERS: [(f32, f32, f32); 3] = [(20.0, 1.5, 0.35), (45.0, 2.0, 0.6), (90.0, 3.0, 0.9)];
+
+/// Scrolling star background behind everything else
+pub struct StarfieldPlugin;
+
+impl Plugin for StarfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StarCount>()
+            .add_systems(Startup, spawn_starfield)
+            .add_systems(Update, (fit_stars_to_play_field, scroll_stars));
+    }
+}
+
+/// How many stars the starfield is made of
+#[derive(Resource)]
+pub struct StarCount(pub usize);
+
+impl Default for StarCount {
+    fn default() -> Self {
+        StarCount(STAR_COUNT)
+    }
+}
+
+/// A background star drifting down the screen
+#[derive(Component)]
+pub struct Star {
+    pub speed: f32, // Units per second
+}
+
+/// Scatters the stars over the play field, spread evenly across the layers.
+/// They are never state scoped, so the menus get the same sky.
+fn spawn_starfield(
+    mut commands: Commands,
+    star_count: Res<StarCount>,
+    play_field: Res<PlayField>,
+    color_palette: Res<ColorsPalette>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let half_size = play_field.half_size();
+
+    for i in 0..star_count.0 {
+        let (speed, size, brightness) = STAR_LAYERS[i % STAR_LAYERS.len()];
+        let position = Vec3::new(
+            game_rng.gen_range(-half_size.x..half_size.x),
+            game_rng.gen_range(-half_size.y..half_size.y),
+            layer::BACKGROUND,
+        );
+
+        commands.spawn((
+            Star { speed },
+            Sprite {
+                color: star_color(&color_palette, brightness),
+                ..Default::default()
+            },
+            Transform {
+                translation: position,
+                scale: Vec3::splat(size),
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+/// Star tint: the background color lightened toward white, so the sky matches the palette
+fn star_color(color_palette: &ColorsPalette, brightness: f32) -> Color {
+    color_palette.background_color.mix(&Color::WHITE, brightness)
+}
+
+/// Stretches the sky along with a play field fitted to a resized window, so
+/// the stars still reach its edges. `spread` is the half size of the field
+/// they were last spread over.
+fn fit_stars_to_play_field(
+    mut star_query: Query<&mut Transform, With<Star>>,
+    play_field: Res<PlayField>,
+    mut spread: Local<Option<Vec2>>,
+) {
+    let half_size = play_field.half_size();
+    let Some(previous) = spread.replace(half_size) else {
+        return;
+    };
+    if previous == half_size {
+        return;
+    }
+
+    let stretch = half_size / previous;
+    for mut transform in star_query.iter_mut() {
+        transform.translation.x *= stretch.x;
+        transform.translation.y *= stretch.y;
+    }
+}
+
+/// Scrolls stars downward, wrapping them back to the top edge of the play
+/// field once they pass the bottom
+pub fn scroll_stars(
+    mut star_query: Query<(&mut Transform, &Star)>,
+    play_field: Res<PlayField>,
+    time: Res<Time>,
+) {
+    let half_height = play_field.half_size().y;
+
+    for (mut transform, star) in star_query.iter_mut() {
+        transform.translation.y -= star.speed * time.delta_secs();
+
+        if transform.translation.y < -half_height {
+            transform.translation.y += 2.0 * half_height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn star_below_the_bottom_wraps_to_the_top() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(PlayField {
+                width: 1200.0,
+                height: 800.0,
+            })
+            .add_systems(Update, scroll_stars);
+
+        let star = app
+            .world_mut()
+            .spawn((Star { speed: 0.0 }, Transform::from_xyz(0.0, -401.0, -1.0)))
+            .id();
+
+        app.update();
+
+        let y = app.world().get::<Transform>(star).unwrap().translation.y;
+        assert_eq!(y, 399.0);
+    }
+
+    #[test]
+    fn stars_spread_over_a_refitted_play_field() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<PlayField>()
+            .add_systems(Update, fit_stars_to_play_field);
+        let star = app
+            .world_mut()
+            .spawn((Star { speed: 0.0 }, Transform::from_xyz(500.0, 300.0, -1.0)))
+            .id();
+        app.update();
+
+        let fit = |app: &mut App, width: f32| {
+            app.world_mut().resource_mut::<PlayField>().width = width;
+            app.update();
+            app.world().get::<Transform>(star).unwrap().translation
+        };
+        assert_eq!(fit(&mut app, 600.0), Vec3::new(250.0, 300.0, -1.0));
+        assert_eq!(fit(&mut app, 1200.0), Vec3::new(500.0, 300.0, -1.0));
+    }
+}
@@ -0,0 +1,292 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{
+    attract::DemoRun,
+    game_state::{GameState, PauseState},
+    highscore::{record_high_score, HighScores},
+    practice::PracticeRun,
+    wave::{NextWaveTimer, Wave},
+    Bullet, BulletHit, BulletOwner, EnemyKilled, EntityType,
+};
+
+/// Shots, hits and kills of the run in progress, shown on the game over
+/// screen, and the totals of every run, saved along with the high score. The
+/// wave in progress is counted on its own for the wave results screen.
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .init_resource::<WaveStats>()
+            .add_systems(
+                Update,
+                (
+                    // Before this frame's shots and kills, which already belong to the new wave
+                    reset_wave_stats
+                        .before(count_shots)
+                        .before(count_hits)
+                        .before(count_kills)
+                        .run_if(resource_exists_and_changed::<Wave>),
+                    count_shots,
+                    count_hits,
+                    count_kills,
+                    count_cleared_waves.run_if(resource_added::<NextWaveTimer>),
+                    count_time_played,
+                )
+                    .run_if(in_state(PauseState::Running)),
+            )
+            // Saved along with the high scores. The demo's runs and practice runs never count.
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                add_run_to_totals
+                    .before(record_high_score)
+                    .run_if(not(resource_exists::<DemoRun>))
+                    .run_if(not(resource_exists::<PracticeRun>)),
+            );
+
+        // Every way into a fresh run, the same ones `PlayersPlugin` starts new slots on
+        for state in [GameState::Loading, GameState::Menu, GameState::GameOver] {
+            app.add_systems(OnExit(state), reset_run_stats);
+        }
+    }
+}
+
+/// What happened during the run in progress, over every player and turn of it
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct RunStats {
+    pub shots_fired: u32, // Player bullets and missiles, a twin or dual shot counts each bullet
+    pub hits: u32,        // Player bullets and missiles that hit an enemy
+    pub flies_killed: u32,
+    pub waves_cleared: u32,
+    pub time_played: f32, // Seconds of game time, pauses left out
+}
+
+impl RunStats {
+    /// Share of the shots that hit, as a percentage. No shots at all is 0%.
+    pub fn accuracy(&self) -> f32 {
+        accuracy(self.hits, self.shots_fired)
+    }
+}
+
+/// What happened during the wave in progress, for the results shown once it is cleared
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct WaveStats {
+    pub shots_fired: u32,
+    pub hits: u32,
+    pub flies_killed: u32,
+    pub points: u32, // Points for the kills, bonuses left out
+    pub bonus: u32,  // Perfect-clear bonus, only ever earned on challenge stages
+}
+
+impl WaveStats {
+    /// Share of the wave's shots that hit, as a percentage. No shots at all is 0%.
+    pub fn accuracy(&self) -> f32 {
+        accuracy(self.hits, self.shots_fired)
+    }
+}
+
+fn accuracy(hits: u32, shots_fired: u32) -> f32 {
+    if shots_fired == 0 {
+        return 0.0;
+    }
+    hits as f32 / shots_fired as f32 * 100.0
+}
+
+/// `RunStats` of every finished run added up
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    pub runs: u64,
+    pub shots_fired: u64,
+    pub hits: u64,
+    pub flies_killed: u64,
+    pub waves_cleared: u64,
+    pub time_played: f64, // Seconds
+}
+
+impl LifetimeStats {
+    /// Adds a finished run. Totals only ever grow, saturating rather than wrapping.
+    pub fn add(&mut self, run: &RunStats) {
+        self.runs = self.runs.saturating_add(1);
+        self.shots_fired = self.shots_fired.saturating_add(run.shots_fired.into());
+        self.hits = self.hits.saturating_add(run.hits.into());
+        self.flies_killed = self.flies_killed.saturating_add(run.flies_killed.into());
+        self.waves_cleared = self.waves_cleared.saturating_add(run.waves_cleared.into());
+        self.time_played += f64::from(run.time_played);
+    }
+}
+
+/// Starts the stats of a new run from zero
+fn reset_run_stats(mut run_stats: ResMut<RunStats>, mut wave_stats: ResMut<WaveStats>) {
+    *run_stats = RunStats::default();
+    *wave_stats = WaveStats::default();
+}
+
+/// Starts the stats of a wave from zero as it starts, including the first
+/// wave of a turn
+fn reset_wave_stats(mut wave_stats: ResMut<WaveStats>) {
+    *wave_stats = WaveStats::default();
+}
+
+/// Counts every player bullet and missile the frame they appear
+fn count_shots(
+    bullet_query: Query<&Bullet, Added<Bullet>>,
+    mut run_stats: ResMut<RunStats>,
+    mut wave_stats: ResMut<WaveStats>,
+) {
+    let shots = bullet_query
+        .iter()
+        .filter(|bullet| bullet.owner == BulletOwner::Player)
+        .count() as u32;
+    run_stats.shots_fired += shots;
+    wave_stats.shots_fired += shots;
+}
+
+/// Counts the shots that hit. Only player bullets can hit a fly.
+fn count_hits(
+    mut bullet_hit_events: EventReader<BulletHit>,
+    mut run_stats: ResMut<RunStats>,
+    mut wave_stats: ResMut<WaveStats>,
+) {
+    let hits = bullet_hit_events
+        .read()
+        .filter(|event| event.target_type == EntityType::Fly)
+        .count() as u32;
+    run_stats.hits += hits;
+    wave_stats.hits += hits;
+}
+
+/// Counts every enemy destroyed, however it went down, and the points it was worth
+fn count_kills(
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut run_stats: ResMut<RunStats>,
+    mut wave_stats: ResMut<WaveStats>,
+) {
+    for event in enemy_killed_events.read() {
+        run_stats.flies_killed += 1;
+        wave_stats.flies_killed += 1;
+        wave_stats.points += event.points;
+    }
+}
+
+/// Counts a wave as cleared once the countdown to the next one starts
+fn count_cleared_waves(mut run_stats: ResMut<RunStats>) {
+    run_stats.waves_cleared += 1;
+}
+
+/// Adds up game time while the run is not paused
+fn count_time_played(mut run_stats: ResMut<RunStats>, time: Res<Time>) {
+    run_stats.time_played += time.delta_secs();
+}
+
+/// Adds the run that just ended to the lifetime totals
+fn add_run_to_totals(run_stats: Res<RunStats>, mut high_scores: ResMut<HighScores>) {
+    high_scores.totals.add(&run_stats);
+    info!(
+        shots_fired = run_stats.shots_fired,
+        hits = run_stats.hits,
+        flies_killed = run_stats.flies_killed,
+        waves_cleared = run_stats.waves_cleared,
+        "Run finished"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accuracy_is_zero_without_shots() {
+        assert_eq!(RunStats::default().accuracy(), 0.0);
+
+        let run = RunStats {
+            shots_fired: 8,
+            hits: 3,
+            ..Default::default()
+        };
+        assert_eq!(run.accuracy(), 37.5);
+    }
+
+    #[test]
+    fn totals_only_grow() {
+        let run = RunStats {
+            shots_fired: 10,
+            hits: 4,
+            flies_killed: 5,
+            waves_cleared: 1,
+            time_played: 30.0,
+        };
+        let mut totals = LifetimeStats::default();
+        totals.add(&run);
+        totals.add(&RunStats::default());
+
+        assert_eq!(totals.runs, 2);
+        assert_eq!(totals.shots_fired, 10);
+        assert_eq!(totals.flies_killed, 5);
+        assert_eq!(totals.time_played, 30.0);
+
+        totals.shots_fired = u64::MAX;
+        totals.add(&run);
+        assert_eq!(totals.shots_fired, u64::MAX);
+    }
+
+    #[test]
+    fn shots_and_hits_are_counted_for_the_player_only() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<RunStats>()
+            .init_resource::<WaveStats>()
+            .add_event::<BulletHit>()
+            .add_systems(Update, (count_shots, count_hits));
+
+        let bullet = app.world_mut().spawn(Bullet { owner: BulletOwner::Player }).id();
+        app.world_mut().spawn(Bullet { owner: BulletOwner::Enemy });
+        app.update();
+
+        let target = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(BulletHit {
+            bullet,
+            target,
+            target_type: EntityType::Fly,
+        });
+        app.update();
+
+        let run_stats = app.world().resource::<RunStats>();
+        assert_eq!((run_stats.shots_fired, run_stats.hits), (1, 1));
+        assert_eq!(run_stats.accuracy(), 100.0);
+    }
+
+    #[test]
+    fn wave_stats_start_over_with_every_wave() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<RunStats>()
+            .init_resource::<WaveStats>()
+            .insert_resource(Wave(1))
+            .add_event::<EnemyKilled>()
+            .add_systems(
+                Update,
+                (reset_wave_stats.run_if(resource_exists_and_changed::<Wave>), count_kills).chain(),
+            );
+
+        let kill = |app: &mut App, points: u32| {
+            app.world_mut().send_event(EnemyKilled {
+                entity: Entity::PLACEHOLDER,
+                points,
+                position: Vec3::ZERO,
+                kind: crate::fly_logic::EnemyKind::Bee,
+                player: 0,
+            });
+            app.update();
+        };
+        kill(&mut app, 100);
+        kill(&mut app, 50);
+        let wave_stats = *app.world().resource::<WaveStats>();
+        assert_eq!((wave_stats.flies_killed, wave_stats.points), (2, 150));
+
+        app.world_mut().resource_mut::<Wave>().0 = 2;
+        kill(&mut app, 80);
+        let wave_stats = *app.world().resource::<WaveStats>();
+        assert_eq!((wave_stats.flies_killed, wave_stats.points), (1, 80));
+        assert_eq!(app.world().resource::<RunStats>().flies_killed, 3);
+    }
+}
@@ -0,0 +1,409 @@
+use std::path::Path;
+use bevy::prelude::*;
+use crate::{
+    challenge::{ChallengeStage, CHALLENGE_FLIES},
+    config::GameConfig,
+    explosion::Lifetime,
+    fly_logic::{spawn_formation_wave, FlySpawnTimer},
+    formation::Formation,
+    game_assets::GameAssets,
+    game_rng::GameRng,
+    game_state::{GameState, PauseState},
+    mothership::spawn_mothership,
+    paths::PathLibrary,
+    run_intro::RunIntro,
+    wave_plan::{WavePlan, WAVES_PATH},
+    ColorsPalette, EntityIdCounter, Fly, PlayField,
+};
+
+/// Default seconds between clearing a wave and the next one flying in
+pub(crate) const NEXT_WAVE_DELAY: f32 = 2.0;
+
+/// Seconds the "STAGE N" banner is shown before the wave flies in. No flies
+/// spawn and none shoot in the meantime.
+const WAVE_INTRO_DURATION: f32 = 1.5;
+
+/// Seconds the stage banner takes to fade out once the wave has started
+const BANNER_FADE_DURATION: f32 = 0.5;
+
+/// Highest stage number the banner shows, as in the arcade
+const MAX_SHOWN_STAGE: u32 = 99;
+
+/// Every this many waves, the wave is a challenge stage
+const CHALLENGE_STAGE_EVERY: u32 = 3;
+
+/// Every this many waves, a normal wave opens with the mothership
+const MOTHERSHIP_EVERY: u32 = 5;
+
+/// Wave progression: the next wave starts once every fly of the current one
+/// is gone, normal waves bringing in the groups of the `WavePlan`. Needs the
+/// `PathLibrary` to check the plan against, so goes after `PathsPlugin`.
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        let plan = WavePlan::load(
+            Path::new(WAVES_PATH),
+            app.world().resource::<PathLibrary>(),
+            &Formation::default(),
+        );
+        let first_wave_flies = plan.flies_in(&Wave(1));
+        app.insert_resource(plan)
+            .insert_resource(Wave(1))
+            .insert_resource(WaveRemaining(first_wave_flies))
+            .init_resource::<StartingWave>()
+            .add_systems(
+                Update,
+                (
+                    // Challenge stages decide themselves when they are over
+                    detect_cleared_wave.run_if(not(resource_exists::<ChallengeStage>)),
+                    start_next_wave.after(detect_cleared_wave),
+                    show_wave_banner.after(start_next_wave),
+                    // Nothing flies in while the run intro or a new ship's "READY" is up
+                    end_wave_intro.after(show_wave_banner).run_if(not(resource_exists::<RunIntro>)),
+                    fade_wave_banner,
+                )
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+/// Current wave number, starting at 1
+#[derive(Resource)]
+pub struct Wave(pub u32);
+
+/// Wave every run starts on, 1 unless `--wave` asked for a later one
+#[derive(Resource)]
+pub struct StartingWave(pub u32);
+
+impl Default for StartingWave {
+    fn default() -> Self {
+        StartingWave(1)
+    }
+}
+
+/// What a wave plays like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveKind {
+    /// Flies fill the formation, shoot and dive until all are destroyed
+    Normal,
+    /// Flies stream past along fixed paths without attacking, for bonus points
+    Challenge,
+}
+
+impl Wave {
+    pub fn kind(&self) -> WaveKind {
+        if self.0 % CHALLENGE_STAGE_EVERY == 0 {
+            WaveKind::Challenge
+        } else {
+            WaveKind::Normal
+        }
+    }
+
+    /// Whether the wave opens with the mothership, the rest of its flies
+    /// coming in once it is destroyed. Challenge stages never have one.
+    pub fn has_mothership(&self) -> bool {
+        self.kind() == WaveKind::Normal && self.0 % MOTHERSHIP_EVERY == 0
+    }
+
+    /// How many normal waves come before this one, challenge stages not counted
+    pub fn normal_index(&self) -> usize {
+        let before = self.0.saturating_sub(1);
+        (before - before / CHALLENGE_STAGE_EVERY) as usize
+    }
+}
+
+/// Flies of the current wave that have not been spawned yet
+#[derive(Resource)]
+pub struct WaveRemaining(pub usize);
+
+/// Present between waves, counting down until the next one starts
+#[derive(Resource)]
+pub struct NextWaveTimer(pub Timer);
+
+/// Present while the banner of a wave that just started is shown. Flies start
+/// flying in, and shooting, only once it ends.
+#[derive(Resource)]
+pub struct WaveIntroTimer(pub Timer);
+
+/// Marks the banner naming the wave that is about to start
+#[derive(Component)]
+struct WaveBanner;
+
+/// Starts the countdown to the next wave once the whole wave has been spawned
+/// and destroyed
+pub fn detect_cleared_wave(
+    mut commands: Commands,
+    remaining: Res<WaveRemaining>,
+    next_wave_timer: Option<Res<NextWaveTimer>>,
+    fly_query: Query<(), With<Fly>>,
+    config: Res<GameConfig>,
+) {
+    if remaining.0 > 0 || next_wave_timer.is_some() || !fly_query.is_empty() {
+        return;
+    }
+
+    commands.insert_resource(NextWaveTimer(Timer::from_seconds(config.next_wave_delay, TimerMode::Once)));
+}
+
+/// Moves on to the next wave when the countdown ends. Its flies come in once
+/// the wave intro is over.
+fn start_next_wave(
+    mut commands: Commands,
+    next_wave_timer: Option<ResMut<NextWaveTimer>>,
+    mut wave: ResMut<Wave>,
+    mut remaining: ResMut<WaveRemaining>,
+    plan: Res<WavePlan>,
+    time: Res<Time>,
+) {
+    let Some(mut next_wave_timer) = next_wave_timer else {
+        return;
+    };
+
+    if !next_wave_timer.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    commands.remove_resource::<NextWaveTimer>();
+
+    wave.0 += 1;
+
+    if wave.kind() == WaveKind::Challenge {
+        remaining.0 = CHALLENGE_FLIES;
+        commands.insert_resource(ChallengeStage::default());
+        info!(wave = wave.0, flies = remaining.0, "Starting challenge stage");
+        return;
+    }
+
+    remaining.0 = plan.flies_in(&wave);
+    info!(wave = wave.0, flies = remaining.0, "Starting wave");
+}
+
+/// Text of the banner starting `wave`
+pub fn wave_banner_text(wave: &Wave) -> String {
+    match wave.kind() {
+        WaveKind::Normal => format!("STAGE {}", wave.0.min(MAX_SHOWN_STAGE)),
+        WaveKind::Challenge => "CHALLENGING STAGE".to_string(),
+    }
+}
+
+/// Shows the stage banner whenever a wave starts, including the first one of
+/// a turn, and holds the wave back until it has been up for
+/// `WAVE_INTRO_DURATION`. The first wave of a run is named by the run intro
+/// instead, and flies in as soon as the intro is over.
+fn show_wave_banner(
+    mut commands: Commands,
+    wave: Res<Wave>,
+    run_intro: Option<Res<RunIntro>>,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+) {
+    if !wave.is_changed() {
+        return;
+    }
+
+    if run_intro.is_some_and(|run_intro| run_intro.announces_stage()) {
+        commands.insert_resource(WaveIntroTimer(Timer::from_seconds(0.0, TimerMode::Once)));
+        return;
+    }
+
+    let banner = spawn_banner(
+        &mut commands,
+        &game_assets,
+        &wave_banner_text(&wave),
+        color_palette.bee_color,
+        WAVE_INTRO_DURATION + BANNER_FADE_DURATION,
+    );
+    commands.entity(banner).insert(WaveBanner);
+    commands.insert_resource(WaveIntroTimer(Timer::from_seconds(WAVE_INTRO_DURATION, TimerMode::Once)));
+}
+
+/// Ends the wave intro once its banner has been up long enough. Normal waves
+/// send in their first group of flies right away, or the mothership every
+/// `MOTHERSHIP_EVERY` waves. Challenge stages start streaming on their own.
+#[allow(clippy::too_many_arguments)]
+fn end_wave_intro(
+    mut commands: Commands,
+    mut entity_ids: ResMut<EntityIdCounter>,
+    mut game_rng: ResMut<GameRng>,
+    intro_timer: Option<ResMut<WaveIntroTimer>>,
+    wave: Res<Wave>,
+    mut remaining: ResMut<WaveRemaining>,
+    mut spawn_timer: ResMut<FlySpawnTimer>,
+    plan: Res<WavePlan>,
+    play_field: Res<PlayField>,
+    paths: Res<PathLibrary>,
+    color_palette: Res<ColorsPalette>,
+    game_assets: Res<GameAssets>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let Some(mut intro_timer) = intro_timer else {
+        return;
+    };
+
+    if !intro_timer.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    commands.remove_resource::<WaveIntroTimer>();
+
+    if wave.kind() == WaveKind::Challenge {
+        return;
+    }
+
+    if wave.has_mothership() {
+        spawn_mothership(
+            &mut commands,
+            &mut entity_ids,
+            &mut game_rng,
+            &play_field,
+            &color_palette,
+            &game_assets,
+            &config,
+        );
+        spawn_timer.0.reset();
+        return;
+    }
+
+    let Some(group) = plan.next_group(&wave, remaining.0) else {
+        return;
+    };
+    spawn_formation_wave(
+        &mut commands,
+        &mut entity_ids,
+        group,
+        plan.scaling(&wave),
+        &mut remaining,
+        &play_field,
+        &paths,
+        &color_palette,
+        &game_assets,
+        &config,
+    );
+    spawn_timer.0.reset();
+}
+
+/// Keeps the stage banner fully visible during the intro, then fades it out
+fn fade_wave_banner(mut banner_query: Query<(&Lifetime, &mut TextColor), With<WaveBanner>>) {
+    for (lifetime, mut color) in banner_query.iter_mut() {
+        let fading = lifetime.0.elapsed_secs() - WAVE_INTRO_DURATION;
+        let alpha = 1.0 - (fading / BANNER_FADE_DURATION).clamp(0.0, 1.0);
+        color.0.set_alpha(alpha);
+    }
+}
+
+/// Shows `message` across the middle of the screen for `duration` seconds
+pub fn spawn_banner(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    message: &str,
+    color: Color,
+    duration: f32,
+) -> Entity {
+    commands
+        .spawn((
+            Lifetime(Timer::from_seconds(duration, TimerMode::Once)),
+            StateScoped(GameState::Playing),
+            Text::new(message),
+            TextFont {
+                font: game_assets.font.clone(),
+                font_size: 48.0,
+                ..Default::default()
+            },
+            TextColor(color),
+            TextLayout::new_with_justify(JustifyText::Center),
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                top: Val::Percent(45.0),
+                ..Default::default()
+            },
+        ))
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    #[test]
+    fn stage_banner_tops_out_at_99() {
+        assert_eq!(wave_banner_text(&Wave(7)), "STAGE 7");
+        assert_eq!(wave_banner_text(&Wave(99)), "STAGE 99");
+        assert_eq!(wave_banner_text(&Wave(250)), "STAGE 99");
+        assert_eq!(wave_banner_text(&Wave(6)), "CHALLENGING STAGE");
+    }
+
+    #[test]
+    fn flies_wait_for_the_wave_intro_to_end() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(500)))
+            .insert_resource(Wave(1))
+            .insert_resource(WaveRemaining(12))
+            .insert_resource(GameRng::from_seed(0))
+            .init_resource::<FlySpawnTimer>()
+            .init_resource::<WavePlan>()
+            .init_resource::<EntityIdCounter>()
+            .init_resource::<PlayField>()
+            .init_resource::<PathLibrary>()
+            .init_resource::<ColorsPalette>()
+            .init_resource::<GameAssets>()
+            .init_resource::<GameConfig>()
+            .add_systems(Update, (show_wave_banner, end_wave_intro).chain());
+
+        let flies = |app: &mut App| app.world_mut().query_filtered::<(), With<Fly>>().iter(app.world()).count();
+
+        for _ in 0..3 {
+            app.update(); // 1 second, the first update has no elapsed time
+        }
+        assert_eq!(flies(&mut app), 0);
+        assert!(app.world().contains_resource::<WaveIntroTimer>());
+
+        app.update();
+        assert!(flies(&mut app) > 0);
+        assert!(!app.world().contains_resource::<WaveIntroTimer>());
+    }
+
+    #[test]
+    fn countdown_starts_only_after_the_last_fly_is_gone() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(WaveRemaining(0))
+            .init_resource::<GameConfig>()
+            .add_systems(Update, detect_cleared_wave);
+
+        let fly = app.world_mut().spawn(Fly).id();
+        app.update();
+        assert!(!app.world().contains_resource::<NextWaveTimer>());
+
+        app.world_mut().despawn(fly);
+        app.update();
+        assert!(app.world().contains_resource::<NextWaveTimer>());
+    }
+
+    #[test]
+    fn every_fifth_normal_wave_has_the_mothership() {
+        let waves: Vec<u32> = (1..=30).filter(|&wave| Wave(wave).has_mothership()).collect();
+        assert_eq!(waves, vec![5, 10, 20, 25]);
+    }
+
+    #[test]
+    fn every_third_wave_is_a_challenge_stage() {
+        let kinds: Vec<WaveKind> = (1..=6).map(|wave| Wave(wave).kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                WaveKind::Normal,
+                WaveKind::Normal,
+                WaveKind::Challenge,
+                WaveKind::Normal,
+                WaveKind::Normal,
+                WaveKind::Challenge,
+            ]
+        );
+    }
+}
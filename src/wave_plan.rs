@@ -0,0 +1,242 @@
+use std::{fs, path::Path};
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::{
+    fly_logic::EnemyKind,
+    formation::{Formation, FormationSlot},
+    paths::PathLibrary,
+    wave::Wave,
+};
+
+/// Wave definitions read at startup, so waves can be rearranged without recompiling
+pub const WAVES_PATH: &str = "assets/config/waves.ron";
+
+/// The waves shipped with the game, used when the file is missing or broken
+const BUILT_IN_WAVES: &str = include_str!("../assets/config/waves.ron");
+
+/// Repeats of the last wave past this many stop getting faster
+const MAX_SCALED_REPEATS: u32 = 10;
+
+/// Every normal wave of a run as groups of flies, in the order they come in.
+/// Waves past the end of the plan repeat its last wave, scaled up.
+#[derive(Resource, Debug, Clone, PartialEq, Deserialize)]
+pub struct WavePlan {
+    waves: Vec<PlannedWave>,
+    repeat_scaling: RepeatScaling,
+}
+
+/// One normal wave of the plan
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PlannedWave {
+    pub groups: Vec<SpawnGroup>,
+}
+
+/// Flies of one kind flying in together along `path`, `spawn_delay` seconds
+/// apart, into the slots from `row` and `col` rightwards
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SpawnGroup {
+    pub kind: EnemyKind,
+    pub count: u32,
+    pub path: String,
+    pub row: u32,
+    pub col: u32,
+    pub spawn_delay: f32,
+}
+
+/// How much each repeat of the last wave speeds up over the one before
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct RepeatScaling {
+    pub path_speed: f32,  // Multiplies how fast entrances are flown
+    pub spawn_delay: f32, // Multiplies the seconds between members of a group
+}
+
+impl Default for WavePlan {
+    fn default() -> Self {
+        WavePlan::from_ron(BUILT_IN_WAVES, &PathLibrary::default(), &Formation::default())
+            .expect("built-in waves are valid")
+    }
+}
+
+impl WavePlan {
+    /// Reads the waves at `path`, keeping the built-in ones if the file is
+    /// missing. A file that can't be used is logged as an error, naming the
+    /// wave and field at fault, before falling back to the built-in waves.
+    pub fn load(path: &Path, paths: &PathLibrary, formation: &Formation) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return WavePlan::default();
+        };
+
+        match WavePlan::from_ron(&contents, paths, formation) {
+            Ok(plan) => plan,
+            Err(error) => {
+                error!("Ignoring wave plan {}: {}", path.display(), error);
+                WavePlan::default()
+            }
+        }
+    }
+
+    /// Parses a waves file, checking every group flies a path of `paths` into
+    /// slots that exist in `formation` and aren't taken twice in the same wave
+    pub fn from_ron(contents: &str, paths: &PathLibrary, formation: &Formation) -> Result<Self, String> {
+        let plan: WavePlan = ron::from_str(contents).map_err(|error| error.to_string())?;
+
+        if plan.waves.is_empty() {
+            return Err("`waves` lists no waves".to_string());
+        }
+        for (name, value) in [
+            ("path_speed", plan.repeat_scaling.path_speed),
+            ("spawn_delay", plan.repeat_scaling.spawn_delay),
+        ] {
+            if value <= 0.0 {
+                return Err(format!("`repeat_scaling.{}` needs to be positive", name));
+            }
+        }
+
+        for (index, wave) in plan.waves.iter().enumerate() {
+            if wave.groups.is_empty() {
+                return Err(format!("wave {}: `groups` lists no flies", index + 1));
+            }
+
+            let mut taken: Vec<FormationSlot> = Vec::new();
+            for (group_index, group) in wave.groups.iter().enumerate() {
+                // Numbered from 1, like the waves and groups of the file read top to bottom
+                let at = format!("wave {}, group {}", index + 1, group_index + 1);
+                if group.kind == EnemyKind::Mothership {
+                    return Err(format!("{}: `kind` can't be Mothership, it comes on its own", at));
+                }
+                if group.count == 0 {
+                    return Err(format!("{}: `count` needs at least 1 fly", at));
+                }
+                if paths.get(&group.path).is_none() {
+                    return Err(format!("{}: `path` uses unknown path `{}`", at, group.path));
+                }
+                if group.row >= formation.rows {
+                    return Err(format!(
+                        "{}: `row` {} is outside the {} formation rows",
+                        at, group.row, formation.rows
+                    ));
+                }
+                if group.col.saturating_add(group.count) > formation.cols {
+                    return Err(format!(
+                        "{}: `col` {} leaves no room for {} flies in the {} formation columns",
+                        at, group.col, group.count, formation.cols
+                    ));
+                }
+                if group.spawn_delay < 0.0 {
+                    return Err(format!("{}: `spawn_delay` can't be negative", at));
+                }
+                for slot in group.slots() {
+                    if taken.contains(&slot) {
+                        return Err(format!("{}: `col` reuses the slot at row {}, col {}", at, slot.row, slot.col));
+                    }
+                    taken.push(slot);
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// The planned wave `wave` plays, and how many repeats of the last wave
+    /// past the end of the plan it is
+    fn planned(&self, wave: &Wave) -> (&PlannedWave, u32) {
+        let index = wave.normal_index();
+        let last = self.waves.len() - 1;
+        (&self.waves[index.min(last)], index.saturating_sub(last) as u32)
+    }
+
+    /// Flies that have to be destroyed to clear `wave`
+    pub fn flies_in(&self, wave: &Wave) -> usize {
+        let (planned, _) = self.planned(wave);
+        planned.groups.iter().map(|group| group.count as usize).sum()
+    }
+
+    /// The group of `wave` to bring in next, with `remaining` of its flies
+    /// not spawned yet. Groups come in whole, in the order of the plan.
+    pub fn next_group(&self, wave: &Wave, remaining: usize) -> Option<&SpawnGroup> {
+        let (planned, _) = self.planned(wave);
+        let mut spawned = self.flies_in(wave).checked_sub(remaining)?;
+        planned.groups.iter().find(|group| {
+            let done = spawned >= group.count as usize;
+            spawned = spawned.saturating_sub(group.count as usize);
+            !done
+        })
+    }
+
+    /// How many times as fast as planned `wave` flies its entrances, and the
+    /// factor on the delay between members of a group
+    pub fn scaling(&self, wave: &Wave) -> (f32, f32) {
+        let (_, repeats) = self.planned(wave);
+        let repeats = repeats.min(MAX_SCALED_REPEATS) as i32;
+        (
+            self.repeat_scaling.path_speed.powi(repeats),
+            self.repeat_scaling.spawn_delay.powi(repeats),
+        )
+    }
+}
+
+impl SpawnGroup {
+    /// Slots the members of the group fly to, in the order they come in
+    pub fn slots(&self) -> impl Iterator<Item = FormationSlot> + '_ {
+        (self.col..self.col + self.count).map(|col| FormationSlot { row: self.row, col })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_with_group(group: &str) -> Result<WavePlan, String> {
+        WavePlan::from_ron(
+            &format!(
+                "(waves: [(groups: [(kind: Bee, count: 3, path: \"swoop_left\", row: 2, col: 0, spawn_delay: 0.1)]), \
+                 (groups: [{}])], repeat_scaling: (path_speed: 1.5, spawn_delay: 0.5))",
+                group
+            ),
+            &PathLibrary::default(),
+            &Formation::default(),
+        )
+    }
+
+    #[test]
+    fn built_in_plan_loads() {
+        let plan = WavePlan::default();
+        assert_eq!(plan.flies_in(&Wave(1)), 12);
+        assert!(plan.next_group(&Wave(1), 12).is_some());
+    }
+
+    #[test]
+    fn bad_groups_name_the_wave_and_field() {
+        let error = plan_with_group("(kind: Bee, count: 2, path: \"nowhere\", row: 0, col: 0, spawn_delay: 0.1)")
+            .unwrap_err();
+        assert!(error.contains("wave 2, group 1") && error.contains("`path`"), "{}", error);
+
+        let error = plan_with_group("(kind: Bee, count: 4, path: \"swoop_left\", row: 0, col: 4, spawn_delay: 0.1)")
+            .unwrap_err();
+        assert!(error.contains("wave 2, group 1") && error.contains("`col`"), "{}", error);
+
+        let error = plan_with_group("(kind: Boss, count: 1, path: \"swoop_left\", row: 3, col: 0, spawn_delay: 0.1)")
+            .unwrap_err();
+        assert!(error.contains("`row`"), "{}", error);
+    }
+
+    #[test]
+    fn groups_come_in_order_and_the_last_wave_repeats_faster() {
+        let plan = plan_with_group(
+            "(kind: Boss, count: 2, path: \"drop_left\", row: 0, col: 0, spawn_delay: 0.2), \
+             (kind: Bee, count: 1, path: \"drop_right\", row: 2, col: 5, spawn_delay: 0.0)",
+        )
+        .unwrap();
+
+        let second = Wave(2);
+        assert_eq!(plan.next_group(&second, 3).unwrap().kind, EnemyKind::Boss);
+        assert_eq!(plan.next_group(&second, 1).unwrap().kind, EnemyKind::Bee);
+        assert!(plan.next_group(&second, 0).is_none());
+        assert_eq!(plan.scaling(&second), (1.0, 1.0));
+
+        // Wave 3 is a challenge stage, waves 4 and 5 repeat the last planned wave
+        assert_eq!(plan.flies_in(&Wave(5)), 3);
+        assert_eq!(plan.scaling(&Wave(4)), (1.5, 0.5));
+        assert_eq!(plan.scaling(&Wave(5)), (2.25, 0.25));
+    }
+}
@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+use crate::{
+    bullet::release_bullet,
+    challenge::CHALLENGE_FLIES,
+    explosion::Lifetime,
+    game_assets::GameAssets,
+    game_state::PauseState,
+    player_input::{handle_player_input, ShipKeys, TouchControls},
+    stats::WaveStats,
+    wave::{spawn_banner, wave_banner_text, NextWaveTimer, Wave, WaveKind},
+    Bullet, BulletOwner, ColorsPalette, InputSet,
+};
+
+/// Seconds the results are up before fire skips them, so the shot that
+/// finished the wave doesn't skip them as well
+const MIN_RESULTS_SHOWN: f32 = 0.5;
+
+/// Size of the results text, smaller than a banner so every line fits
+const RESULTS_FONT_SIZE: f32 = 32.0;
+
+/// What the wave just cleared came to: flies destroyed, accuracy, points and
+/// any perfect-clear bonus, shown for as long as the countdown to the next
+/// wave runs, before its stage banner. Enemy bullets still flying are cleared
+/// as the wave ends, so nothing can hit the ship while the results are up.
+/// Fire skips them, starting the next wave straight away.
+pub struct WaveResultsPlugin;
+
+impl Plugin for WaveResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (show_wave_results, clear_enemy_bullets).run_if(resource_added::<NextWaveTimer>),
+                // Fire pressed while the results are up, never a shot the ship ignored
+                skip_wave_results
+                    .in_set(InputSet)
+                    .after(handle_player_input)
+                    .run_if(resource_exists::<NextWaveTimer>),
+                hide_wave_results.run_if(resource_removed::<NextWaveTimer>),
+            )
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// Marks the text of the wave results
+#[derive(Component)]
+struct WaveResultsText;
+
+/// What the results screen says once `wave` is cleared with `wave_stats`
+fn results_text(wave: &Wave, wave_stats: &WaveStats) -> String {
+    let destroyed = match wave.kind() {
+        WaveKind::Normal => wave_stats.flies_killed.to_string(),
+        WaveKind::Challenge => format!("{} / {}", wave_stats.flies_killed, CHALLENGE_FLIES),
+    };
+    let mut text = format!(
+        "{} CLEAR\n\nFLIES DESTROYED  {}\nACCURACY  {:.0}%\nPOINTS  {}",
+        wave_banner_text(wave),
+        destroyed,
+        wave_stats.accuracy(),
+        wave_stats.points
+    );
+    if wave_stats.bonus > 0 {
+        text.push_str(&format!("\n\nPERFECT!\nBONUS {}", wave_stats.bonus));
+    }
+    text
+}
+
+/// Shows the results once the countdown to the next wave starts. They stay
+/// up until it ends, whether on time or skipped.
+fn show_wave_results(
+    mut commands: Commands,
+    wave: Res<Wave>,
+    wave_stats: Res<WaveStats>,
+    next_wave_timer: Res<NextWaveTimer>,
+    game_assets: Res<GameAssets>,
+    color_palette: Res<ColorsPalette>,
+) {
+    info!(
+        wave = wave.0,
+        flies_killed = wave_stats.flies_killed,
+        accuracy = wave_stats.accuracy(),
+        points = wave_stats.points,
+        bonus = wave_stats.bonus,
+        "Wave cleared"
+    );
+
+    let text = spawn_banner(
+        &mut commands,
+        &game_assets,
+        &results_text(&wave, &wave_stats),
+        color_palette.player_color,
+        next_wave_timer.0.duration().as_secs_f32(),
+    );
+    commands
+        .entity(text)
+        .insert((
+            WaveResultsText,
+            TextFont {
+                font: game_assets.font.clone(),
+                font_size: RESULTS_FONT_SIZE,
+                ..Default::default()
+            },
+        ))
+        .remove::<Lifetime>();
+}
+
+/// Takes every enemy bullet still flying out of play as the wave ends
+fn clear_enemy_bullets(mut commands: Commands, bullet_query: Query<(Entity, &Bullet)>) {
+    for (entity, bullet) in bullet_query.iter() {
+        if bullet.owner == BulletOwner::Enemy {
+            release_bullet(&mut commands, entity);
+        }
+    }
+}
+
+/// Ends the countdown to the next wave when fire is pressed or tapped
+fn skip_wave_results(
+    ship_keys: ShipKeys,
+    touch_controls: Res<TouchControls>,
+    mut next_wave_timer: ResMut<NextWaveTimer>,
+) {
+    let timer = &mut next_wave_timer.0;
+    if timer.elapsed_secs() < MIN_RESULTS_SHOWN || !(ship_keys.fire() || touch_controls.fire) {
+        return;
+    }
+
+    info!("Wave results skipped");
+    let duration = timer.duration();
+    timer.set_elapsed(duration);
+}
+
+/// Takes the results down once the next wave starts
+fn hide_wave_results(mut commands: Commands, text_query: Query<Entity, With<WaveResultsText>>) {
+    for text in text_query.iter() {
+        commands.entity(text).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybindings::KeyBindings;
+    use std::time::Duration;
+
+    #[test]
+    fn results_name_the_wave_and_any_bonus() {
+        let wave_stats = WaveStats {
+            shots_fired: 40,
+            hits: 30,
+            flies_killed: 24,
+            points: 2400,
+            bonus: 0,
+        };
+        assert_eq!(
+            results_text(&Wave(2), &wave_stats),
+            "STAGE 2 CLEAR\n\nFLIES DESTROYED  24\nACCURACY  75%\nPOINTS  2400"
+        );
+
+        let perfect = WaveStats { bonus: 1000, ..wave_stats };
+        let text = results_text(&Wave(3), &perfect);
+        assert!(text.starts_with("CHALLENGING STAGE CLEAR\n\nFLIES DESTROYED  24 / 24"));
+        assert!(text.ends_with("PERFECT!\nBONUS 1000"));
+    }
+
+    #[test]
+    fn results_clear_enemy_bullets_and_fire_skips_them() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GameAssets>()
+            .init_resource::<ColorsPalette>()
+            .insert_resource(Wave(1))
+            .init_resource::<WaveStats>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<TouchControls>()
+            .add_systems(
+                Update,
+                (
+                    (show_wave_results, clear_enemy_bullets).run_if(resource_added::<NextWaveTimer>),
+                    skip_wave_results.run_if(resource_exists::<NextWaveTimer>),
+                    hide_wave_results.run_if(resource_removed::<NextWaveTimer>),
+                ),
+            );
+        let enemy_bullet = app.world_mut().spawn(Bullet { owner: BulletOwner::Enemy }).id();
+        let player_bullet = app.world_mut().spawn(Bullet { owner: BulletOwner::Player }).id();
+        app.insert_resource(NextWaveTimer(Timer::from_seconds(2.0, TimerMode::Once)));
+
+        let shown = |app: &mut App| {
+            app.world_mut()
+                .query_filtered::<(), With<WaveResultsText>>()
+                .iter(app.world())
+                .count()
+        };
+        app.world_mut().resource_mut::<TouchControls>().fire = true;
+        app.update();
+        assert_eq!(shown(&mut app), 1);
+        assert!(app.world().get_entity(enemy_bullet).is_err());
+        assert!(app.world().get_entity(player_bullet).is_ok());
+        // Too early to skip, the results have only just come up
+        assert_eq!(app.world().resource::<NextWaveTimer>().0.elapsed(), Duration::ZERO);
+
+        let shown_for = Duration::from_secs_f32(MIN_RESULTS_SHOWN);
+        app.world_mut().resource_mut::<NextWaveTimer>().0.set_elapsed(shown_for);
+        app.update();
+        let timer = &app.world().resource::<NextWaveTimer>().0;
+        assert_eq!(timer.elapsed(), timer.duration());
+
+        app.world_mut().remove_resource::<NextWaveTimer>();
+        app.update();
+        assert_eq!(shown(&mut app), 0);
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use galaga_bevy::{
+    bullet::apply_velocity,
+    collision::{apply_bullet_hits, collision, despawn_hit_bullets, rebuild_spatial_grid, CollisionSet},
+    despawn_out_of_bounds_entities, layer,
+    spatial_grid::SpatialGrid,
+    Bullet, BulletHit, BulletOwner, BulletSpeed, CleanupSet, ColorsPalette, DebugSettings,
+    DespawnOutOfBounds, EnemyKilled, EntityType, Fly, GameEntity, MovementSet, OutlineContainer, PlayField,
+    Player, PlayerHit, PlayerPosition, Velocity,
+};
+use std::time::Duration;
+
+const PLAYER_POSITION: Vec3 = Vec3::new(0.0, -250.0, 0.0);
+
+/// Headless app running bullet movement, hit checks and culling in the sets
+/// the game puts them in, one 60 Hz step per `update`. No window, audio or assets.
+fn gameplay_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / 60.0)))
+        .insert_resource(BulletSpeed(300.0))
+        .insert_resource(PlayerPosition(PLAYER_POSITION))
+        .init_resource::<ColorsPalette>()
+        .insert_resource(PlayField {
+            width: 1200.0,
+            height: 800.0,
+        })
+        .init_resource::<DebugSettings>()
+        .init_resource::<SpatialGrid>()
+        .add_event::<BulletHit>()
+        .add_event::<EnemyKilled>()
+        .add_event::<PlayerHit>()
+        .configure_sets(
+            Update,
+            (MovementSet, CollisionSet::Detect, CollisionSet::Resolve, CleanupSet).chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                despawn_out_of_bounds_entities.in_set(CleanupSet),
+                (despawn_hit_bullets, apply_bullet_hits).in_set(CollisionSet::Resolve),
+                (rebuild_spatial_grid, collision).chain().in_set(CollisionSet::Detect),
+                apply_velocity.in_set(MovementSet),
+            ),
+        );
+    app
+}
+
+/// Spawns a ship or a fly on its layer, like the game does, so every hit
+/// checked here crosses layers
+fn spawn_target(app: &mut App, id: u32, entity_type: EntityType, position: Vec3) -> Entity {
+    let z = match entity_type {
+        EntityType::Player => layer::PLAYER,
+        _ => layer::ENEMIES,
+    };
+    let mut entity = app.world_mut().spawn((
+        GameEntity { id, entity_type },
+        Transform::from_translation(position.with_z(z)).with_scale(Vec3::splat(50.0)),
+    ));
+    match entity_type {
+        EntityType::Player => entity.insert(Player),
+        _ => entity.insert(Fly),
+    };
+    entity.id()
+}
+
+/// Spawns a bullet flying straight at the usual bullet speed, up for the
+/// player's and down for the enemies'
+fn spawn_bullet(app: &mut App, owner: BulletOwner, position: Vec3) -> Entity {
+    let speed = app.world().resource::<BulletSpeed>().0;
+    let direction = match owner {
+        BulletOwner::Player => 1.0,
+        BulletOwner::Enemy => -1.0,
+    };
+
+    app.world_mut()
+        .spawn((
+            Bullet { owner },
+            DespawnOutOfBounds,
+            Velocity(Vec2::new(0.0, speed * direction)),
+            Transform::from_translation(position.with_z(layer::BULLETS)).with_scale(owner.bullet_scale()),
+        ))
+        .id()
+}
+
+fn exists(app: &App, entity: Entity) -> bool {
+    app.world().get_entity(entity).is_ok()
+}
+
+fn step(app: &mut App, steps: usize) {
+    for _ in 0..steps {
+        app.update();
+    }
+}
+
+#[test]
+fn bullet_reaching_a_fly_despawns_both_and_nothing_else() {
+    let mut app = gameplay_app();
+    let player = spawn_target(&mut app, 1, EntityType::Player, PLAYER_POSITION);
+    let target = spawn_target(&mut app, 2, EntityType::Fly, Vec3::new(0.0, 150.0, 0.0));
+    let bystander = spawn_target(&mut app, 3, EntityType::Fly, Vec3::new(300.0, 150.0, 0.0));
+    let bullet = spawn_bullet(&mut app, BulletOwner::Player, Vec3::new(0.0, 50.0, 0.0));
+    let other_bullet = spawn_bullet(&mut app, BulletOwner::Player, Vec3::new(-300.0, 50.0, 0.0));
+
+    // 300 units per second covers the 70 units to the fly's edge in well under 30 steps
+    step(&mut app, 30);
+
+    assert!(!exists(&app, bullet));
+    assert!(!exists(&app, target));
+    assert!(exists(&app, player));
+    assert!(exists(&app, bystander));
+    assert!(exists(&app, other_bullet));
+}
+
+#[test]
+fn bullet_leaving_the_top_is_despawned() {
+    let mut app = gameplay_app();
+    let bullet = spawn_bullet(&mut app, BulletOwner::Player, Vec3::new(0.0, 380.0, 0.0));
+
+    step(&mut app, 2);
+    assert!(exists(&app, bullet));
+
+    // 20 units left to the top edge at 5 units per step
+    step(&mut app, 10);
+    assert!(!exists(&app, bullet));
+}
+
+#[test]
+fn player_is_never_despawned_by_its_own_bullet() {
+    let mut app = gameplay_app();
+    let player = spawn_target(&mut app, 1, EntityType::Player, PLAYER_POSITION);
+    // Fired from right on top of the ship, overlapping it for several steps
+    let bullet = spawn_bullet(&mut app, BulletOwner::Player, PLAYER_POSITION);
+
+    step(&mut app, 10);
+
+    assert!(exists(&app, player));
+    assert!(exists(&app, bullet));
+    assert!(app.world().resource::<Events<PlayerHit>>().is_empty());
+}
+
+#[test]
+fn out_of_bounds_culling_ignores_the_container() {
+    let mut app = gameplay_app();
+    let play_field = *app.world().resource::<PlayField>();
+    let outside = Vec3::new(0.0, play_field.height, 0.0);
+
+    let container = app
+        .world_mut()
+        .spawn((
+            OutlineContainer {
+                width: play_field.width,
+                height: play_field.height,
+            },
+            Transform::from_translation(outside),
+        ))
+        .id();
+    let stray = app
+        .world_mut()
+        .spawn((DespawnOutOfBounds, Transform::from_translation(outside)))
+        .id();
+
+    step(&mut app, 1);
+
+    assert!(exists(&app, container));
+    assert!(!exists(&app, stray));
+}
+
+#[test]
+fn bullet_overlapping_a_fly_is_resolved_in_one_frame() {
+    let mut app = gameplay_app();
+    let play_field = *app.world().resource::<PlayField>();
+    // Right at the top edge: the bullet is already outside, but still overlaps
+    // the fly, so the hit has to be resolved before the culling removes it
+    let top = play_field.height / 2.0;
+    let fly = spawn_target(&mut app, 1, EntityType::Fly, Vec3::new(0.0, top - 10.0, 0.0));
+    let bullet = spawn_bullet(&mut app, BulletOwner::Player, Vec3::new(0.0, top + 5.0, 0.0));
+
+    step(&mut app, 1);
+
+    assert!(!exists(&app, fly));
+    assert!(!exists(&app, bullet));
+    assert_eq!(app.world().resource::<Events<EnemyKilled>>().len(), 1);
+}